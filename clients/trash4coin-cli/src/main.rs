@@ -0,0 +1,290 @@
+//! Admin CLI for operating a trash4coin deployment.
+//!
+//! Every write command signs with `--keypair` as the config authority and sends to
+//! `--url`. With `--dry-run` the transaction is built and printed (base64 bincode)
+//! instead of being sent.
+
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use trash::{CurveMode, NFTConfig, RewardLeg};
+use trash4coin_client::{accounts, instructions, pda};
+
+#[derive(Parser)]
+#[command(name = "trash4coin", version, about)]
+struct Cli {
+    /// RPC endpoint
+    #[arg(
+        long,
+        short = 'u',
+        env = "TRASH4COIN_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    /// Authority keypair file (defaults to ~/.config/solana/id.json)
+    #[arg(long, short = 'k', env = "TRASH4COIN_KEYPAIR")]
+    keypair: Option<String>,
+
+    /// Which of the authority's configs to operate on
+    #[arg(long, default_value_t = 1)]
+    config_id: u64,
+
+    /// Operate on the config created before config ids existed, ignoring --config-id
+    #[arg(long)]
+    legacy: bool,
+
+    /// Print the serialized transaction instead of sending it
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the NFT config
+    Initialize {
+        #[arg(long, default_value_t = 10)]
+        max_nft_types: u8,
+    },
+    /// Register a new NFT type
+    AddNftType {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long)]
+        uri: String,
+    },
+    /// Set the first reward amount of an NFT type
+    SetReward {
+        #[arg(long)]
+        type_index: u8,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Change the reward amount of an NFT type
+    EditReward {
+        #[arg(long)]
+        type_index: u8,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Create the redeemable mint and mint the initial supply into the vault
+    CreateRedeemableToken {
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Mint more redeemable tokens into the authority's reward vault
+    FundVault {
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Upgrade an NFT config account in an older layout to the current one
+    MigrateConfig,
+    /// Print the NFT config and vault balances
+    Show,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let authority = load_keypair(cli.keypair.as_deref())?;
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+    let nft_config = if cli.legacy {
+        pda::legacy_nft_config(&authority.pubkey()).0
+    } else {
+        pda::nft_config(&authority.pubkey(), cli.config_id).0
+    };
+    let signer = authority.pubkey();
+
+    let ix = match cli.command {
+        Command::Initialize { max_nft_types } => {
+            instructions::initialize(&signer, cli.config_id, max_nft_types)
+        }
+        Command::AddNftType { name, symbol, uri } => {
+            instructions::add_nft_type(&signer, &nft_config, name, symbol, uri)
+        }
+        Command::SetReward { type_index, amount } => {
+            instructions::set_reward_amount(&signer, &nft_config, type_index, amount)
+        }
+        Command::EditReward { type_index, amount } => {
+            instructions::edit_reward_amount(&signer, &nft_config, type_index, amount)
+        }
+        Command::CreateRedeemableToken { amount } => {
+            instructions::create_redeemable_token(&signer, &nft_config, amount)
+        }
+        Command::FundVault { amount } => {
+            instructions::increase_token_supply(&signer, &nft_config, amount)
+        }
+        Command::MigrateConfig if cli.legacy => instructions::migrate_legacy_config(&signer),
+        Command::MigrateConfig => instructions::migrate_config(&signer, cli.config_id),
+        Command::Show => return show(&rpc, &signer, &nft_config),
+    };
+
+    if cli.dry_run {
+        return print_transaction(ix, &authority);
+    }
+
+    let blockhash = rpc.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&signer), &[&authority], blockhash);
+    let signature = rpc
+        .send_and_confirm_transaction(&tx)
+        .context("transaction failed")?;
+    println!("{signature}");
+
+    Ok(())
+}
+
+fn load_keypair(path: Option<&str>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path.to_string(),
+        None => {
+            let home = std::env::var("HOME").context("HOME is not set; pass --keypair")?;
+            format!("{home}/.config/solana/id.json")
+        }
+    };
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => format!("{}/{rest}", std::env::var("HOME").unwrap_or_default()),
+        None => path,
+    };
+
+    match read_keypair_file(&path) {
+        Ok(keypair) => Ok(keypair),
+        Err(err) => bail!("failed to read keypair {path}: {err}"),
+    }
+}
+
+// Dry runs never touch the network, so the transaction is signed over a zero blockhash
+fn print_transaction(ix: Instruction, authority: &Keypair) -> Result<()> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        Hash::default(),
+    );
+
+    for (index, meta) in tx.message.account_keys.iter().enumerate() {
+        let writable = if tx.message.is_writable(index) {
+            "writable"
+        } else {
+            "readonly"
+        };
+        let signer = if tx.message.is_signer(index) {
+            ", signer"
+        } else {
+            ""
+        };
+        println!("account {index}: {meta} ({writable}{signer})");
+    }
+    println!("{}", STANDARD.encode(bincode::serialize(&tx)?));
+
+    Ok(())
+}
+
+fn show(rpc: &RpcClient, authority: &Pubkey, nft_config: &Pubkey) -> Result<()> {
+    let account = rpc
+        .get_account(nft_config)
+        .with_context(|| format!("NFT config {nft_config} not found"))?;
+    let config: NFTConfig = accounts::decode_nft_config(&account.data)?;
+
+    println!("NFT config {nft_config}");
+    println!("  authority:        {}", config.authority);
+    println!("  config id:        {}", config.config_id);
+    println!(
+        "  nft types:        {}/{}",
+        config.nft_type_count, config.max_nft_types
+    );
+    println!(
+        "  epoch limits:     {} per user, {} total (epoch {}, {} rewarded)",
+        config.max_reward_per_user_per_epoch,
+        config.max_reward_per_epoch,
+        config.current_epoch,
+        config.epoch_rewarded
+    );
+    for (tier, tier_config) in ["bronze", "silver", "gold"].iter().zip(config.tiers.iter()) {
+        println!(
+            "  {tier:<6} tier:      {} units, +{} bps",
+            tier_config.units_threshold, tier_config.boost_bps
+        );
+    }
+    println!(
+        "  referrals:        {} bps, cap {}",
+        config.referral_bps, config.referral_cap
+    );
+    println!(
+        "  streak bonus:     {} bps/day, max {} bps",
+        config.streak_bonus_bps_per_day, config.max_streak_bonus_bps
+    );
+
+    for (index, nft_type) in config.nft_types().iter().enumerate() {
+        println!(
+            "  type {index}: {} ({}) {}",
+            nft_type.name(),
+            nft_type.symbol(),
+            nft_type.uri()
+        );
+        match nft_type.reward_amount() {
+            Some(amount) => println!("    reward:         {amount}"),
+            None => println!("    reward:         unset"),
+        }
+        for leg in &nft_type.reward_legs() {
+            match leg {
+                RewardLeg::Token { mint, amount } => {
+                    println!("    reward leg:     {amount} of {mint}")
+                }
+                RewardLeg::Lamports { amount } => println!("    reward leg:     {amount} lamports"),
+            }
+        }
+        if let Some(vesting) = &nft_type.vesting() {
+            println!(
+                "    vesting:        >= {}, cliff {}s, duration {}s",
+                vesting.min_reward, vesting.cliff_seconds, vesting.duration_seconds
+            );
+        }
+        if nft_type.staking_reward_rate > 0 {
+            println!(
+                "    staking rate:   {}/unit/s",
+                nft_type.staking_reward_rate
+            );
+        }
+        if let Some(curve) = &nft_type.reward_curve() {
+            let mode = match curve.mode {
+                CurveMode::Linear => "linear",
+                CurveMode::Exponential => "exponential",
+            };
+            println!(
+                "    reward curve:   {mode}, reference {}, floor {} bps, exponent {}",
+                curve.reference_balance, curve.min_multiplier_bps, curve.exponent
+            );
+        }
+    }
+
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+    let vault = pda::associated_token_account(authority, &redeemable_mint);
+    println!("Redeemable mint {redeemable_mint}");
+    match rpc.get_token_supply(&redeemable_mint) {
+        Ok(supply) => println!("  supply:           {}", supply.ui_amount_string),
+        Err(_) => println!("  not created"),
+    }
+    match rpc.get_token_account_balance(&vault) {
+        Ok(balance) => println!("  vault {vault}: {}", balance.ui_amount_string),
+        Err(_) => println!("  vault {vault}: not created"),
+    }
+
+    let lamport_vault = pda::lamport_vault(nft_config).0;
+    println!(
+        "Lamport vault {lamport_vault}: {} lamports",
+        rpc.get_balance(&lamport_vault).unwrap_or(0)
+    );
+
+    Ok(())
+}
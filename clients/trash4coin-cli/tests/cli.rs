@@ -0,0 +1,226 @@
+//! Runs the `trash4coin` binary with `--dry-run` and checks the transaction it prints
+//! against the client SDK's instruction builders.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{write_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use trash4coin_client::{instructions, pda};
+
+// An RPC endpoint nothing listens on, so a command that reaches the network fails fast
+const UNREACHABLE_URL: &str = "http://127.0.0.1:1";
+
+struct Authority {
+    keypair: Keypair,
+    path: PathBuf,
+}
+
+impl Authority {
+    fn new(name: &str) -> Self {
+        let keypair = Keypair::new();
+        let path =
+            env::temp_dir().join(format!("trash4coin-cli-{name}-{}.json", std::process::id()));
+        write_keypair_file(&keypair, &path).unwrap();
+        Authority { keypair, path }
+    }
+
+    fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_trash4coin"))
+            .arg("--keypair")
+            .arg(&self.path)
+            .args(["--url", UNREACHABLE_URL])
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    // Run a write command with --dry-run and check the printed accounts and transaction
+    // are those of `expected`, signed by the authority
+    fn assert_dry_run(&self, args: &[&str], expected: Instruction) {
+        let output = self.run(&[&["--dry-run"], args].concat());
+        assert!(
+            output.status.success(),
+            "{args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let mut lines: Vec<&str> = stdout.lines().collect();
+        let encoded = lines.pop().expect("no transaction printed");
+
+        let tx: Transaction = bincode::deserialize(&STANDARD.decode(encoded).unwrap()).unwrap();
+        tx.verify().unwrap();
+        assert_eq!(tx.message.account_keys[0], self.pubkey());
+        assert_eq!(tx.message.instructions.len(), 1);
+        let ix = &tx.message.instructions[0];
+        assert_eq!(
+            tx.message.account_keys[ix.program_id_index as usize],
+            expected.program_id
+        );
+        let accounts: Vec<Pubkey> = ix
+            .accounts
+            .iter()
+            .map(|&index| tx.message.account_keys[index as usize])
+            .collect();
+        let expected_accounts: Vec<Pubkey> =
+            expected.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(accounts, expected_accounts, "{args:?} accounts");
+        assert_eq!(ix.data, expected.data, "{args:?} instruction data");
+
+        // The printed flags are the builder's, with the authority paying
+        let mut flags: BTreeMap<Pubkey, (bool, bool)> = BTreeMap::new();
+        flags.insert(self.pubkey(), (true, true));
+        flags.insert(expected.program_id, (false, false));
+        for meta in &expected.accounts {
+            let entry = flags.entry(meta.pubkey).or_default();
+            entry.0 |= meta.is_writable;
+            entry.1 |= meta.is_signer;
+        }
+        let printed: BTreeMap<Pubkey, (bool, bool)> =
+            lines.iter().map(|line| parse_account_line(line)).collect();
+        assert_eq!(printed, flags, "{args:?} account flags");
+    }
+}
+
+impl Drop for Authority {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// "account 0: <key> (writable, signer)"
+fn parse_account_line(line: &str) -> (Pubkey, (bool, bool)) {
+    let (_, rest) = line.split_once(": ").unwrap();
+    let (key, flags) = rest.split_once(" (").unwrap();
+    let flags = flags.trim_end_matches(')');
+    (
+        key.parse().unwrap(),
+        (flags.starts_with("writable"), flags.ends_with(", signer")),
+    )
+}
+
+#[test]
+fn initialize_dry_run() {
+    let authority = Authority::new("initialize");
+    authority.assert_dry_run(
+        &["--config-id", "3", "initialize", "--max-nft-types", "5"],
+        instructions::initialize(&authority.pubkey(), 3, 5),
+    );
+}
+
+#[test]
+fn add_nft_type_dry_run() {
+    let authority = Authority::new("add-nft-type");
+    let nft_config = pda::nft_config(&authority.pubkey(), 1).0;
+    authority.assert_dry_run(
+        &[
+            "add-nft-type",
+            "--name",
+            "Can",
+            "--symbol",
+            "CAN",
+            "--uri",
+            "https://example.com/can.json",
+        ],
+        instructions::add_nft_type(
+            &authority.pubkey(),
+            &nft_config,
+            "Can".to_string(),
+            "CAN".to_string(),
+            "https://example.com/can.json".to_string(),
+        ),
+    );
+}
+
+#[test]
+fn set_reward_dry_run() {
+    let authority = Authority::new("set-reward");
+    let nft_config = pda::nft_config(&authority.pubkey(), 2).0;
+    authority.assert_dry_run(
+        &[
+            "--config-id",
+            "2",
+            "set-reward",
+            "--type-index",
+            "1",
+            "--amount",
+            "100",
+        ],
+        instructions::set_reward_amount(&authority.pubkey(), &nft_config, 1, 100),
+    );
+}
+
+#[test]
+fn edit_reward_dry_run() {
+    let authority = Authority::new("edit-reward");
+    let nft_config = pda::legacy_nft_config(&authority.pubkey()).0;
+    authority.assert_dry_run(
+        &[
+            "--legacy",
+            "edit-reward",
+            "--type-index",
+            "0",
+            "--amount",
+            "250",
+        ],
+        instructions::edit_reward_amount(&authority.pubkey(), &nft_config, 0, 250),
+    );
+}
+
+#[test]
+fn create_redeemable_token_dry_run() {
+    let authority = Authority::new("create-redeemable-token");
+    let nft_config = pda::nft_config(&authority.pubkey(), 1).0;
+    authority.assert_dry_run(
+        &["create-redeemable-token", "--amount", "1000000"],
+        instructions::create_redeemable_token(&authority.pubkey(), &nft_config, 1_000_000),
+    );
+}
+
+#[test]
+fn fund_vault_dry_run() {
+    let authority = Authority::new("fund-vault");
+    let nft_config = pda::nft_config(&authority.pubkey(), 1).0;
+    authority.assert_dry_run(
+        &["fund-vault", "--amount", "500"],
+        instructions::increase_token_supply(&authority.pubkey(), &nft_config, 500),
+    );
+}
+
+#[test]
+fn migrate_config_dry_run() {
+    let authority = Authority::new("migrate-config");
+    authority.assert_dry_run(
+        &["--config-id", "4", "migrate-config"],
+        instructions::migrate_config(&authority.pubkey(), 4),
+    );
+    authority.assert_dry_run(
+        &["--legacy", "migrate-config"],
+        instructions::migrate_legacy_config(&authority.pubkey()),
+    );
+}
+
+#[test]
+fn show_reports_a_missing_config() {
+    // show only reads, so --dry-run has nothing to print and it goes to the network
+    let authority = Authority::new("show");
+    let nft_config = pda::nft_config(&authority.pubkey(), 1).0;
+    let output = authority.run(&["--dry-run", "show"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("NFT config {nft_config} not found")),
+        "{stderr}"
+    );
+}
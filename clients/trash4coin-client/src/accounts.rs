@@ -0,0 +1,63 @@
+//! Decoders for accounts owned by the trash4coin program.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator, Result};
+use bytemuck::Zeroable;
+use trash::{
+    Achievement, AchievementClaim, Campaign, NFTConfig, NFTConfigV1, SponsorPool, StakePosition,
+    UserRecord, VestingPosition,
+};
+
+/// Decode any program account from its raw data, checking the discriminator.
+pub fn decode<T: AccountDeserialize>(mut data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut data)
+}
+
+/// Decode an NFT config in either layout; a v1 account that has not been through
+/// `migrate_config` yet is upgraded in memory.
+///
+/// The account is told apart by its size, the same way `migrate_config` does it.
+pub fn decode_nft_config(data: &[u8]) -> Result<NFTConfig> {
+    if !data.starts_with(&NFTConfig::DISCRIMINATOR) {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+
+    match data.len() {
+        len if len == NFTConfigV1::space() => {
+            let mut config = NFTConfig::zeroed();
+            config.copy_from_v1(&NFTConfigV1::deserialize(&mut &data[8..])?);
+            Ok(config)
+        }
+        // RPC buffers carry no alignment guarantee, so the zero-copy layout is copied out
+        len if len == NFTConfig::space() => Ok(bytemuck::pod_read_unaligned(&data[8..])),
+        _ => Err(ErrorCode::AccountDidNotDeserialize.into()),
+    }
+}
+
+pub fn decode_user_record(data: &[u8]) -> Result<UserRecord> {
+    decode(data)
+}
+
+pub fn decode_campaign(data: &[u8]) -> Result<Campaign> {
+    decode(data)
+}
+
+pub fn decode_sponsor_pool(data: &[u8]) -> Result<SponsorPool> {
+    decode(data)
+}
+
+pub fn decode_vesting_position(data: &[u8]) -> Result<VestingPosition> {
+    decode(data)
+}
+
+pub fn decode_stake_position(data: &[u8]) -> Result<StakePosition> {
+    decode(data)
+}
+
+pub fn decode_achievement(data: &[u8]) -> Result<Achievement> {
+    decode(data)
+}
+
+pub fn decode_achievement_claim(data: &[u8]) -> Result<AchievementClaim> {
+    decode(data)
+}
@@ -0,0 +1,160 @@
+//! Parsing of program events from transactions.
+//!
+//! Events are emitted with `emit_cpi!`: the program invokes itself, signed by the
+//! [`event_authority`](crate::pda::event_authority) PDA, with instruction data made of
+//! Anchor's event tag, the 8-byte event discriminator and the Borsh-encoded event. Those
+//! inner instructions survive log truncation; decode them with [`decode_cpi_data`].
+//!
+//! The read-only `UserInfoFetched` is still written with `emit!` as
+//! `Program data: <base64>` (discriminator and event), which [`parse_logs`] decodes.
+//!
+//! Every event starts with an [`EventHeader`]: the config it belongs to, the signer that
+//! acted, its sequence number within the config, and the slot and Unix time it was
+//! emitted at.
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use trash::*;
+
+const PROGRAM_LOG: &str = "Program log: ";
+const PROGRAM_DATA: &str = "Program data: ";
+
+macro_rules! program_events {
+    ($($event:ident),* $(,)?) => {
+        /// Every event the program can emit.
+        pub enum ProgramEvent {
+            $($event($event),)*
+        }
+
+        impl ProgramEvent {
+            /// The name of every event, as returned by [`ProgramEvent::name`].
+            pub const NAMES: &'static [&'static str] = &[$(stringify!($event),)*];
+
+            /// Decode an event from its raw bytes (discriminator followed by the event).
+            pub fn decode(data: &[u8]) -> Option<Self> {
+                if data.len() < 8 {
+                    return None;
+                }
+                let (discriminator, mut payload) = data.split_at(8);
+
+                $(
+                    if discriminator == $event::DISCRIMINATOR {
+                        return $event::deserialize(&mut payload).ok().map(ProgramEvent::$event);
+                    }
+                )*
+
+                None
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(ProgramEvent::$event(_) => stringify!($event),)*
+                }
+            }
+
+            /// The context every event carries.
+            pub fn header(&self) -> &EventHeader {
+                match self {
+                    $(ProgramEvent::$event(e) => &e.header,)*
+                }
+            }
+        }
+    };
+}
+
+program_events!(
+    InitializeEvent,
+    AddNFTTypeEvent,
+    SetRewardAmountEvent,
+    MintNFTEvent,
+    MintNFTBatchEvent,
+    CreateRedeemableTokenEvent,
+    RedeemAndBurnNFTEvent,
+    IncreaseTokenSupplyEvent,
+    IncreaseMaxNFTTypesEvent,
+    EditRewardAmountEvent,
+    UserInfoFetched,
+    CreateCampaignEvent,
+    CreateSponsorPoolEvent,
+    SetSponsorPayoutRateEvent,
+    SponsorDepositEvent,
+    SponsorWithdrawEvent,
+    SetRewardLegsEvent,
+    CreateRewardVaultEvent,
+    RedeemAndBurnNFTMultiEvent,
+    RedeemAndBurnNFTBatchEvent,
+    SetRedemptionLimitsEvent,
+    SetVestingScheduleEvent,
+    ClaimVestedEvent,
+    SetStakingRewardRateEvent,
+    StakeNFTEvent,
+    UnstakeNFTEvent,
+    ClaimStakingRewardsEvent,
+    SetRewardCurveEvent,
+    SetTierThresholdsEvent,
+    TierUpgraded,
+    CreateAchievementEvent,
+    ClaimAchievementEvent,
+    SetReferralConfigEvent,
+    SetReferrerEvent,
+    SetStreakBonusEvent,
+    StreakExtended,
+    StreakBroken,
+    MigrateConfigEvent,
+);
+
+/// Decode the data of an inner instruction the program invoked on itself.
+///
+/// Returns `None` for instructions that are not event CPIs.
+pub fn decode_cpi_data(data: &[u8]) -> Option<ProgramEvent> {
+    ProgramEvent::decode(data.strip_prefix(EVENT_IX_TAG_LE.as_slice())?)
+}
+
+/// Decode the base64 payload of a single `Program data:` log line.
+pub fn decode_log_data(encoded: &str) -> Option<ProgramEvent> {
+    let data = STANDARD.decode(encoded.trim()).ok()?;
+    ProgramEvent::decode(&data)
+}
+
+/// Parse all program events from a transaction's log messages.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Vec<ProgramEvent> {
+    program_data(logs)
+        .into_iter()
+        .filter_map(decode_log_data)
+        .collect()
+}
+
+/// The base64 payloads of every `Program data:` line logged by the program.
+///
+/// Invocations are tracked so that only data logged while the trash4coin
+/// program is executing is returned; CPIs into other programs are skipped.
+pub fn program_data<S: AsRef<str>>(logs: &[S]) -> Vec<&str> {
+    let program_id = trash::ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut payloads = Vec::new();
+
+    for line in logs.iter().map(AsRef::as_ref) {
+        if let Some(data) = line.strip_prefix(PROGRAM_DATA) {
+            if stack.last() == Some(&program_id.as_str()) {
+                payloads.push(data);
+            }
+        } else if line.starts_with(PROGRAM_LOG) {
+            continue;
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            let mut parts = rest.split_whitespace();
+            let (Some(id), Some(action)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            match action {
+                "invoke" => stack.push(id),
+                "success" | "failed:" => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    payloads
+}
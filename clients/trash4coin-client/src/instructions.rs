@@ -0,0 +1,944 @@
+//! Typed instruction builders for every trash4coin instruction.
+//!
+//! Builders take the signers and the few keys that cannot be derived, and
+//! derive every PDA and associated token account themselves.
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::metadata::mpl_token_metadata;
+use trash::{accounts, instruction};
+use trash::{
+    AchievementKind, MintBatchEntry, RewardCurve, RewardLeg, TierConfig, VestingSchedule,
+    TIER_COUNT,
+};
+
+use crate::pda;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: trash::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize(authority: &Pubkey, config_id: u64, max_nft_types: u8) -> Instruction {
+    build(
+        accounts::Initialize {
+            authority: *authority,
+            nft_config: pda::nft_config(authority, config_id).0,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::Initialize {
+            config_id,
+            max_nft_types,
+        },
+    )
+}
+
+pub fn add_nft_type(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    build(
+        accounts::AddNFTType {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::AddNftType { name, symbol, uri },
+    )
+}
+
+pub fn set_reward_amount(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_type_index: u8,
+    reward_amount: u64,
+) -> Instruction {
+    build(
+        accounts::SetRewardAmount {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetRewardAmount {
+            nft_type_index,
+            reward_amount,
+        },
+    )
+}
+
+pub fn edit_reward_amount(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_type_index: u8,
+    new_reward_amount: u64,
+) -> Instruction {
+    build(
+        accounts::EditRewardAmount {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::EditRewardAmount {
+            nft_type_index,
+            new_reward_amount,
+        },
+    )
+}
+
+pub fn increase_token_supply(authority: &Pubkey, nft_config: &Pubkey, amount: u64) -> Instruction {
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+
+    build(
+        accounts::IncreaseTokenSupply {
+            authority: *authority,
+            redeemable_mint,
+            redeemable_token_account: get_associated_token_address(authority, &redeemable_mint),
+            nft_config: *nft_config,
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::IncreaseTokenSupply { amount },
+    )
+}
+
+pub fn increase_max_nft_types(authority: &Pubkey, nft_config: &Pubkey, new_max: u8) -> Instruction {
+    build(
+        accounts::IncreaseMaxNFTTypes {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::IncreaseMaxNftTypes { new_max },
+    )
+}
+
+pub fn mint_nft(
+    minter: &Pubkey,
+    nft_config: &Pubkey,
+    nft_type_index: u8,
+    amount: u64,
+) -> Instruction {
+    let mint = pda::nft_mint(nft_config, nft_type_index).0;
+
+    build(
+        accounts::MintNFT {
+            minter: *minter,
+            mint,
+            token_account: get_associated_token_address(minter, &mint),
+            metadata: pda::metadata(&mint).0,
+            master_edition: pda::master_edition(&mint).0,
+            nft_config: *nft_config,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+            user_record: pda::user_record(nft_config, minter).0,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::MintNft {
+            nft_type_index,
+            amount,
+        },
+    )
+}
+
+/// Builds `mint_nft_batch`. Each entry gets its type's mint, the minter's associated
+/// token account and the mint's metadata and master edition accounts appended as
+/// remaining accounts.
+pub fn mint_nft_batch(
+    minter: &Pubkey,
+    nft_config: &Pubkey,
+    entries: Vec<MintBatchEntry>,
+) -> Instruction {
+    let remaining_accounts: Vec<AccountMeta> = entries
+        .iter()
+        .flat_map(|entry| {
+            let mint = pda::nft_mint(nft_config, entry.nft_type_index).0;
+            [
+                AccountMeta::new(mint, false),
+                AccountMeta::new(get_associated_token_address(minter, &mint), false),
+                AccountMeta::new(pda::metadata(&mint).0, false),
+                AccountMeta::new(pda::master_edition(&mint).0, false),
+            ]
+        })
+        .collect();
+
+    let mut ix = build(
+        accounts::MintNFTBatch {
+            minter: *minter,
+            nft_config: *nft_config,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+            user_record: pda::user_record(nft_config, minter).0,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::MintNftBatch { entries },
+    );
+    ix.accounts.extend(remaining_accounts);
+
+    ix
+}
+
+pub fn create_redeemable_token(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+
+    build(
+        accounts::CreateRedeemableToken {
+            authority: *authority,
+            redeemable_mint,
+            redeemable_token_account: get_associated_token_address(authority, &redeemable_mint),
+            nft_config: *nft_config,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::CreateRedeemableToken { amount },
+    )
+}
+
+/// Optional accounts for [`redeem_and_burn_nft`].
+#[derive(Debug, Clone, Default)]
+pub struct RedeemExtras {
+    /// Vest the reward into the user's next vesting position (its id is the
+    /// user record's `vesting_positions` counter).
+    pub vesting_position_id: Option<u64>,
+    /// Burn from the user's stake escrow instead of their wallet.
+    pub from_stake: bool,
+    /// Apply the campaign with this id.
+    pub campaign_id: Option<u64>,
+    /// Also pay out from the sponsor pool of `(sponsor, sponsor_mint)`.
+    pub sponsor: Option<(Pubkey, Pubkey)>,
+    /// The user's registered referrer.
+    pub referrer: Option<Pubkey>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn redeem_and_burn_nft(
+    user: &Pubkey,
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_mint: &Pubkey,
+    amount: u64,
+    min_reward_out: u64,
+    expected_reward_rate: Option<u64>,
+    extras: &RedeemExtras,
+) -> Instruction {
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+    let stake_position = pda::stake_position(nft_config, user, nft_mint).0;
+    let sponsor_pool = extras
+        .sponsor
+        .map(|(sponsor, mint)| (pda::sponsor_pool(nft_config, &sponsor, &mint).0, mint));
+
+    build(
+        accounts::RedeemAndBurnNFT {
+            user: *user,
+            nft_mint: *nft_mint,
+            nft_token_account: get_associated_token_address(user, nft_mint),
+            redeemable_mint,
+            redeemable_token_account: get_associated_token_address(authority, &redeemable_mint),
+            user_redeemable_token_account: get_associated_token_address(user, &redeemable_mint),
+            authority: *authority,
+            metadata: pda::metadata(nft_mint).0,
+            nft_config: *nft_config,
+            user_record: pda::user_record(nft_config, user).0,
+            vesting_position: extras
+                .vesting_position_id
+                .map(|id| pda::vesting_position(nft_config, user, id).0),
+            vesting_vault: extras
+                .vesting_position_id
+                .map(|id| pda::vesting_vault(nft_config, user, id).0),
+            stake_position: extras.from_stake.then_some(stake_position),
+            stake_escrow: extras
+                .from_stake
+                .then(|| pda::stake_escrow(&stake_position).0),
+            campaign: extras.campaign_id.map(|id| pda::campaign(nft_config, id).0),
+            sponsor_pool: sponsor_pool.map(|(pool, _)| pool),
+            sponsor_vault: sponsor_pool.map(|(pool, _)| pda::sponsor_vault(&pool).0),
+            user_sponsor_token_account: sponsor_pool
+                .map(|(_, mint)| get_associated_token_address(user, &mint)),
+            referrer_record: extras
+                .referrer
+                .map(|referrer| pda::user_record(nft_config, &referrer).0),
+            referrer_token_account: extras
+                .referrer
+                .map(|referrer| get_associated_token_address(&referrer, &redeemable_mint)),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::RedeemAndBurnNft {
+            amount,
+            min_reward_out,
+            expected_reward_rate,
+        },
+    )
+}
+
+/// Optional accounts for [`redeem_and_burn_batch`].
+#[derive(Debug, Clone, Default)]
+pub struct RedeemBatchExtras {
+    /// Apply the campaign with this id to every type in the batch it covers.
+    pub campaign_id: Option<u64>,
+    /// Also pay out from the sponsor pool of `(sponsor, sponsor_mint)`.
+    pub sponsor: Option<(Pubkey, Pubkey)>,
+    /// The user's registered referrer.
+    pub referrer: Option<Pubkey>,
+}
+
+/// Builds `redeem_and_burn_batch`, which redeems the user's whole balance of each of
+/// `nft_mints`. Each mint is appended as remaining accounts with the user's associated
+/// token account and its metadata. `extras.referrer` must be set once the user has one.
+pub fn redeem_and_burn_batch(
+    user: &Pubkey,
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_mints: &[Pubkey],
+    min_reward_out: u64,
+    extras: &RedeemBatchExtras,
+) -> Instruction {
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+    let sponsor_pool = extras
+        .sponsor
+        .map(|(sponsor, mint)| (pda::sponsor_pool(nft_config, &sponsor, &mint).0, mint));
+
+    let mut ix = build(
+        accounts::RedeemAndBurnBatch {
+            user: *user,
+            redeemable_mint,
+            redeemable_token_account: get_associated_token_address(authority, &redeemable_mint),
+            user_redeemable_token_account: get_associated_token_address(user, &redeemable_mint),
+            authority: *authority,
+            nft_config: *nft_config,
+            user_record: pda::user_record(nft_config, user).0,
+            campaign: extras.campaign_id.map(|id| pda::campaign(nft_config, id).0),
+            sponsor_pool: sponsor_pool.map(|(pool, _)| pool),
+            sponsor_vault: sponsor_pool.map(|(pool, _)| pda::sponsor_vault(&pool).0),
+            user_sponsor_token_account: sponsor_pool
+                .map(|(_, mint)| get_associated_token_address(user, &mint)),
+            referrer_record: extras
+                .referrer
+                .map(|referrer| pda::user_record(nft_config, &referrer).0),
+            referrer_token_account: extras
+                .referrer
+                .map(|referrer| get_associated_token_address(&referrer, &redeemable_mint)),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::RedeemAndBurnBatch { min_reward_out },
+    );
+
+    for mint in nft_mints {
+        ix.accounts.push(AccountMeta::new(*mint, false));
+        ix.accounts.push(AccountMeta::new(
+            get_associated_token_address(user, mint),
+            false,
+        ));
+        ix.accounts
+            .push(AccountMeta::new_readonly(pda::metadata(mint).0, false));
+    }
+
+    ix
+}
+
+/// Builds `redeem_and_burn_nft_multi`. `min_reward_out` holds the smallest acceptable
+/// payout of each reward leg, in leg order. `token_leg_mints` lists the mints of the
+/// type's token reward legs in order; each gets its reward vault and the user's
+/// associated token account appended as remaining accounts.
+pub fn redeem_and_burn_nft_multi(
+    user: &Pubkey,
+    nft_config: &Pubkey,
+    nft_mint: &Pubkey,
+    amount: u64,
+    min_reward_out: Vec<u64>,
+    token_leg_mints: &[Pubkey],
+) -> Instruction {
+    let mut ix = build(
+        accounts::RedeemAndBurnNFTMulti {
+            user: *user,
+            nft_mint: *nft_mint,
+            nft_token_account: get_associated_token_address(user, nft_mint),
+            metadata: pda::metadata(nft_mint).0,
+            nft_config: *nft_config,
+            lamport_vault: pda::lamport_vault(nft_config).0,
+            redeemable_mint: pda::redeemable_mint(nft_config).0,
+            user_record: pda::user_record(nft_config, user).0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::RedeemAndBurnNftMulti {
+            amount,
+            min_reward_out,
+        },
+    );
+
+    for mint in token_leg_mints {
+        ix.accounts.push(AccountMeta::new(
+            pda::reward_vault(nft_config, mint).0,
+            false,
+        ));
+        ix.accounts.push(AccountMeta::new(
+            get_associated_token_address(user, mint),
+            false,
+        ));
+    }
+
+    ix
+}
+
+pub fn set_reward_curve(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_type_index: u8,
+    reward_curve: Option<RewardCurve>,
+) -> Instruction {
+    build(
+        accounts::SetRewardCurve {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetRewardCurve {
+            nft_type_index,
+            reward_curve,
+        },
+    )
+}
+
+pub fn quote_redemption(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_type_index: u8,
+) -> Instruction {
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+
+    build(
+        accounts::QuoteRedemption {
+            nft_config: *nft_config,
+            redeemable_mint,
+            redeemable_token_account: get_associated_token_address(authority, &redeemable_mint),
+        },
+        instruction::QuoteRedemption { nft_type_index },
+    )
+}
+
+pub fn get_user_info(user: &Pubkey, nft_config: &Pubkey, nft_mint: &Pubkey) -> Instruction {
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+
+    build(
+        accounts::GetUserInfo {
+            user: *user,
+            nft_config: *nft_config,
+            nft_mint: *nft_mint,
+            nft_token_account: get_associated_token_address(user, nft_mint),
+            redeemable_mint,
+            user_redeemable_token_account: get_associated_token_address(user, &redeemable_mint),
+        },
+        instruction::GetUserInfo {},
+    )
+}
+
+pub fn set_vesting_schedule(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_type_index: u8,
+    vesting: Option<VestingSchedule>,
+) -> Instruction {
+    build(
+        accounts::SetVestingSchedule {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetVestingSchedule {
+            nft_type_index,
+            vesting,
+        },
+    )
+}
+
+pub fn claim_vested(user: &Pubkey, nft_config: &Pubkey, position_id: u64) -> Instruction {
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+
+    build(
+        accounts::ClaimVested {
+            user: *user,
+            nft_config: *nft_config,
+            vesting_position: pda::vesting_position(nft_config, user, position_id).0,
+            vesting_vault: pda::vesting_vault(nft_config, user, position_id).0,
+            user_redeemable_token_account: get_associated_token_address(user, &redeemable_mint),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::ClaimVested {},
+    )
+}
+
+pub fn set_staking_reward_rate(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_type_index: u8,
+    staking_reward_rate: u64,
+) -> Instruction {
+    build(
+        accounts::SetStakingRewardRate {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetStakingRewardRate {
+            nft_type_index,
+            staking_reward_rate,
+        },
+    )
+}
+
+pub fn stake_nft(
+    user: &Pubkey,
+    nft_config: &Pubkey,
+    nft_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let stake_position = pda::stake_position(nft_config, user, nft_mint).0;
+
+    build(
+        accounts::StakeNFT {
+            user: *user,
+            nft_mint: *nft_mint,
+            nft_token_account: get_associated_token_address(user, nft_mint),
+            metadata: pda::metadata(nft_mint).0,
+            nft_config: *nft_config,
+            stake_position,
+            stake_escrow: pda::stake_escrow(&stake_position).0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::StakeNft { amount },
+    )
+}
+
+pub fn unstake_nft(
+    user: &Pubkey,
+    nft_config: &Pubkey,
+    nft_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let stake_position = pda::stake_position(nft_config, user, nft_mint).0;
+
+    build(
+        accounts::UnstakeNFT {
+            user: *user,
+            nft_config: *nft_config,
+            stake_position,
+            stake_escrow: pda::stake_escrow(&stake_position).0,
+            nft_token_account: get_associated_token_address(user, nft_mint),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::UnstakeNft { amount },
+    )
+}
+
+pub fn claim_staking_rewards(user: &Pubkey, nft_config: &Pubkey, nft_mint: &Pubkey) -> Instruction {
+    let redeemable_mint = pda::redeemable_mint(nft_config).0;
+
+    build(
+        accounts::ClaimStakingRewards {
+            user: *user,
+            nft_config: *nft_config,
+            stake_position: pda::stake_position(nft_config, user, nft_mint).0,
+            redeemable_mint,
+            reward_vault: pda::reward_vault(nft_config, &redeemable_mint).0,
+            user_redeemable_token_account: get_associated_token_address(user, &redeemable_mint),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::ClaimStakingRewards {},
+    )
+}
+
+pub fn set_tier_thresholds(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    tiers: [TierConfig; TIER_COUNT],
+) -> Instruction {
+    build(
+        accounts::SetTierThresholds {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetTierThresholds { tiers },
+    )
+}
+
+pub fn create_achievement(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    achievement_id: u64,
+    kind: AchievementKind,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    build(
+        accounts::CreateAchievement {
+            authority: *authority,
+            nft_config: *nft_config,
+            achievement: pda::achievement(nft_config, achievement_id).0,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::CreateAchievement {
+            achievement_id,
+            kind,
+            name,
+            symbol,
+            uri,
+        },
+    )
+}
+
+pub fn claim_achievement(user: &Pubkey, nft_config: &Pubkey, achievement_id: u64) -> Instruction {
+    let achievement = pda::achievement(nft_config, achievement_id).0;
+    let badge_mint = pda::badge_mint(&achievement, user).0;
+
+    build(
+        accounts::ClaimAchievement {
+            user: *user,
+            nft_config: *nft_config,
+            achievement,
+            user_record: pda::user_record(nft_config, user).0,
+            achievement_claim: pda::achievement_claim(&achievement, user).0,
+            badge_mint,
+            badge_token_account: get_associated_token_address(user, &badge_mint),
+            metadata: pda::metadata(&badge_mint).0,
+            master_edition: pda::master_edition(&badge_mint).0,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::ClaimAchievement {},
+    )
+}
+
+pub fn set_referral_config(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    referral_bps: u16,
+    referral_cap: u64,
+) -> Instruction {
+    build(
+        accounts::SetReferralConfig {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetReferralConfig {
+            referral_bps,
+            referral_cap,
+        },
+    )
+}
+
+pub fn set_referrer(user: &Pubkey, nft_config: &Pubkey, referrer: &Pubkey) -> Instruction {
+    build(
+        accounts::SetReferrer {
+            user: *user,
+            nft_config: *nft_config,
+            user_record: pda::user_record(nft_config, user).0,
+            referrer_record: pda::user_record(nft_config, referrer).0,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetReferrer {
+            referrer: *referrer,
+        },
+    )
+}
+
+pub fn set_streak_bonus(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    streak_bonus_bps_per_day: u16,
+    max_streak_bonus_bps: u16,
+) -> Instruction {
+    build(
+        accounts::SetStreakBonus {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetStreakBonus {
+            streak_bonus_bps_per_day,
+            max_streak_bonus_bps,
+        },
+    )
+}
+
+pub fn set_redemption_limits(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    max_reward_per_user_per_epoch: u64,
+    max_reward_per_epoch: u64,
+) -> Instruction {
+    build(
+        accounts::SetRedemptionLimits {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetRedemptionLimits {
+            max_reward_per_user_per_epoch,
+            max_reward_per_epoch,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_campaign(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    campaign_id: u64,
+    start_ts: i64,
+    end_ts: i64,
+    eligible_type_indices: Vec<u8>,
+    multiplier_bps: u16,
+    budget: u64,
+) -> Instruction {
+    build(
+        accounts::CreateCampaign {
+            authority: *authority,
+            nft_config: *nft_config,
+            campaign: pda::campaign(nft_config, campaign_id).0,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::CreateCampaign {
+            campaign_id,
+            start_ts,
+            end_ts,
+            eligible_type_indices,
+            multiplier_bps,
+            budget,
+        },
+    )
+}
+
+pub fn set_reward_legs(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    nft_type_index: u8,
+    reward_legs: Vec<RewardLeg>,
+) -> Instruction {
+    build(
+        accounts::SetRewardLegs {
+            authority: *authority,
+            nft_config: *nft_config,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetRewardLegs {
+            nft_type_index,
+            reward_legs,
+        },
+    )
+}
+
+pub fn create_reward_vault(
+    authority: &Pubkey,
+    nft_config: &Pubkey,
+    reward_mint: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CreateRewardVault {
+            authority: *authority,
+            nft_config: *nft_config,
+            reward_mint: *reward_mint,
+            reward_vault: pda::reward_vault(nft_config, reward_mint).0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::CreateRewardVault {},
+    )
+}
+
+pub fn create_sponsor_pool(
+    sponsor: &Pubkey,
+    nft_config: &Pubkey,
+    sponsor_mint: &Pubkey,
+) -> Instruction {
+    let sponsor_pool = pda::sponsor_pool(nft_config, sponsor, sponsor_mint).0;
+
+    build(
+        accounts::CreateSponsorPool {
+            sponsor: *sponsor,
+            nft_config: *nft_config,
+            sponsor_mint: *sponsor_mint,
+            sponsor_pool,
+            sponsor_vault: pda::sponsor_vault(&sponsor_pool).0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::CreateSponsorPool {},
+    )
+}
+
+pub fn set_sponsor_payout_rate(
+    sponsor: &Pubkey,
+    nft_config: &Pubkey,
+    sponsor_mint: &Pubkey,
+    nft_type_index: u8,
+    payout_rate: u64,
+) -> Instruction {
+    build(
+        accounts::SetSponsorPayoutRate {
+            sponsor: *sponsor,
+            nft_config: *nft_config,
+            sponsor_pool: pda::sponsor_pool(nft_config, sponsor, sponsor_mint).0,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::SetSponsorPayoutRate {
+            nft_type_index,
+            payout_rate,
+        },
+    )
+}
+
+pub fn deposit_sponsor_tokens(
+    sponsor: &Pubkey,
+    nft_config: &Pubkey,
+    sponsor_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let sponsor_pool = pda::sponsor_pool(nft_config, sponsor, sponsor_mint).0;
+
+    build(
+        accounts::DepositSponsorTokens {
+            sponsor: *sponsor,
+            nft_config: *nft_config,
+            sponsor_pool,
+            sponsor_vault: pda::sponsor_vault(&sponsor_pool).0,
+            sponsor_token_account: get_associated_token_address(sponsor, sponsor_mint),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::DepositSponsorTokens { amount },
+    )
+}
+
+pub fn withdraw_sponsor_tokens(
+    sponsor: &Pubkey,
+    nft_config: &Pubkey,
+    sponsor_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let sponsor_pool = pda::sponsor_pool(nft_config, sponsor, sponsor_mint).0;
+
+    build(
+        accounts::WithdrawSponsorTokens {
+            sponsor: *sponsor,
+            nft_config: *nft_config,
+            sponsor_pool,
+            sponsor_vault: pda::sponsor_vault(&sponsor_pool).0,
+            sponsor_token_account: get_associated_token_address(sponsor, sponsor_mint),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::WithdrawSponsorTokens { amount },
+    )
+}
+
+pub fn migrate_config(authority: &Pubkey, config_id: u64) -> Instruction {
+    build(
+        accounts::MigrateConfig {
+            authority: *authority,
+            nft_config: pda::nft_config(authority, config_id).0,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::MigrateConfig { config_id },
+    )
+}
+
+/// Builds `migrate_config` for a config from before `config_id` existed, which lives at
+/// [`pda::legacy_nft_config`] and is migrated as config 0.
+pub fn migrate_legacy_config(authority: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateConfig {
+            authority: *authority,
+            nft_config: pda::legacy_nft_config(authority).0,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority().0,
+            program: trash::ID,
+        },
+        instruction::MigrateConfig { config_id: 0 },
+    )
+}
@@ -0,0 +1,19 @@
+//! Client SDK for the trash4coin program.
+//!
+//! - [`pda`] derives every program address the program seeds.
+//! - [`instructions`] builds typed [`Instruction`]s for every program instruction.
+//! - [`accounts`] decodes program-owned accounts such as [`NFTConfig`].
+//! - [`events`] decodes program events from event CPIs and transaction logs.
+
+pub mod accounts;
+pub mod events;
+pub mod instructions;
+pub mod pda;
+
+pub use anchor_lang::solana_program::instruction::Instruction;
+pub use anchor_lang::solana_program::pubkey::Pubkey;
+pub use trash::{
+    Achievement, AchievementClaim, AchievementKind, Campaign, CurveMode, MintBatchEntry, NFTConfig,
+    NFTType, NFTTypeData, RecyclingTier, RedeemBatchEntry, RewardCurve, RewardLeg, SponsorPool,
+    StakePosition, TierConfig, UserRecord, VestingPosition, VestingSchedule, ID as PROGRAM_ID,
+};
@@ -0,0 +1,190 @@
+//! Program-derived addresses used by the trash4coin program.
+//!
+//! Every helper returns the address together with its bump seed.
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::metadata::mpl_token_metadata;
+use trash::{
+    ACHIEVEMENT_CLAIM_SEED, ACHIEVEMENT_SEED, BADGE_MINT_SEED, CAMPAIGN_SEED, LAMPORT_VAULT_SEED,
+    NFT_CONFIG_SEED, NFT_MINT_SEED, REDEEMABLE_MINT_SEED, REWARD_VAULT_SEED, SPONSOR_POOL_SEED,
+    SPONSOR_VAULT_SEED, STAKE_ESCROW_SEED, STAKE_POSITION_SEED, USER_RECORD_SEED,
+    VESTING_POSITION_SEED, VESTING_VAULT_SEED,
+};
+
+pub const METADATA_SEED: &[u8] = b"metadata";
+pub const EDITION_SEED: &[u8] = b"edition";
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+pub fn nft_config(authority: &Pubkey, config_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            NFT_CONFIG_SEED,
+            authority.as_ref(),
+            &config_id.to_le_bytes(),
+        ],
+        &trash::ID,
+    )
+}
+
+/// Address of a config created before configs were namespaced by `config_id`. After
+/// `migrate_config` it works like any other config and keeps this address.
+pub fn legacy_nft_config(authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[NFT_CONFIG_SEED, authority.as_ref()], &trash::ID)
+}
+
+/// Signs the self-CPI that carries each event (see Anchor's `#[event_cpi]`).
+pub fn event_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &trash::ID)
+}
+
+pub fn redeemable_mint(nft_config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REDEEMABLE_MINT_SEED, nft_config.as_ref()], &trash::ID)
+}
+
+pub fn nft_mint(nft_config: &Pubkey, nft_type_index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[NFT_MINT_SEED, nft_config.as_ref(), &[nft_type_index]],
+        &trash::ID,
+    )
+}
+
+/// Metaplex metadata account for `mint`.
+pub fn metadata(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            METADATA_SEED,
+            mpl_token_metadata::ID.as_ref(),
+            mint.as_ref(),
+        ],
+        &mpl_token_metadata::ID,
+    )
+}
+
+/// Metaplex master edition account for `mint`.
+pub fn master_edition(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            METADATA_SEED,
+            mpl_token_metadata::ID.as_ref(),
+            mint.as_ref(),
+            EDITION_SEED,
+        ],
+        &mpl_token_metadata::ID,
+    )
+}
+
+pub fn user_record(nft_config: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[USER_RECORD_SEED, nft_config.as_ref(), user.as_ref()],
+        &trash::ID,
+    )
+}
+
+pub fn campaign(nft_config: &Pubkey, campaign_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            CAMPAIGN_SEED,
+            nft_config.as_ref(),
+            &campaign_id.to_le_bytes(),
+        ],
+        &trash::ID,
+    )
+}
+
+pub fn sponsor_pool(nft_config: &Pubkey, sponsor: &Pubkey, sponsor_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SPONSOR_POOL_SEED,
+            nft_config.as_ref(),
+            sponsor.as_ref(),
+            sponsor_mint.as_ref(),
+        ],
+        &trash::ID,
+    )
+}
+
+pub fn sponsor_vault(sponsor_pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SPONSOR_VAULT_SEED, sponsor_pool.as_ref()], &trash::ID)
+}
+
+pub fn reward_vault(nft_config: &Pubkey, reward_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REWARD_VAULT_SEED, nft_config.as_ref(), reward_mint.as_ref()],
+        &trash::ID,
+    )
+}
+
+pub fn lamport_vault(nft_config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LAMPORT_VAULT_SEED, nft_config.as_ref()], &trash::ID)
+}
+
+pub fn vesting_position(nft_config: &Pubkey, user: &Pubkey, position_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            VESTING_POSITION_SEED,
+            nft_config.as_ref(),
+            user.as_ref(),
+            &position_id.to_le_bytes(),
+        ],
+        &trash::ID,
+    )
+}
+
+pub fn vesting_vault(nft_config: &Pubkey, user: &Pubkey, position_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            VESTING_VAULT_SEED,
+            nft_config.as_ref(),
+            user.as_ref(),
+            &position_id.to_le_bytes(),
+        ],
+        &trash::ID,
+    )
+}
+
+pub fn stake_position(nft_config: &Pubkey, user: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            STAKE_POSITION_SEED,
+            nft_config.as_ref(),
+            user.as_ref(),
+            nft_mint.as_ref(),
+        ],
+        &trash::ID,
+    )
+}
+
+pub fn stake_escrow(stake_position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_ESCROW_SEED, stake_position.as_ref()], &trash::ID)
+}
+
+pub fn achievement(nft_config: &Pubkey, achievement_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            ACHIEVEMENT_SEED,
+            nft_config.as_ref(),
+            &achievement_id.to_le_bytes(),
+        ],
+        &trash::ID,
+    )
+}
+
+pub fn achievement_claim(achievement: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ACHIEVEMENT_CLAIM_SEED, achievement.as_ref(), user.as_ref()],
+        &trash::ID,
+    )
+}
+
+pub fn badge_mint(achievement: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[BADGE_MINT_SEED, achievement.as_ref(), user.as_ref()],
+        &trash::ID,
+    )
+}
+
+/// Associated token account of `owner` for `mint`.
+pub fn associated_token_account(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, mint)
+}
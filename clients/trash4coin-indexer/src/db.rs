@@ -0,0 +1,385 @@
+//! SQLite storage for indexed events.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::{params, Connection, OptionalExtension};
+use solana_sdk::pubkey::Pubkey;
+use trash::{RedeemAndBurnNFTBatchEvent, RedeemAndBurnNFTMultiEvent, RewardLeg};
+use trash4coin_client::events::ProgramEvent;
+use trash4coin_client::pda;
+
+use crate::source::RecordedTransaction;
+
+// `data` keeps the base64 event (discriminator and payload) so it can be re-decoded with
+// the client SDK.
+// `nft_config`, `actor` and `seq` come from the header every event carries; `user` is the
+// actor of events about a user's own activity.
+// Batch redemptions get one row per redeemed mint, numbered by `entry_index`; every other
+// event has a single row with `entry_index` 0.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    nft_config TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    user TEXT,
+    nft_mint TEXT,
+    nft_type_index INTEGER,
+    amount INTEGER,
+    reward INTEGER,
+    data TEXT NOT NULL,
+    entry_index INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (signature, event_index, entry_index)
+);
+CREATE INDEX IF NOT EXISTS events_by_slot ON events (slot);
+CREATE INDEX IF NOT EXISTS events_by_user ON events (user);
+CREATE INDEX IF NOT EXISTS events_by_mint ON events (nft_mint);
+CREATE INDEX IF NOT EXISTS events_by_config ON events (nft_config, seq);
+
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    slot INTEGER NOT NULL,
+    signature TEXT NOT NULL
+);
+
+-- Views hold no data, so they are recreated to pick up changes to their definitions
+DROP VIEW IF EXISTS user_stats;
+CREATE VIEW user_stats AS
+SELECT
+    nft_config,
+    user,
+    SUM(CASE WHEN name = 'MintNFTEvent' THEN amount ELSE 0 END) AS units_minted,
+    SUM(CASE WHEN name LIKE 'RedeemAndBurnNFT%' THEN amount ELSE 0 END) AS units_redeemed,
+    COUNT(DISTINCT CASE WHEN name LIKE 'RedeemAndBurnNFT%'
+        THEN signature || ':' || event_index END) AS redemptions,
+    SUM(CASE WHEN name LIKE 'RedeemAndBurnNFT%' THEN reward ELSE 0 END) AS rewarded,
+    SUM(CASE WHEN name = 'ClaimStakingRewardsEvent' THEN reward ELSE 0 END) AS staking_rewards,
+    SUM(CASE WHEN name = 'ClaimVestedEvent' THEN amount ELSE 0 END) AS vested_claimed,
+    MIN(slot) AS first_slot,
+    MAX(slot) AS last_slot
+FROM events
+WHERE user IS NOT NULL
+GROUP BY nft_config, user;
+
+DROP VIEW IF EXISTS type_stats;
+CREATE VIEW type_stats AS
+SELECT
+    nft_config,
+    nft_type_index,
+    SUM(CASE WHEN name = 'MintNFTEvent' THEN amount ELSE 0 END) AS units_minted,
+    SUM(CASE WHEN name LIKE 'RedeemAndBurnNFT%' THEN amount ELSE 0 END) AS units_redeemed,
+    SUM(CASE WHEN name LIKE 'RedeemAndBurnNFT%' THEN reward ELSE 0 END) AS rewarded,
+    COUNT(DISTINCT CASE WHEN name LIKE 'RedeemAndBurnNFT%' THEN user END) AS redeemers,
+    (SELECT r.reward FROM events r
+     WHERE r.name IN ('SetRewardAmountEvent', 'EditRewardAmountEvent')
+       AND r.nft_config = t.nft_config
+       AND r.nft_type_index = t.nft_type_index
+     ORDER BY r.seq DESC LIMIT 1) AS reward_amount
+FROM events t
+WHERE nft_type_index IS NOT NULL
+GROUP BY nft_config, nft_type_index;
+";
+
+// Bumped whenever the events table changes shape. Older databases are dropped and
+// re-indexed from the start, since their rows can't be rebuilt without the transactions
+const SCHEMA_VERSION: u32 = 1;
+
+/// The last indexed transaction.
+pub struct Cursor {
+    pub slot: u64,
+    pub signature: String,
+}
+
+pub struct Db {
+    conn: Connection,
+}
+
+impl Db {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn =
+            Connection::open(path).with_context(|| format!("failed to open database {path:?}"))?;
+        let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < SCHEMA_VERSION {
+            conn.execute_batch("DROP TABLE IF EXISTS events; DROP TABLE IF EXISTS cursor;")?;
+        }
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        Ok(Self { conn })
+    }
+
+    /// The underlying connection, for querying the tables and views.
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn cursor(&self) -> Result<Option<Cursor>> {
+        let cursor = self
+            .conn
+            .query_row(
+                "SELECT slot, signature FROM cursor WHERE id = 0",
+                [],
+                |row| {
+                    Ok(Cursor {
+                        slot: row.get(0)?,
+                        signature: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(cursor)
+    }
+
+    /// Store a transaction's events and move the cursor to it, atomically.
+    ///
+    /// Returns how many events were new; indexing the same transaction twice is a no-op.
+    pub fn index_transaction(&mut self, tx: &RecordedTransaction) -> Result<usize> {
+        let db_tx = self.conn.transaction()?;
+        let mut inserted = 0;
+
+        if !tx.failed {
+            let mut insert = db_tx.prepare(
+                "INSERT OR IGNORE INTO events (signature, event_index, slot, block_time,
+                     nft_config, seq, name, actor, user, nft_mint, nft_type_index, amount,
+                     reward, data, entry_index)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            )?;
+            for (event_index, data) in tx.events.iter().enumerate() {
+                // Events from other program versions are skipped rather than failing the run
+                let Some(event) = ProgramEvent::decode(data) else {
+                    continue;
+                };
+                let header = event.header();
+                let mut new_rows = 0;
+                for (entry_index, row) in event_rows(&event).into_iter().enumerate() {
+                    new_rows += insert.execute(params![
+                        tx.signature,
+                        event_index,
+                        tx.slot,
+                        tx.block_time,
+                        header.nft_config.to_string(),
+                        header.seq,
+                        event.name(),
+                        header.actor.to_string(),
+                        row.user.map(|key| key.to_string()),
+                        row.nft_mint.map(|key| key.to_string()),
+                        row.nft_type_index,
+                        row.amount,
+                        row.reward,
+                        STANDARD.encode(data),
+                        entry_index,
+                    ])?;
+                }
+                if new_rows > 0 {
+                    inserted += 1;
+                }
+            }
+        }
+
+        db_tx.execute(
+            "INSERT INTO cursor (id, slot, signature) VALUES (0, ?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET slot = excluded.slot, signature = excluded.signature",
+            params![tx.slot, tx.signature],
+        )?;
+        db_tx.commit()?;
+
+        Ok(inserted)
+    }
+}
+
+// The columns the views aggregate on; everything else stays in the raw payload
+#[derive(Default)]
+struct EventRow {
+    user: Option<Pubkey>,
+    nft_mint: Option<Pubkey>,
+    nft_type_index: Option<u8>,
+    amount: Option<u64>,
+    reward: Option<u64>,
+}
+
+// The rows an event is stored as: one per redeemed mint for batches, one otherwise
+fn event_rows(event: &ProgramEvent) -> Vec<EventRow> {
+    match event {
+        ProgramEvent::RedeemAndBurnNFTBatchEvent(e) => batch_rows(e),
+        event => vec![EventRow::from(event)],
+    }
+}
+
+// Each entry's row carries its share of the batch reward. The tier boost, streak bonus and
+// campaign bonus are only reported for the whole batch, so they are spread in proportion
+// to each entry's base reward, with the rounding left over going to the last entry
+fn batch_rows(e: &RedeemAndBurnNFTBatchEvent) -> Vec<EventRow> {
+    let base_total: u128 = e
+        .entries
+        .iter()
+        .map(|entry| entry.reward_amount as u128)
+        .sum();
+    let mut unassigned = e.reward_amount;
+
+    e.entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let reward = if i + 1 == e.entries.len() {
+                unassigned
+            } else {
+                (e.reward_amount as u128 * entry.reward_amount as u128)
+                    .checked_div(base_total)
+                    .unwrap_or(0) as u64
+            };
+            unassigned -= reward;
+
+            EventRow {
+                user: Some(e.header.actor),
+                nft_mint: Some(entry.nft_mint),
+                nft_type_index: Some(entry.nft_type_index),
+                amount: Some(entry.amount_burned),
+                reward: Some(reward),
+            }
+        })
+        .collect()
+}
+
+// Only the legs paid in the config's redeemable token count as reward, like the program's
+// lifetime and epoch totals
+fn multi_reward(e: &RedeemAndBurnNFTMultiEvent) -> u64 {
+    let redeemable_mint = pda::redeemable_mint(&e.header.nft_config).0;
+    e.payouts
+        .iter()
+        .map(|leg| match leg {
+            RewardLeg::Token { mint, amount } if *mint == redeemable_mint => *amount,
+            _ => 0,
+        })
+        .sum()
+}
+
+impl From<&ProgramEvent> for EventRow {
+    fn from(event: &ProgramEvent) -> Self {
+        let user = Some(event.header().actor);
+        match event {
+            ProgramEvent::MintNFTEvent(e) => EventRow {
+                user,
+                nft_mint: Some(e.mint),
+                nft_type_index: Some(e.nft_type_index),
+                amount: Some(e.amount),
+                ..EventRow::default()
+            },
+            // The per-type MintNFTEvents carry the mints; this only records how many there were
+            ProgramEvent::MintNFTBatchEvent(e) => EventRow {
+                user,
+                amount: Some(e.total_amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::RedeemAndBurnNFTEvent(e) => EventRow {
+                user,
+                nft_mint: Some(e.nft_mint),
+                nft_type_index: Some(e.nft_type_index),
+                amount: Some(e.amount_burned),
+                reward: Some(e.reward_amount),
+            },
+            ProgramEvent::RedeemAndBurnNFTMultiEvent(e) => EventRow {
+                user,
+                nft_mint: Some(e.nft_mint),
+                nft_type_index: Some(e.nft_type_index),
+                amount: Some(e.amount_burned),
+                reward: Some(multi_reward(e)),
+            },
+            ProgramEvent::StakeNFTEvent(e) => EventRow {
+                user,
+                nft_mint: Some(e.nft_mint),
+                nft_type_index: Some(e.nft_type_index),
+                amount: Some(e.amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::UnstakeNFTEvent(e) => EventRow {
+                user,
+                nft_mint: Some(e.nft_mint),
+                nft_type_index: Some(e.nft_type_index),
+                amount: Some(e.amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::UserInfoFetched(e) => EventRow {
+                user,
+                nft_mint: Some(e.nft_mint),
+                ..EventRow::default()
+            },
+            ProgramEvent::ClaimStakingRewardsEvent(e) => EventRow {
+                user,
+                nft_type_index: Some(e.nft_type_index),
+                reward: Some(e.amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::ClaimVestedEvent(e) => EventRow {
+                user,
+                amount: Some(e.amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::AddNFTTypeEvent(e) => EventRow {
+                nft_type_index: Some(e.nft_type_index),
+                ..EventRow::default()
+            },
+            ProgramEvent::SetRewardAmountEvent(e) => EventRow {
+                nft_type_index: Some(e.nft_type_index),
+                reward: Some(e.new_reward_amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::EditRewardAmountEvent(e) => EventRow {
+                nft_type_index: Some(e.nft_type_index),
+                reward: Some(e.new_reward_amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::SetSponsorPayoutRateEvent(e) => EventRow {
+                nft_type_index: Some(e.nft_type_index),
+                ..EventRow::default()
+            },
+            ProgramEvent::SetRewardLegsEvent(e) => EventRow {
+                nft_type_index: Some(e.nft_type_index),
+                ..EventRow::default()
+            },
+            ProgramEvent::SetVestingScheduleEvent(e) => EventRow {
+                nft_type_index: Some(e.nft_type_index),
+                ..EventRow::default()
+            },
+            ProgramEvent::SetStakingRewardRateEvent(e) => EventRow {
+                nft_type_index: Some(e.nft_type_index),
+                ..EventRow::default()
+            },
+            ProgramEvent::SetRewardCurveEvent(e) => EventRow {
+                nft_type_index: Some(e.nft_type_index),
+                ..EventRow::default()
+            },
+            ProgramEvent::CreateRedeemableTokenEvent(e) => EventRow {
+                amount: Some(e.amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::IncreaseTokenSupplyEvent(e) => EventRow {
+                amount: Some(e.amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::SponsorDepositEvent(e) => EventRow {
+                amount: Some(e.amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::SponsorWithdrawEvent(e) => EventRow {
+                amount: Some(e.amount),
+                ..EventRow::default()
+            },
+            ProgramEvent::TierUpgraded(_)
+            | ProgramEvent::ClaimAchievementEvent(_)
+            | ProgramEvent::SetReferrerEvent(_)
+            | ProgramEvent::StreakExtended(_)
+            | ProgramEvent::StreakBroken(_) => EventRow {
+                user,
+                ..EventRow::default()
+            },
+            _ => EventRow::default(),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! Storage and transaction sources behind the `trash4coin-indexer` binary.
+
+pub mod db;
+pub mod source;
+
+use anyhow::Result;
+
+use db::Db;
+use source::RecordedTransaction;
+
+/// What a call to [`index`] added.
+pub struct Indexed {
+    pub transactions: usize,
+    pub events: usize,
+}
+
+/// Index `transactions`, oldest first, skipping those before the stored cursor.
+pub fn index(db: &mut Db, transactions: &[RecordedTransaction]) -> Result<Indexed> {
+    // Slots before the cursor were fully indexed; the cursor's own slot may not have been,
+    // and re-inserting its events is a no-op
+    let resume_slot = db.cursor()?.map_or(0, |cursor| cursor.slot);
+
+    let mut indexed = Indexed {
+        transactions: 0,
+        events: 0,
+    };
+    for tx in transactions.iter().filter(|tx| tx.slot >= resume_slot) {
+        indexed.events += db.index_transaction(tx)?;
+        indexed.transactions += 1;
+    }
+
+    Ok(indexed)
+}
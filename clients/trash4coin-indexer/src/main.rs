@@ -0,0 +1,100 @@
+//! Indexes trash4coin program events into SQLite.
+//!
+//! Transactions are read oldest first, either from an RPC node (`rpc`) or from a JSON
+//! array of recorded `getTransaction` responses (`file`). Every event the program
+//! emitted, read from its event CPIs (or `Program data:` logs for events still written
+//! with `emit!`), is stored in the `events` table with its slot and signature, and the
+//! `user_stats` and `type_stats` views aggregate them per user and per NFT type.
+//!
+//! The last indexed transaction is kept as a cursor, so rerunning either command
+//! resumes from the last processed slot instead of starting over.
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use trash4coin_indexer::db::Db;
+use trash4coin_indexer::source::{self, RecordedTransaction};
+
+#[derive(Parser)]
+#[command(name = "trash4coin-indexer", version, about)]
+struct Cli {
+    /// SQLite database file, created if missing
+    #[arg(
+        long,
+        short = 'd',
+        env = "TRASH4COIN_INDEXER_DB",
+        default_value = "trash4coin.db"
+    )]
+    db: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index the program's transactions from an RPC node
+    Rpc {
+        /// RPC endpoint
+        #[arg(
+            long,
+            short = 'u',
+            env = "TRASH4COIN_RPC_URL",
+            default_value = "http://127.0.0.1:8899"
+        )]
+        url: String,
+
+        /// Keep polling for new transactions every this many seconds
+        #[arg(long)]
+        poll: Option<u64>,
+    },
+    /// Index recorded transactions from a JSON file of `getTransaction` responses
+    File { path: PathBuf },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut db = Db::open(&cli.db)?;
+
+    match cli.command {
+        Command::Rpc { url, poll } => {
+            let rpc = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+            loop {
+                let until = db.cursor()?.map(|cursor| cursor.signature);
+                let transactions = source::fetch_rpc(&rpc, until.as_deref())?;
+                index(&mut db, &transactions)?;
+
+                let Some(seconds) = poll else {
+                    break;
+                };
+                thread::sleep(Duration::from_secs(seconds));
+            }
+        }
+        Command::File { path } => {
+            let transactions = source::read_file(&path)?;
+            index(&mut db, &transactions)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn index(db: &mut Db, transactions: &[RecordedTransaction]) -> Result<()> {
+    let indexed = trash4coin_indexer::index(db, transactions)?;
+
+    match db.cursor()? {
+        Some(cursor) => println!(
+            "indexed {} transactions ({} new events), at slot {}",
+            indexed.transactions, indexed.events, cursor.slot
+        ),
+        None => println!("no transactions to index"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Transaction sources: an RPC node or a file of recorded transactions.
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::bs58;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiInstruction, UiMessage,
+    UiParsedInstruction, UiTransactionEncoding,
+};
+use trash4coin_client::events::program_data;
+
+// Largest page getSignaturesForAddress returns
+const SIGNATURE_PAGE: usize = 1_000;
+
+/// What the indexer needs from a confirmed transaction.
+pub struct RecordedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    // Raw events (discriminator and Borsh payload) in emission order
+    pub events: Vec<Vec<u8>>,
+    // Failed transactions still carry inner instructions and logs, but their events never happened
+    pub failed: bool,
+}
+
+impl TryFrom<EncodedConfirmedTransactionWithStatusMeta> for RecordedTransaction {
+    type Error = anyhow::Error;
+
+    fn try_from(tx: EncodedConfirmedTransactionWithStatusMeta) -> Result<Self> {
+        let (signature, mut account_keys) = match &tx.transaction.transaction {
+            EncodedTransaction::Json(ui) => {
+                let keys = match &ui.message {
+                    UiMessage::Raw(message) => message.account_keys.clone(),
+                    UiMessage::Parsed(message) => message
+                        .account_keys
+                        .iter()
+                        .map(|account| account.pubkey.clone())
+                        .collect(),
+                };
+                (ui.signatures.first().cloned(), keys)
+            }
+            EncodedTransaction::Accounts(list) => (list.signatures.first().cloned(), vec![]),
+            encoded => match encoded.decode() {
+                Some(decoded) => (
+                    decoded.signatures.first().map(ToString::to_string),
+                    decoded
+                        .message
+                        .static_account_keys()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                ),
+                None => (None, vec![]),
+            },
+        };
+        let Some(signature) = signature else {
+            bail!("transaction in slot {} has no signature", tx.slot);
+        };
+        let Some(meta) = tx.transaction.meta else {
+            bail!("transaction {signature} has no status meta");
+        };
+
+        // Inner instructions index into the static keys followed by any lookup table keys
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            account_keys.extend(loaded.writable.iter().cloned());
+            account_keys.extend(loaded.readonly.iter().cloned());
+        }
+        let program_id = trash::ID.to_string();
+
+        let mut events = vec![];
+        if let OptionSerializer::Some(inner) = &meta.inner_instructions {
+            for ix in inner.iter().flat_map(|inner| &inner.instructions) {
+                let (program, data) = match ix {
+                    UiInstruction::Compiled(ix) => {
+                        (account_keys.get(ix.program_id_index as usize), &ix.data)
+                    }
+                    UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(ix)) => {
+                        (Some(&ix.program_id), &ix.data)
+                    }
+                    UiInstruction::Parsed(UiParsedInstruction::Parsed(_)) => continue,
+                };
+                if program != Some(&program_id) {
+                    continue;
+                }
+                let data = bs58::decode(data)
+                    .into_vec()
+                    .with_context(|| format!("bad instruction data in {signature}"))?;
+                if let Some(event) = data.strip_prefix(EVENT_IX_TAG_LE.as_slice()) {
+                    events.push(event.to_vec());
+                }
+            }
+        }
+        // Events written with emit! instead of emit_cpi!
+        if let OptionSerializer::Some(logs) = &meta.log_messages {
+            for data in program_data(logs) {
+                if let Ok(event) = STANDARD.decode(data.trim()) {
+                    events.push(event);
+                }
+            }
+        }
+
+        Ok(Self {
+            signature,
+            slot: tx.slot,
+            block_time: tx.block_time,
+            events,
+            failed: meta.err.is_some(),
+        })
+    }
+}
+
+/// Read a JSON array of `getTransaction` responses, ordered by slot.
+pub fn read_file(path: &Path) -> Result<Vec<RecordedTransaction>> {
+    let json = fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+    let recorded: Vec<EncodedConfirmedTransactionWithStatusMeta> =
+        serde_json::from_str(&json).with_context(|| format!("failed to parse {path:?}"))?;
+
+    let mut transactions = recorded
+        .into_iter()
+        .map(RecordedTransaction::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    // Stable, so transactions recorded in block order keep it within a slot
+    transactions.sort_by_key(|tx| tx.slot);
+
+    Ok(transactions)
+}
+
+/// Fetch the program's transactions newer than `until`, oldest first.
+pub fn fetch_rpc(rpc: &RpcClient, until: Option<&str>) -> Result<Vec<RecordedTransaction>> {
+    let until = until.map(Signature::from_str).transpose()?;
+
+    // Signatures come back newest first, a page at a time
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: Some(SIGNATURE_PAGE),
+            commitment: Some(rpc.commitment()),
+        };
+        let page = rpc.get_signatures_for_address_with_config(&trash::ID, config)?;
+        let full = page.len() == SIGNATURE_PAGE;
+        before = match page.last() {
+            Some(status) => Some(Signature::from_str(&status.signature)?),
+            None => None,
+        };
+        signatures.extend(page);
+        if !full {
+            break;
+        }
+    }
+
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(rpc.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+    signatures
+        .iter()
+        .rev()
+        .map(|status| {
+            let signature = Signature::from_str(&status.signature)?;
+            let tx = rpc
+                .get_transaction_with_config(&signature, config)
+                .with_context(|| format!("failed to fetch transaction {signature}"))?;
+            RecordedTransaction::try_from(tx)
+        })
+        .collect()
+}
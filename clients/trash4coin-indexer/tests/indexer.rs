@@ -0,0 +1,769 @@
+//! Indexer tests: decoding recorded transactions, resuming from the cursor, and the
+//! aggregate views.
+//!
+//! `fixtures/transactions.json` holds one `getTransaction` response per event type. After
+//! an intended change to an event, rewrite it by running these tests with
+//! `INDEXER_FIXTURE_UPDATE=1`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::Event;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use solana_sdk::bs58;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use trash::*;
+use trash4coin_client::events::ProgramEvent;
+use trash4coin_client::pda;
+use trash4coin_indexer::db::Db;
+use trash4coin_indexer::index;
+use trash4coin_indexer::source::{self, RecordedTransaction};
+
+const FIXTURE_SLOT: u64 = 250_000_000;
+const FIXTURE_TIME: i64 = 1_700_000_000;
+
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+fn nft_config() -> Pubkey {
+    key(1)
+}
+
+fn authority() -> Pubkey {
+    key(2)
+}
+
+fn header(actor: Pubkey, seq: u64) -> EventHeader {
+    EventHeader {
+        nft_config: nft_config(),
+        actor,
+        seq,
+        slot: FIXTURE_SLOT + seq,
+        unix_timestamp: FIXTURE_TIME + seq as i64,
+    }
+}
+
+fn fixture_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/transactions.json")
+}
+
+// A fresh database file, so reopening it sees what the last run stored
+fn temp_db(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!(
+        "trash4coin-indexer-{name}-{}.db",
+        std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+// One event of every type, in the order of ProgramEvent::NAMES
+fn sample_events() -> Vec<Vec<u8>> {
+    let (admin, user, sponsor) = (authority(), key(3), key(4));
+    let mut seq = 0;
+    let mut next = |actor| {
+        seq += 1;
+        header(actor, seq - 1)
+    };
+    let redeemable_mint = pda::redeemable_mint(&nft_config()).0;
+
+    vec![
+        InitializeEvent {
+            header: next(admin),
+            config_id: 0,
+            max_nft_types: 10,
+        }
+        .data(),
+        AddNFTTypeEvent {
+            header: next(admin),
+            nft_type_index: 0,
+            name: "Can".to_string(),
+            symbol: "CAN".to_string(),
+            uri: "https://example.com/can.json".to_string(),
+        }
+        .data(),
+        SetRewardAmountEvent {
+            header: next(admin),
+            nft_type_index: 0,
+            old_reward_amount: None,
+            new_reward_amount: 100,
+        }
+        .data(),
+        MintNFTEvent {
+            header: next(user),
+            mint: key(10),
+            nft_type_index: 0,
+            amount: 1,
+        }
+        .data(),
+        MintNFTBatchEvent {
+            header: next(user),
+            entries: vec![MintBatchEntry { nft_type_index: 1 }],
+            total_amount: 1,
+        }
+        .data(),
+        CreateRedeemableTokenEvent {
+            header: next(admin),
+            mint: redeemable_mint,
+            amount: 1_000_000,
+        }
+        .data(),
+        RedeemAndBurnNFTEvent {
+            header: next(user),
+            nft_mint: key(10),
+            nft_type_index: 0,
+            amount_burned: 1,
+            reward_amount: 115,
+            tier_boost: 5,
+            streak_bonus: 10,
+            campaign_bonus: 0,
+            sponsor_reward: 0,
+            referral_reward: 0,
+            vesting_position: None,
+        }
+        .data(),
+        IncreaseTokenSupplyEvent {
+            header: next(admin),
+            mint: redeemable_mint,
+            amount: 500,
+        }
+        .data(),
+        IncreaseMaxNFTTypesEvent {
+            header: next(admin),
+            old_max: 10,
+            new_max: 20,
+        }
+        .data(),
+        EditRewardAmountEvent {
+            header: next(admin),
+            nft_type_index: 0,
+            old_reward_amount: Some(100),
+            new_reward_amount: 120,
+        }
+        .data(),
+        UserInfoFetched {
+            header: next(user),
+            nft_mint: key(10),
+            nft_balance: 0,
+            redeemable_balance: 115,
+        }
+        .data(),
+        CreateCampaignEvent {
+            header: next(admin),
+            campaign: key(20),
+            campaign_id: 7,
+            start_ts: FIXTURE_TIME,
+            end_ts: FIXTURE_TIME + 86_400,
+            eligible_type_indices: vec![0, 1],
+            multiplier_bps: 15_000,
+            budget: 1_000,
+        }
+        .data(),
+        CreateSponsorPoolEvent {
+            header: next(sponsor),
+            sponsor_pool: key(21),
+            mint: key(22),
+        }
+        .data(),
+        SetSponsorPayoutRateEvent {
+            header: next(sponsor),
+            sponsor_pool: key(21),
+            nft_type_index: 0,
+            payout_rate: 5,
+        }
+        .data(),
+        SponsorDepositEvent {
+            header: next(sponsor),
+            sponsor_pool: key(21),
+            amount: 500,
+        }
+        .data(),
+        SponsorWithdrawEvent {
+            header: next(sponsor),
+            sponsor_pool: key(21),
+            amount: 100,
+        }
+        .data(),
+        SetRewardLegsEvent {
+            header: next(admin),
+            nft_type_index: 1,
+            reward_legs: vec![
+                RewardLeg::Token {
+                    mint: redeemable_mint,
+                    amount: 50,
+                },
+                RewardLeg::Lamports { amount: 1_000 },
+            ],
+        }
+        .data(),
+        CreateRewardVaultEvent {
+            header: next(admin),
+            vault: key(23),
+            mint: redeemable_mint,
+        }
+        .data(),
+        RedeemAndBurnNFTMultiEvent {
+            header: next(user),
+            nft_mint: key(11),
+            nft_type_index: 1,
+            amount_burned: 1,
+            payouts: vec![
+                RewardLeg::Token {
+                    mint: redeemable_mint,
+                    amount: 50,
+                },
+                RewardLeg::Lamports { amount: 1_000 },
+            ],
+        }
+        .data(),
+        RedeemAndBurnNFTBatchEvent {
+            header: next(user),
+            entries: vec![
+                RedeemBatchEntry {
+                    nft_mint: key(10),
+                    nft_type_index: 0,
+                    amount_burned: 2,
+                    reward_amount: 240,
+                },
+                RedeemBatchEntry {
+                    nft_mint: key(12),
+                    nft_type_index: 2,
+                    amount_burned: 1,
+                    reward_amount: 60,
+                },
+            ],
+            amount_burned: 3,
+            reward_amount: 330,
+            tier_boost: 15,
+            streak_bonus: 15,
+            campaign_bonus: 0,
+            sponsor_reward: 0,
+            referral_reward: 0,
+        }
+        .data(),
+        SetRedemptionLimitsEvent {
+            header: next(admin),
+            max_reward_per_user_per_epoch: 10_000,
+            max_reward_per_epoch: 100_000,
+        }
+        .data(),
+        SetVestingScheduleEvent {
+            header: next(admin),
+            nft_type_index: 2,
+            vesting: Some(VestingSchedule {
+                min_reward: 1_000,
+                cliff_seconds: 3_600,
+                duration_seconds: 86_400,
+            }),
+        }
+        .data(),
+        ClaimVestedEvent {
+            header: next(user),
+            vesting_position: key(24),
+            amount: 250,
+            remaining: 750,
+        }
+        .data(),
+        SetStakingRewardRateEvent {
+            header: next(admin),
+            nft_type_index: 0,
+            staking_reward_rate: 2,
+        }
+        .data(),
+        StakeNFTEvent {
+            header: next(user),
+            nft_mint: key(10),
+            nft_type_index: 0,
+            amount: 3,
+            total_staked: 3,
+        }
+        .data(),
+        UnstakeNFTEvent {
+            header: next(user),
+            nft_mint: key(10),
+            nft_type_index: 0,
+            amount: 1,
+            total_staked: 2,
+        }
+        .data(),
+        ClaimStakingRewardsEvent {
+            header: next(user),
+            stake_position: key(25),
+            nft_type_index: 0,
+            amount: 60,
+        }
+        .data(),
+        SetRewardCurveEvent {
+            header: next(admin),
+            nft_type_index: 0,
+            reward_curve: Some(RewardCurve {
+                mode: CurveMode::Linear,
+                reference_balance: 1_000_000,
+                min_multiplier_bps: 5_000,
+                exponent: 1,
+            }),
+        }
+        .data(),
+        SetTierThresholdsEvent {
+            header: next(admin),
+            tiers: vec![
+                TierConfig {
+                    units_threshold: 10,
+                    boost_bps: 500,
+                },
+                TierConfig {
+                    units_threshold: 50,
+                    boost_bps: 1_000,
+                },
+                TierConfig {
+                    units_threshold: 100,
+                    boost_bps: 2_000,
+                },
+            ],
+        }
+        .data(),
+        TierUpgraded {
+            header: next(user),
+            old_tier: RecyclingTier::None,
+            new_tier: RecyclingTier::Bronze,
+            lifetime_units_redeemed: 10,
+        }
+        .data(),
+        CreateAchievementEvent {
+            header: next(admin),
+            achievement: key(26),
+            achievement_id: 1,
+            kind: AchievementKind::LifetimeUnits { threshold: 10 },
+            name: "First ten".to_string(),
+        }
+        .data(),
+        ClaimAchievementEvent {
+            header: next(user),
+            achievement: key(26),
+            badge_mint: key(27),
+        }
+        .data(),
+        SetReferralConfigEvent {
+            header: next(admin),
+            referral_bps: 1_000,
+            referral_cap: 5_000,
+        }
+        .data(),
+        SetReferrerEvent {
+            header: next(user),
+            referrer: key(5),
+        }
+        .data(),
+        SetStreakBonusEvent {
+            header: next(admin),
+            streak_bonus_bps_per_day: 100,
+            max_streak_bonus_bps: 1_000,
+        }
+        .data(),
+        StreakExtended {
+            header: next(user),
+            current_streak: 2,
+        }
+        .data(),
+        StreakBroken {
+            header: next(user),
+            previous_streak: 2,
+        }
+        .data(),
+        MigrateConfigEvent {
+            header: next(admin),
+            old_version: 1,
+            new_version: NFT_CONFIG_VERSION,
+        }
+        .data(),
+    ]
+}
+
+// A getTransaction response for one instruction of the program emitting `event`, through
+// an event CPI, or as a `Program data:` log for the events still written with emit!
+fn recorded_transaction(index: usize, event: &[u8]) -> Value {
+    let program_id = trash::ID.to_string();
+    let signature = Signature::from([index as u8 + 1; 64]).to_string();
+    let slot = FIXTURE_SLOT + index as u64;
+    let logged = ProgramEvent::decode(event).is_some_and(|e| e.name() == "UserInfoFetched");
+
+    let (inner_instructions, log_messages) = if logged {
+        (
+            json!([]),
+            json!([
+                format!("Program {program_id} invoke [1]"),
+                format!("Program data: {}", STANDARD.encode(event)),
+                format!("Program {program_id} consumed 5000 of 200000 compute units"),
+                format!("Program {program_id} success"),
+            ]),
+        )
+    } else {
+        let data = [EVENT_IX_TAG_LE.as_slice(), event].concat();
+        (
+            json!([{
+                "index": 0,
+                "instructions": [{
+                    "programIdIndex": 1,
+                    "accounts": [2],
+                    "data": bs58::encode(data).into_string(),
+                    "stackHeight": 2,
+                }],
+            }]),
+            json!([
+                format!("Program {program_id} invoke [1]"),
+                format!("Program {program_id} invoke [2]"),
+                format!("Program {program_id} consumed 2000 of 190000 compute units"),
+                format!("Program {program_id} success"),
+                format!("Program {program_id} consumed 12000 of 200000 compute units"),
+                format!("Program {program_id} success"),
+            ]),
+        )
+    };
+
+    json!({
+        "slot": slot,
+        "blockTime": FIXTURE_TIME + index as i64,
+        "transaction": {
+            "signatures": [signature],
+            "message": {
+                "header": {
+                    "numRequiredSignatures": 1,
+                    "numReadonlySignedAccounts": 0,
+                    "numReadonlyUnsignedAccounts": 2,
+                },
+                "accountKeys": [
+                    authority().to_string(),
+                    program_id,
+                    pda::event_authority().0.to_string(),
+                ],
+                "recentBlockhash": key(9).to_string(),
+                "instructions": [{
+                    "programIdIndex": 1,
+                    "accounts": [0, 2],
+                    "data": bs58::encode([0u8; 8]).into_string(),
+                    "stackHeight": null,
+                }],
+            },
+        },
+        "meta": {
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [1_000_000_000u64, 1, 0],
+            "postBalances": [999_995_000u64, 1, 0],
+            "innerInstructions": inner_instructions,
+            "logMessages": log_messages,
+            "preTokenBalances": [],
+            "postTokenBalances": [],
+            "rewards": [],
+            "loadedAddresses": { "writable": [], "readonly": [] },
+            "computeUnitsConsumed": 12000,
+        },
+        "version": "legacy",
+    })
+}
+
+fn transaction(slot: u64, signature: u8, events: Vec<Vec<u8>>) -> RecordedTransaction {
+    RecordedTransaction {
+        signature: Signature::from([signature; 64]).to_string(),
+        slot,
+        block_time: Some(FIXTURE_TIME),
+        events,
+        failed: false,
+    }
+}
+
+fn count(db: &Db, sql: &str) -> u64 {
+    db.conn().query_row(sql, [], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn fixture_decodes_every_event_type() {
+    let events = sample_events();
+    let path = fixture_path();
+    if env::var("INDEXER_FIXTURE_UPDATE").is_ok() {
+        let recorded: Vec<Value> = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| recorded_transaction(index, event))
+            .collect();
+        fs::write(
+            &path,
+            serde_json::to_string_pretty(&recorded).unwrap() + "\n",
+        )
+        .unwrap();
+    }
+
+    // The recorded bytes must still be what the program emits today
+    let transactions = source::read_file(&path).unwrap();
+    let recorded: Vec<Vec<u8>> = transactions
+        .iter()
+        .flat_map(|tx| tx.events.clone())
+        .collect();
+    assert!(
+        recorded == events,
+        "{path:?} is out of date; rewrite it with INDEXER_FIXTURE_UPDATE=1"
+    );
+
+    let names: Vec<&str> = recorded
+        .iter()
+        .map(|data| {
+            ProgramEvent::decode(data)
+                .expect("undecodable event")
+                .name()
+        })
+        .collect();
+    assert_eq!(names, ProgramEvent::NAMES);
+
+    // Every event is stored, the batch as one row per redeemed mint
+    let mut db = Db::open(Path::new(":memory:")).unwrap();
+    let indexed = index(&mut db, &transactions).unwrap();
+    assert_eq!(indexed.transactions, ProgramEvent::NAMES.len());
+    assert_eq!(indexed.events, ProgramEvent::NAMES.len());
+    assert_eq!(
+        count(&db, "SELECT COUNT(*) FROM events"),
+        ProgramEvent::NAMES.len() as u64 + 1
+    );
+    let (mint, nft_type_index, amount, reward): (String, u8, u64, u64) = db
+        .conn()
+        .query_row(
+            "SELECT nft_mint, nft_type_index, amount, reward FROM events
+             WHERE name = 'RedeemAndBurnNFTBatchEvent' AND entry_index = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .unwrap();
+    assert_eq!(mint, key(12).to_string());
+    assert_eq!((nft_type_index, amount, reward), (2, 1, 66));
+}
+
+#[test]
+fn resuming_from_the_cursor_inserts_nothing_twice() {
+    let path = temp_db("resume");
+    let events = sample_events();
+    let transactions = vec![
+        transaction(10, 1, vec![events[3].clone()]),
+        transaction(20, 2, vec![events[6].clone()]),
+        transaction(20, 3, vec![events[19].clone()]),
+        transaction(30, 4, vec![events[24].clone()]),
+    ];
+
+    // A run that stopped after the first transaction in slot 20
+    let mut db = Db::open(&path).unwrap();
+    let indexed = index(&mut db, &transactions[..2]).unwrap();
+    assert_eq!((indexed.transactions, indexed.events), (2, 2));
+    drop(db);
+
+    // The next run starts over at the cursor's slot, where only the new events are inserted
+    let mut db = Db::open(&path).unwrap();
+    let cursor = db.cursor().unwrap().unwrap();
+    assert_eq!(cursor.slot, 20);
+    assert_eq!(cursor.signature, transactions[1].signature);
+    let indexed = index(&mut db, &transactions).unwrap();
+    assert_eq!((indexed.transactions, indexed.events), (3, 2));
+    assert_eq!(count(&db, "SELECT COUNT(*) FROM events"), 5);
+    assert_eq!(db.cursor().unwrap().unwrap().slot, 30);
+
+    // Running again over the same transactions is a no-op
+    let indexed = index(&mut db, &transactions).unwrap();
+    assert_eq!((indexed.transactions, indexed.events), (1, 0));
+    assert_eq!(count(&db, "SELECT COUNT(*) FROM events"), 5);
+
+    drop(db);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn failed_transactions_only_move_the_cursor() {
+    let mut db = Db::open(Path::new(":memory:")).unwrap();
+    let mut failed = transaction(10, 1, vec![sample_events()[3].clone()]);
+    failed.failed = true;
+
+    let indexed = index(&mut db, &[failed]).unwrap();
+    assert_eq!((indexed.transactions, indexed.events), (1, 0));
+    assert_eq!(count(&db, "SELECT COUNT(*) FROM events"), 0);
+    assert_eq!(db.cursor().unwrap().unwrap().slot, 10);
+}
+
+#[test]
+fn databases_from_before_batch_entries_are_rebuilt() {
+    let path = temp_db("upgrade");
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE events (signature TEXT NOT NULL, event_index INTEGER NOT NULL,
+             PRIMARY KEY (signature, event_index));
+         INSERT INTO events VALUES ('old', 0);
+         CREATE TABLE cursor (id INTEGER PRIMARY KEY, slot INTEGER NOT NULL,
+             signature TEXT NOT NULL);
+         INSERT INTO cursor VALUES (0, 99, 'old');",
+    )
+    .unwrap();
+    drop(conn);
+
+    let mut db = Db::open(&path).unwrap();
+    assert!(db.cursor().unwrap().is_none());
+    let batch = transaction(10, 1, vec![sample_events()[19].clone()]);
+    assert_eq!(index(&mut db, &[batch]).unwrap().events, 1);
+    assert_eq!(count(&db, "SELECT COUNT(*) FROM events"), 2);
+
+    drop(db);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn views_aggregate_every_redemption_path() {
+    let (user, other_user) = (key(3), key(5));
+    let redeemable_mint = pda::redeemable_mint(&nft_config()).0;
+    let events = vec![
+        SetRewardAmountEvent {
+            header: header(authority(), 0),
+            nft_type_index: 0,
+            old_reward_amount: None,
+            new_reward_amount: 100,
+        }
+        .data(),
+        EditRewardAmountEvent {
+            header: header(authority(), 1),
+            nft_type_index: 0,
+            old_reward_amount: Some(100),
+            new_reward_amount: 120,
+        }
+        .data(),
+        MintNFTEvent {
+            header: header(user, 2),
+            mint: key(10),
+            nft_type_index: 0,
+            amount: 1,
+        }
+        .data(),
+        RedeemAndBurnNFTEvent {
+            header: header(user, 3),
+            nft_mint: key(10),
+            nft_type_index: 0,
+            amount_burned: 2,
+            reward_amount: 230,
+            tier_boost: 0,
+            streak_bonus: 30,
+            campaign_bonus: 0,
+            sponsor_reward: 0,
+            referral_reward: 0,
+            vesting_position: None,
+        }
+        .data(),
+        // 400 in base rewards plus 40 in bonuses, split 110 and 330 between the types
+        RedeemAndBurnNFTBatchEvent {
+            header: header(user, 4),
+            entries: vec![
+                RedeemBatchEntry {
+                    nft_mint: key(10),
+                    nft_type_index: 0,
+                    amount_burned: 1,
+                    reward_amount: 100,
+                },
+                RedeemBatchEntry {
+                    nft_mint: key(11),
+                    nft_type_index: 1,
+                    amount_burned: 3,
+                    reward_amount: 300,
+                },
+            ],
+            amount_burned: 4,
+            reward_amount: 440,
+            tier_boost: 0,
+            streak_bonus: 40,
+            campaign_bonus: 0,
+            sponsor_reward: 0,
+            referral_reward: 0,
+        }
+        .data(),
+        // Only the leg paid in the redeemable token counts as reward
+        RedeemAndBurnNFTMultiEvent {
+            header: header(other_user, 5),
+            nft_mint: key(11),
+            nft_type_index: 1,
+            amount_burned: 1,
+            payouts: vec![
+                RewardLeg::Token {
+                    mint: redeemable_mint,
+                    amount: 50,
+                },
+                RewardLeg::Token {
+                    mint: key(30),
+                    amount: 7,
+                },
+                RewardLeg::Lamports { amount: 9 },
+            ],
+        }
+        .data(),
+        ClaimStakingRewardsEvent {
+            header: header(user, 6),
+            stake_position: key(25),
+            nft_type_index: 0,
+            amount: 5,
+        }
+        .data(),
+        ClaimVestedEvent {
+            header: header(other_user, 7),
+            vesting_position: key(24),
+            amount: 8,
+            remaining: 0,
+        }
+        .data(),
+    ];
+    let transactions: Vec<RecordedTransaction> = events
+        .into_iter()
+        .enumerate()
+        .map(|(i, event)| transaction(10 + i as u64, i as u8 + 1, vec![event]))
+        .collect();
+    let mut db = Db::open(Path::new(":memory:")).unwrap();
+    index(&mut db, &transactions).unwrap();
+
+    let user_stats = |user: Pubkey| -> [u64; 6] {
+        db.conn()
+            .query_row(
+                "SELECT units_minted, units_redeemed, redemptions, rewarded, staking_rewards,
+                     vested_claimed
+                 FROM user_stats WHERE user = ?1",
+                [user.to_string()],
+                |row| {
+                    Ok([
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ])
+                },
+            )
+            .unwrap()
+    };
+    assert_eq!(user_stats(user), [1, 6, 2, 670, 5, 0]);
+    assert_eq!(user_stats(other_user), [0, 1, 1, 50, 0, 8]);
+
+    let type_stats = |nft_type_index: u8| -> (u64, u64, u64, u64, Option<u64>) {
+        db.conn()
+            .query_row(
+                "SELECT units_minted, units_redeemed, rewarded, redeemers, reward_amount
+                 FROM type_stats WHERE nft_type_index = ?1",
+                [nft_type_index],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .unwrap()
+    };
+    assert_eq!(type_stats(0), (1, 3, 230 + 110, 1, Some(120)));
+    assert_eq!(type_stats(1), (0, 4, 330 + 50, 2, None));
+}
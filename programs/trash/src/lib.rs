@@ -1,36 +1,69 @@
 use anchor_lang::prelude::*;
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
 use anchor_spl::metadata::{
     create_master_edition_v3, create_metadata_accounts_v3, mpl_token_metadata::types::DataV2,
-    CreateMasterEditionV3, CreateMetadataAccountsV3, MetadataAccount,
+    CreateMasterEditionV3, CreateMetadataAccountsV3, Metadata, MetadataAccount,
 };
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use bytemuck::Zeroable;
 
 // Constants
 pub const NFT_CONFIG_SEED: &[u8] = b"nft-config";
 pub const REDEEMABLE_MINT_SEED: &[u8] = b"redeemable-mint";
+pub const NFT_MINT_SEED: &[u8] = b"nft-mint";
 pub const MAX_NAME_LENGTH: usize = 32;
 pub const MAX_SYMBOL_LENGTH: usize = 10;
 pub const MAX_URI_LENGTH: usize = 200;
+pub const CAMPAIGN_SEED: &[u8] = b"campaign";
+pub const MAX_CAMPAIGN_TYPES: usize = 10;
+pub const BASIS_POINTS_DENOMINATOR: u64 = 10_000;
+pub const SPONSOR_POOL_SEED: &[u8] = b"sponsor-pool";
+pub const SPONSOR_VAULT_SEED: &[u8] = b"sponsor-vault";
+pub const MAX_NFT_TYPES: usize = 10;
+pub const REWARD_VAULT_SEED: &[u8] = b"reward-vault";
+pub const LAMPORT_VAULT_SEED: &[u8] = b"lamport-vault";
+pub const MAX_REWARD_LEGS: usize = 3;
+pub const USER_RECORD_SEED: &[u8] = b"user-record";
+pub const VESTING_POSITION_SEED: &[u8] = b"vesting-position";
+pub const VESTING_VAULT_SEED: &[u8] = b"vesting-vault";
+pub const STAKE_POSITION_SEED: &[u8] = b"stake-position";
+pub const STAKE_ESCROW_SEED: &[u8] = b"stake-escrow";
+pub const TIER_COUNT: usize = 3;
+pub const ACHIEVEMENT_SEED: &[u8] = b"achievement";
+pub const ACHIEVEMENT_CLAIM_SEED: &[u8] = b"achievement-claim";
+pub const BADGE_MINT_SEED: &[u8] = b"badge-mint";
+pub const SECONDS_PER_DAY: i64 = 86_400;
+// Layout version of NFTConfig accounts; v1 accounts predate the version byte
+pub const NFT_CONFIG_VERSION: u8 = 2;
 
 declare_id!("EfmbcacUa2G3w7hChRSTUeP6yQ8MNh2Jv8oVTgwQijbJ");
 #[program]
 pub mod trash4coin {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>, max_nft_types: u8) -> Result<()> {
+    pub fn initialize(ctx: Context<Initialize>, config_id: u64, max_nft_types: u8) -> Result<()> {
         require!(
             max_nft_types > 0 && max_nft_types <= 10,
             ErrorCode::InvalidMaxNFTTypes
         );
 
-        let nft_config = &mut ctx.accounts.nft_config;
+        // Everything not set here starts zeroed: no types, limits, tiers or bonuses
+        let mut nft_config = ctx.accounts.nft_config.load_init()?;
         nft_config.authority = ctx.accounts.authority.key();
+        nft_config.config_id = config_id;
         nft_config.max_nft_types = max_nft_types;
-        nft_config.nft_types = vec![];
+        nft_config.current_epoch = Clock::get()?.epoch;
+        nft_config.version = NFT_CONFIG_VERSION;
+        // The discriminator is only written on exit, so the header can't go through load_mut
+        let header =
+            nft_config.event_header(ctx.accounts.nft_config.key(), ctx.accounts.authority.key())?;
+        drop(nft_config);
 
-        emit!(InitializeEvent {
-            authority: ctx.accounts.authority.key(),
+        emit_cpi!(InitializeEvent {
+            header,
+            config_id,
             max_nft_types,
         });
 
@@ -43,29 +76,33 @@ pub mod trash4coin {
         symbol: String,
         uri: String,
     ) -> Result<()> {
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
         // Add authority check
         require!(
-            ctx.accounts.authority.key() == ctx.accounts.nft_config.authority,
+            ctx.accounts.authority.key() == nft_config.authority,
             ErrorCode::UnauthorizedAccess
         );
 
         require!(
-            ctx.accounts.nft_config.nft_types.len()
-                < ctx.accounts.nft_config.max_nft_types as usize,
+            nft_config.nft_type_count < nft_config.max_nft_types,
             ErrorCode::MaxNFTTypesReached
         );
         require!(!name.is_empty(), ErrorCode::EmptyName);
         require!(!symbol.is_empty(), ErrorCode::EmptySymbol);
         require!(!uri.is_empty(), ErrorCode::EmptyURI);
 
-        ctx.accounts.nft_config.nft_types.push(NFTType {
-            name: name.clone(),
-            symbol: symbol.clone(),
-            uri: uri.clone(),
-            reward_amount: None,
-        });
+        let nft_type_index = nft_config.nft_type_count;
+        nft_config.nft_types[nft_type_index as usize] = NFTTypeData::new(&name, &symbol, &uri)?;
+        nft_config.nft_type_count += 1;
+        drop(nft_config);
 
-        emit!(AddNFTTypeEvent { name, symbol, uri });
+        emit_cpi!(AddNFTTypeEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            nft_type_index,
+            name,
+            symbol,
+            uri,
+        });
 
         Ok(())
     }
@@ -77,21 +114,22 @@ pub mod trash4coin {
     ) -> Result<()> {
         // Add authority check
         require!(
-            ctx.accounts.authority.key() == ctx.accounts.nft_config.authority,
+            ctx.accounts.authority.key() == ctx.accounts.nft_config.load()?.authority,
             ErrorCode::UnauthorizedAccess
         );
-        require!(
-            (nft_type_index as usize) < ctx.accounts.nft_config.nft_types.len(),
-            ErrorCode::InvalidNFTType
-        );
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let nft_type = nft_config.nft_type_mut(nft_type_index)?;
         require!(reward_amount > 0, ErrorCode::InvalidRewardAmount);
 
-        let nft_type = &mut ctx.accounts.nft_config.nft_types[nft_type_index as usize];
-        nft_type.reward_amount = Some(reward_amount);
+        let old_reward_amount = nft_type.reward_amount();
+        nft_type.reward_amount = reward_amount;
+        drop(nft_config);
 
-        emit!(SetRewardAmountEvent {
+        emit_cpi!(SetRewardAmountEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
             nft_type_index,
-            reward_amount,
+            old_reward_amount,
+            new_reward_amount: reward_amount,
         });
 
         Ok(())
@@ -103,20 +141,19 @@ pub mod trash4coin {
         new_reward_amount: u64,
     ) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == ctx.accounts.nft_config.authority,
+            ctx.accounts.authority.key() == ctx.accounts.nft_config.load()?.authority,
             ErrorCode::UnauthorizedAccess
         );
-        require!(
-            (nft_type_index as usize) < ctx.accounts.nft_config.nft_types.len(),
-            ErrorCode::InvalidNFTType
-        );
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let nft_type = nft_config.nft_type_mut(nft_type_index)?;
         require!(new_reward_amount > 0, ErrorCode::InvalidRewardAmount);
 
-        let nft_type = &mut ctx.accounts.nft_config.nft_types[nft_type_index as usize];
-        let old_reward_amount = nft_type.reward_amount.unwrap_or(0);
-        nft_type.reward_amount = Some(new_reward_amount);
+        let old_reward_amount = nft_type.reward_amount();
+        nft_type.reward_amount = new_reward_amount;
+        drop(nft_config);
 
-        emit!(EditRewardAmountEvent {
+        emit_cpi!(EditRewardAmountEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
             nft_type_index,
             old_reward_amount,
             new_reward_amount,
@@ -139,26 +176,30 @@ pub mod trash4coin {
             ),
             amount,
         )?;
-        emit!(IncreaseTokenSupplyEvent {
+        emit_cpi!(IncreaseTokenSupplyEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
             mint: ctx.accounts.redeemable_mint.key(),
             amount,
-            authority: ctx.accounts.authority.key(),
         });
 
         Ok(())
     }
 
     pub fn increase_max_nft_types(ctx: Context<IncreaseMaxNFTTypes>, new_max: u8) -> Result<()> {
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        // The type slots are fixed in the account, so the maximum can't grow past them
         require!(
-            new_max > ctx.accounts.nft_config.max_nft_types,
+            new_max > nft_config.max_nft_types && new_max as usize <= MAX_NFT_TYPES,
             ErrorCode::InvalidNewMaxNFTTypes
         );
 
-        ctx.accounts.nft_config.max_nft_types = new_max;
+        let old_max = nft_config.max_nft_types;
+        nft_config.max_nft_types = new_max;
+        drop(nft_config);
 
-        emit!(IncreaseMaxNFTTypesEvent {
-            authority: ctx.accounts.authority.key(),
-            old_max: ctx.accounts.nft_config.max_nft_types,
+        emit_cpi!(IncreaseMaxNFTTypesEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            old_max,
             new_max,
         });
 
@@ -166,13 +207,21 @@ pub mod trash4coin {
     }
 
     pub fn mint_nft(ctx: Context<MintNFT>, nft_type_index: u8, amount: u64) -> Result<()> {
-        require!(
-            (nft_type_index as usize) < ctx.accounts.nft_config.nft_types.len(),
-            ErrorCode::InvalidNFTType
-        );
-        require!(amount > 0, ErrorCode::InvalidAmount);
+        let nft_config = ctx.accounts.nft_config.load()?;
+        let nft_type = nft_config.nft_type(nft_type_index)?;
+        // The master edition only accepts a mint holding exactly one token
+        require!(amount == 1, ErrorCode::InvalidAmount);
 
-        let nft_type = &ctx.accounts.nft_config.nft_types[nft_type_index as usize];
+        let data = DataV2 {
+            name: nft_type.name().to_string(),
+            symbol: nft_type.symbol().to_string(),
+            uri: nft_type.uri().to_string(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+        drop(nft_config);
 
         // Create metadata for the NFT
         let cpi_accounts = CreateMetadataAccountsV3 {
@@ -190,19 +239,23 @@ pub mod trash4coin {
             cpi_accounts,
         );
 
-        let data = DataV2 {
-            name: nft_type.name.clone(),
-            symbol: nft_type.symbol.clone(),
-            uri: nft_type.uri.clone(),
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-        };
-
         create_metadata_accounts_v3(cpi_context, data, true, false, None)?;
 
-        // Create master edition (with max supply set to amount)
+        // Mint tokens
+        token::mint_to(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.token_account.to_account_info(),
+                    authority: ctx.accounts.minter.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // Create master edition (one-of-one, no prints). It takes over the mint authority, so
+        // it has to come after the mint and caps the supply at that one token.
         let cpi_accounts = CreateMasterEditionV3 {
             edition: ctx.accounts.master_edition.to_account_info(),
             mint: ctx.accounts.mint.to_account_info(),
@@ -220,24 +273,35 @@ pub mod trash4coin {
             cpi_accounts,
         );
 
-        create_master_edition_v3(cpi_context, Some(amount))?;
+        create_master_edition_v3(cpi_context, Some(0))?;
 
-        // Mint tokens
-        token::mint_to(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::MintTo {
-                    mint: ctx.accounts.mint.to_account_info(),
-                    to: ctx.accounts.token_account.to_account_info(),
-                    authority: ctx.accounts.minter.to_account_info(),
-                },
-            ),
-            amount,
-        )?;
+        // Update the minter's daily streak. Each type's mint is created once per config, so a
+        // streak only grows while there are types nobody has minted yet: at most
+        // max_nft_types days for the whole config, not a daily habit
+        let user_record = &mut ctx.accounts.user_record;
+        user_record.init_if_new(
+            ctx.accounts.nft_config.key(),
+            ctx.accounts.minter.key(),
+            ctx.bumps.user_record,
+        );
+        let today = Clock::get()?.unix_timestamp / SECONDS_PER_DAY;
+        let (broken_streak, extended_streak) = user_record.record_mint(today);
+        if let Some(previous_streak) = broken_streak {
+            emit_cpi!(StreakBroken {
+                header: event_header(&ctx.accounts.nft_config, ctx.accounts.minter.key())?,
+                previous_streak,
+            });
+        }
+        if let Some(current_streak) = extended_streak {
+            emit_cpi!(StreakExtended {
+                header: event_header(&ctx.accounts.nft_config, ctx.accounts.minter.key())?,
+                current_streak,
+            });
+        }
 
-        emit!(MintNFTEvent {
+        emit_cpi!(MintNFTEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.minter.key())?,
             mint: ctx.accounts.mint.key(),
-            owner: ctx.accounts.minter.key(),
             nft_type_index,
             amount,
         });
@@ -245,10 +309,207 @@ pub mod trash4coin {
         Ok(())
     }
 
+    pub fn mint_nft_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MintNFTBatch<'info>>,
+        entries: Vec<MintBatchEntry>,
+    ) -> Result<()> {
+        require!(!entries.is_empty(), ErrorCode::EmptyBatch);
+        // Each entry takes a (mint, token account, metadata, master edition) group from
+        // remaining_accounts
+        require!(
+            ctx.remaining_accounts.len() == entries.len() * 4,
+            ErrorCode::InvalidBatchAccounts
+        );
+
+        let nft_config_key = ctx.accounts.nft_config.key();
+        let minter = ctx.accounts.minter.to_account_info();
+        let token_metadata_program_key = ctx.accounts.token_metadata_program.key();
+        let mut seen_types = 0u64;
+
+        // Every type's mint gets a master edition, as in mint_nft, so each entry mints one NFT
+        for (entry, accounts) in entries.iter().zip(ctx.remaining_accounts.chunks(4)) {
+            let nft_config = ctx.accounts.nft_config.load()?;
+            let nft_type = nft_config.nft_type(entry.nft_type_index)?;
+            let data = DataV2 {
+                name: nft_type.name().to_string(),
+                symbol: nft_type.symbol().to_string(),
+                uri: nft_type.uri().to_string(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            };
+            drop(nft_config);
+
+            // Each type's mint can only be created once, so a repeated type could never succeed
+            let type_bit = 1u64 << entry.nft_type_index;
+            require!(seen_types & type_bit == 0, ErrorCode::DuplicateBatchEntry);
+            seen_types |= type_bit;
+
+            let (mint, token_account, metadata, master_edition) =
+                (&accounts[0], &accounts[1], &accounts[2], &accounts[3]);
+            let type_seed = [entry.nft_type_index];
+            let (mint_key, mint_bump) = Pubkey::find_program_address(
+                &[NFT_MINT_SEED, nft_config_key.as_ref(), &type_seed],
+                ctx.program_id,
+            );
+            require_keys_eq!(mint.key(), mint_key, ErrorCode::InvalidBatchAccounts);
+            require_keys_eq!(
+                token_account.key(),
+                get_associated_token_address(&minter.key(), &mint_key),
+                ErrorCode::InvalidBatchAccounts
+            );
+            let (metadata_key, _) = Pubkey::find_program_address(
+                &[
+                    b"metadata",
+                    token_metadata_program_key.as_ref(),
+                    mint_key.as_ref(),
+                ],
+                &token_metadata_program_key,
+            );
+            require_keys_eq!(metadata.key(), metadata_key, ErrorCode::InvalidBatchAccounts);
+            let (master_edition_key, _) = Pubkey::find_program_address(
+                &[
+                    b"metadata",
+                    token_metadata_program_key.as_ref(),
+                    mint_key.as_ref(),
+                    b"edition",
+                ],
+                &token_metadata_program_key,
+            );
+            require_keys_eq!(
+                master_edition.key(),
+                master_edition_key,
+                ErrorCode::InvalidBatchAccounts
+            );
+
+            create_pda_account(
+                &minter,
+                mint,
+                &ctx.accounts.system_program.to_account_info(),
+                Mint::LEN,
+                &ctx.accounts.token_program.key(),
+                &[
+                    NFT_MINT_SEED,
+                    nft_config_key.as_ref(),
+                    &type_seed,
+                    &[mint_bump],
+                ],
+            )?;
+            token::initialize_mint2(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::InitializeMint2 { mint: mint.clone() },
+                ),
+                0,
+                &minter.key(),
+                Some(&minter.key()),
+            )?;
+
+            associated_token::create_idempotent(CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: minter.clone(),
+                    associated_token: token_account.clone(),
+                    authority: minter.clone(),
+                    mint: mint.clone(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            ))?;
+
+            create_metadata_accounts_v3(
+                CpiContext::new(
+                    ctx.accounts.token_metadata_program.to_account_info(),
+                    CreateMetadataAccountsV3 {
+                        metadata: metadata.clone(),
+                        mint: mint.clone(),
+                        mint_authority: minter.clone(),
+                        payer: minter.clone(),
+                        update_authority: minter.clone(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        rent: ctx.accounts.rent.to_account_info(),
+                    },
+                ),
+                data,
+                true,
+                false,
+                None,
+            )?;
+
+            token::mint_to(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::MintTo {
+                        mint: mint.clone(),
+                        to: token_account.clone(),
+                        authority: minter.clone(),
+                    },
+                ),
+                1,
+            )?;
+
+            create_master_edition_v3(
+                CpiContext::new(
+                    ctx.accounts.token_metadata_program.to_account_info(),
+                    CreateMasterEditionV3 {
+                        edition: master_edition.clone(),
+                        mint: mint.clone(),
+                        update_authority: minter.clone(),
+                        mint_authority: minter.clone(),
+                        metadata: metadata.clone(),
+                        payer: minter.clone(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        rent: ctx.accounts.rent.to_account_info(),
+                    },
+                ),
+                Some(0),
+            )?;
+
+            emit_cpi!(MintNFTEvent {
+                header: event_header(&ctx.accounts.nft_config, ctx.accounts.minter.key())?,
+                mint: mint_key,
+                nft_type_index: entry.nft_type_index,
+                amount: 1,
+            });
+        }
+
+        // A batch counts as a single mint towards the daily streak
+        let user_record = &mut ctx.accounts.user_record;
+        user_record.init_if_new(
+            ctx.accounts.nft_config.key(),
+            ctx.accounts.minter.key(),
+            ctx.bumps.user_record,
+        );
+        let today = Clock::get()?.unix_timestamp / SECONDS_PER_DAY;
+        let (broken_streak, extended_streak) = user_record.record_mint(today);
+        if let Some(previous_streak) = broken_streak {
+            emit_cpi!(StreakBroken {
+                header: event_header(&ctx.accounts.nft_config, ctx.accounts.minter.key())?,
+                previous_streak,
+            });
+        }
+        if let Some(current_streak) = extended_streak {
+            emit_cpi!(StreakExtended {
+                header: event_header(&ctx.accounts.nft_config, ctx.accounts.minter.key())?,
+                current_streak,
+            });
+        }
+
+        emit_cpi!(MintNFTBatchEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.minter.key())?,
+            total_amount: entries.len() as u64,
+            entries,
+        });
+
+        Ok(())
+    }
+
     pub fn create_redeemable_token(ctx: Context<CreateRedeemableToken>, amount: u64) -> Result<()> {
         // Add authority check
         require!(
-            ctx.accounts.authority.key() == ctx.accounts.nft_config.authority,
+            ctx.accounts.authority.key() == ctx.accounts.nft_config.load()?.authority,
             ErrorCode::UnauthorizedAccess
         );
         require!(amount > 0, ErrorCode::InvalidAmount);
@@ -265,59 +526,415 @@ pub mod trash4coin {
             amount,
         )?;
 
-        emit!(CreateRedeemableTokenEvent {
+        emit_cpi!(CreateRedeemableTokenEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
             mint: ctx.accounts.redeemable_mint.key(),
             amount,
-            authority: ctx.accounts.authority.key(),
         });
 
         Ok(())
     }
 
-    pub fn redeem_and_burn_nft(ctx: Context<RedeemAndBurnNFT>, amount: u64) -> Result<()> {
+    pub fn redeem_and_burn_nft(
+        ctx: Context<RedeemAndBurnNFT>,
+        amount: u64,
+        min_reward_out: u64,
+        expected_reward_rate: Option<u64>,
+    ) -> Result<()> {
         require!(amount > 0, ErrorCode::InvalidAmount);
 
-        // Deserialize the metadata account
-        let metadata =
-            MetadataAccount::try_deserialize(&mut &ctx.accounts.metadata.data.borrow()[..])
-                .map_err(|_| ErrorCode::InvalidMetadata)?;
+        let nft_config = ctx.accounts.nft_config.load()?;
+        let nft_type_index = find_nft_type_index(
+            &nft_config,
+            &ctx.accounts.metadata,
+            &ctx.accounts.nft_mint.key(),
+        )?;
+        require_type_mint(
+            &ctx.accounts.nft_config.key(),
+            nft_type_index,
+            &ctx.accounts.nft_mint.key(),
+        )?;
+
+        ctx.accounts.user_record.init_if_new(
+            ctx.accounts.nft_config.key(),
+            ctx.accounts.user.key(),
+            ctx.bumps.user_record,
+        );
 
-        // Extract name and uri
-        let name = metadata.name.trim_end_matches('\0').to_string();
-        let uri = metadata.uri.trim_end_matches('\0').to_string();
+        let nft_type = &nft_config.nft_types[nft_type_index];
 
-        // Find the matching NFT type
-        let nft_type = ctx
-            .accounts
-            .nft_config
-            .nft_types
-            .iter()
-            .find(|t| t.name == name && t.uri == uri)
-            .ok_or(ErrorCode::InvalidNFTType)?;
+        let reward_amount =
+            nft_type.current_reward_rate(ctx.accounts.redeemable_token_account.amount)?;
+        if let Some(expected_reward_rate) = expected_reward_rate {
+            require!(
+                reward_amount == expected_reward_rate,
+                ErrorCode::RewardRateMismatch
+            );
+        }
 
-        let reward_amount = nft_type
-            .reward_amount
-            .ok_or(ErrorCode::RewardAmountNotSet)?;
+        let vesting = nft_type.vesting();
+        let staking_index = nft_type.staking_index_at(Clock::get()?.unix_timestamp);
 
         // Calculate total reward
-        let total_reward = reward_amount
+        let base_reward = reward_amount
             .checked_mul(amount)
             .ok_or(ErrorCode::ArithmeticOverflow)?;
 
-        // Burn the NFTs
-        token::burn(
+        // Apply the boost for the tier the user held before this redemption
+        let tier_boost = nft_config.tier_boost(ctx.accounts.user_record.tier, base_reward)?;
+
+        // Apply the streak bonus while the user's streak is still alive
+        let today = Clock::get()?.unix_timestamp / SECONDS_PER_DAY;
+        let streak_bonus =
+            nft_config.streak_bonus(ctx.accounts.user_record.active_streak(today), base_reward)?;
+        drop(nft_config);
+
+        // Apply the campaign bonus, if a campaign is active for this type
+        let campaign_bonus = match ctx.accounts.campaign.as_mut() {
+            Some(campaign) => {
+                let now = Clock::get()?.unix_timestamp;
+                let bonus = campaign.bonus_for(nft_type_index as u8, base_reward, now)?;
+                campaign.remaining_budget = campaign
+                    .remaining_budget
+                    .checked_sub(bonus)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+                bonus
+            }
+            None => 0,
+        };
+
+        let total_reward = base_reward
+            .checked_add(tier_boost)
+            .and_then(|r| r.checked_add(streak_bonus))
+            .and_then(|r| r.checked_add(campaign_bonus))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(
+            total_reward >= min_reward_out,
+            ErrorCode::RewardBelowMinimum
+        );
+
+        // Enforce the per-user and global redemption limits for the current epoch
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let user_record = &mut ctx.accounts.user_record;
+        consume_epoch_limits(&mut nft_config, user_record, total_reward)?;
+
+        if let Some(tier_upgraded) = record_redemption(
+            &mut nft_config,
+            user_record,
+            1 << nft_type_index,
+            amount,
+            total_reward,
+        )? {
+            emit_cpi!(tier_upgraded);
+        }
+        drop(nft_config);
+
+        // Burn the NFTs, either from the user's wallet or from their stake escrow
+        match (
+            ctx.accounts.stake_position.as_mut(),
+            ctx.accounts.stake_escrow.as_ref(),
+        ) {
+            (Some(stake_position), Some(stake_escrow)) => {
+                require_keys_eq!(
+                    stake_escrow.key(),
+                    stake_position.escrow,
+                    ErrorCode::InvalidStakeEscrow
+                );
+                require!(
+                    stake_position.nft_type_index as usize == nft_type_index,
+                    ErrorCode::InvalidNFTType
+                );
+
+                // Settle rewards earned so far before the staked amount shrinks
+                stake_position.accrue(staking_index);
+                stake_position.amount = stake_position
+                    .amount
+                    .checked_sub(amount)
+                    .ok_or(ErrorCode::InsufficientStakedAmount)?;
+
+                let nft_config_key = stake_position.nft_config;
+                let seeds = &[
+                    STAKE_POSITION_SEED,
+                    nft_config_key.as_ref(),
+                    stake_position.user.as_ref(),
+                    stake_position.nft_mint.as_ref(),
+                    &[stake_position.bump],
+                ];
+
+                token::burn(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        token::Burn {
+                            mint: ctx.accounts.nft_mint.to_account_info(),
+                            from: stake_escrow.to_account_info(),
+                            authority: stake_position.to_account_info(),
+                        },
+                        &[&seeds[..]],
+                    ),
+                    amount,
+                )?;
+            }
+            (None, None) => {
+                token::burn(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        token::Burn {
+                            mint: ctx.accounts.nft_mint.to_account_info(),
+                            from: ctx.accounts.nft_token_account.to_account_info(),
+                            authority: ctx.accounts.user.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+            }
+            _ => return err!(ErrorCode::IncompleteStakeAccounts),
+        }
+
+        // Large redemptions of types with a vesting schedule are escrowed instead of paid out
+        let vesting_position = match vesting.filter(|v| total_reward >= v.min_reward) {
+            Some(schedule) => {
+                let (Some(vesting_position), Some(vesting_vault)) = (
+                    ctx.accounts.vesting_position.as_mut(),
+                    ctx.accounts.vesting_vault.as_ref(),
+                ) else {
+                    return err!(ErrorCode::VestingAccountsRequired);
+                };
+
+                let now = Clock::get()?.unix_timestamp;
+                let user_record = &mut ctx.accounts.user_record;
+                vesting_position.nft_config = ctx.accounts.nft_config.key();
+                vesting_position.user = ctx.accounts.user.key();
+                vesting_position.position_id = user_record.vesting_positions;
+                vesting_position.mint = ctx.accounts.redeemable_mint.key();
+                vesting_position.vault = vesting_vault.key();
+                vesting_position.total_amount = total_reward;
+                vesting_position.claimed_amount = 0;
+                vesting_position.start_ts = now;
+                vesting_position.cliff_ts = now
+                    .checked_add(schedule.cliff_seconds)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+                vesting_position.end_ts = now
+                    .checked_add(schedule.duration_seconds)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+                vesting_position.bump = ctx
+                    .bumps
+                    .vesting_position
+                    .ok_or(ErrorCode::VestingAccountsRequired)?;
+                user_record.vesting_positions = user_record
+                    .vesting_positions
+                    .checked_add(1)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+                Some(vesting_position.key())
+            }
+            None => {
+                require!(
+                    ctx.accounts.vesting_position.is_none() && ctx.accounts.vesting_vault.is_none(),
+                    ErrorCode::VestingNotApplicable
+                );
+                None
+            }
+        };
+
+        // Transfer redeemable tokens to the user, or into the vesting escrow
+        let reward_destination = match ctx.accounts.vesting_vault.as_ref() {
+            Some(vesting_vault) if vesting_position.is_some() => vesting_vault.to_account_info(),
+            _ => ctx.accounts.user_redeemable_token_account.to_account_info(),
+        };
+        token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                token::Burn {
-                    mint: ctx.accounts.nft_mint.to_account_info(),
-                    from: ctx.accounts.nft_token_account.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.redeemable_token_account.to_account_info(),
+                    to: reward_destination,
+                    authority: ctx.accounts.authority.to_account_info(),
                 },
             ),
-            amount,
+            total_reward,
+        )?;
+
+        // Pay the referrer's share on top of the user's reward, up to the per-referrer cap
+        let referral_reward = pay_referral(
+            &ctx.accounts.nft_config,
+            ctx.accounts.user_record.referrer,
+            ctx.accounts
+                .referrer_record
+                .as_mut()
+                .zip(ctx.accounts.referrer_token_account.as_ref()),
+            &ctx.accounts.redeemable_token_account,
+            &ctx.accounts.authority,
+            &ctx.accounts.token_program,
+            total_reward,
         )?;
 
-        // Transfer redeemable tokens to the user
+        // Pay the sponsor tokens from the pool vault
+        let sponsor_reward = pay_sponsor(
+            ctx.accounts.sponsor_pool.as_ref(),
+            ctx.accounts.sponsor_vault.as_ref(),
+            ctx.accounts.user_sponsor_token_account.as_ref(),
+            &ctx.accounts.user.key(),
+            &ctx.accounts.token_program,
+            &[(nft_type_index, amount)],
+        )?;
+
+        emit_cpi!(RedeemAndBurnNFTEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            nft_mint: ctx.accounts.nft_mint.key(),
+            nft_type_index: nft_type_index as u8,
+            amount_burned: amount,
+            reward_amount: total_reward,
+            tier_boost,
+            streak_bonus,
+            campaign_bonus,
+            sponsor_reward,
+            referral_reward,
+            vesting_position,
+        });
+
+        Ok(())
+    }
+
+    pub fn redeem_and_burn_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RedeemAndBurnBatch<'info>>,
+        min_reward_out: u64,
+    ) -> Result<()> {
+        // Each NFT mint takes a (mint, user token account, metadata) triple from
+        // remaining_accounts, and its whole balance is redeemed
+        require!(!ctx.remaining_accounts.is_empty(), ErrorCode::EmptyBatch);
+        // is_multiple_of is newer than the SBF toolchain's rustc
+        #[allow(clippy::manual_is_multiple_of)]
+        let whole_triples = ctx.remaining_accounts.len() % 3 == 0;
+        require!(whole_triples, ErrorCode::InvalidBatchAccounts);
+
+        ctx.accounts.user_record.init_if_new(
+            ctx.accounts.nft_config.key(),
+            ctx.accounts.user.key(),
+            ctx.bumps.user_record,
+        );
+
+        // Every type is priced against the vault balance before the batch pays out
+        let vault_balance = ctx.accounts.redeemable_token_account.amount;
+        let nft_config = ctx.accounts.nft_config.load()?;
+        let mut entries: Vec<RedeemBatchEntry> =
+            Vec::with_capacity(ctx.remaining_accounts.len() / 3);
+        let mut units_burned: u64 = 0;
+        let mut base_reward: u64 = 0;
+
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            let (nft_mint, nft_token_account, metadata) = (&accounts[0], &accounts[1], &accounts[2]);
+            require!(
+                entries.iter().all(|entry| entry.nft_mint != nft_mint.key()),
+                ErrorCode::DuplicateBatchEntry
+            );
+
+            let nft_tokens = Account::<TokenAccount>::try_from(nft_token_account)?;
+            require_keys_eq!(
+                nft_tokens.mint,
+                nft_mint.key(),
+                ErrorCode::InvalidBatchAccounts
+            );
+            require_keys_eq!(
+                nft_tokens.owner,
+                ctx.accounts.user.key(),
+                ErrorCode::InvalidBatchAccounts
+            );
+            require!(nft_tokens.amount > 0, ErrorCode::InvalidAmount);
+
+            let nft_type_index = find_nft_type_index(&nft_config, metadata, &nft_mint.key())?;
+            require_type_mint(&ctx.accounts.nft_config.key(), nft_type_index, &nft_mint.key())?;
+            let nft_type = &nft_config.nft_types[nft_type_index];
+            // Vesting needs per-redemption position accounts, which only redeem_and_burn_nft takes
+            require!(nft_type.vesting().is_none(), ErrorCode::VestingTypeInBatch);
+
+            let reward_amount = nft_type
+                .current_reward_rate(vault_balance)?
+                .checked_mul(nft_tokens.amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            units_burned = units_burned
+                .checked_add(nft_tokens.amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            base_reward = base_reward
+                .checked_add(reward_amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+            entries.push(RedeemBatchEntry {
+                nft_mint: nft_mint.key(),
+                nft_type_index: nft_type_index as u8,
+                amount_burned: nft_tokens.amount,
+                reward_amount,
+            });
+        }
+
+        // Boosts apply once to the summed base reward, for the tier held before the batch
+        let tier_boost = nft_config.tier_boost(ctx.accounts.user_record.tier, base_reward)?;
+        let today = Clock::get()?.unix_timestamp / SECONDS_PER_DAY;
+        let streak_bonus =
+            nft_config.streak_bonus(ctx.accounts.user_record.active_streak(today), base_reward)?;
+        drop(nft_config);
+
+        // Apply the campaign bonus to each type the campaign is active for, drawing the
+        // budget down entry by entry
+        let campaign_bonus = match ctx.accounts.campaign.as_mut() {
+            Some(campaign) => {
+                let now = Clock::get()?.unix_timestamp;
+                let mut campaign_bonus: u64 = 0;
+                for entry in entries.iter() {
+                    let bonus = campaign.bonus_for(entry.nft_type_index, entry.reward_amount, now)?;
+                    campaign.remaining_budget = campaign
+                        .remaining_budget
+                        .checked_sub(bonus)
+                        .ok_or(ErrorCode::ArithmeticOverflow)?;
+                    campaign_bonus = campaign_bonus
+                        .checked_add(bonus)
+                        .ok_or(ErrorCode::ArithmeticOverflow)?;
+                }
+                campaign_bonus
+            }
+            None => 0,
+        };
+
+        let total_reward = base_reward
+            .checked_add(tier_boost)
+            .and_then(|r| r.checked_add(streak_bonus))
+            .and_then(|r| r.checked_add(campaign_bonus))
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(
+            total_reward >= min_reward_out,
+            ErrorCode::RewardBelowMinimum
+        );
+
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let user_record = &mut ctx.accounts.user_record;
+        consume_epoch_limits(&mut nft_config, user_record, total_reward)?;
+        let types_mask = entries
+            .iter()
+            .fold(0, |mask, entry| mask | 1 << entry.nft_type_index);
+        if let Some(tier_upgraded) = record_redemption(
+            &mut nft_config,
+            user_record,
+            types_mask,
+            units_burned,
+            total_reward,
+        )? {
+            emit_cpi!(tier_upgraded);
+        }
+        drop(nft_config);
+
+        for (accounts, entry) in ctx.remaining_accounts.chunks(3).zip(entries.iter()) {
+            token::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Burn {
+                        mint: accounts[0].clone(),
+                        from: accounts[1].clone(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                entry.amount_burned,
+            )?;
+        }
+
+        // One transfer from the vault covers every type in the batch
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
@@ -330,16 +947,84 @@ pub mod trash4coin {
             total_reward,
         )?;
 
-        emit!(RedeemAndBurnNFTEvent {
-            user: ctx.accounts.user.key(),
-            nft_mint: ctx.accounts.nft_mint.key(),
-            amount_burned: amount,
+        // Pay the referrer's share on top of the user's reward, up to the per-referrer cap
+        let referral_reward = pay_referral(
+            &ctx.accounts.nft_config,
+            ctx.accounts.user_record.referrer,
+            ctx.accounts
+                .referrer_record
+                .as_mut()
+                .zip(ctx.accounts.referrer_token_account.as_ref()),
+            &ctx.accounts.redeemable_token_account,
+            &ctx.accounts.authority,
+            &ctx.accounts.token_program,
+            total_reward,
+        )?;
+
+        // Pay the sponsor tokens for every type in the batch from the pool vault
+        let redeemed: Vec<(usize, u64)> = entries
+            .iter()
+            .map(|entry| (entry.nft_type_index as usize, entry.amount_burned))
+            .collect();
+        let sponsor_reward = pay_sponsor(
+            ctx.accounts.sponsor_pool.as_ref(),
+            ctx.accounts.sponsor_vault.as_ref(),
+            ctx.accounts.user_sponsor_token_account.as_ref(),
+            &ctx.accounts.user.key(),
+            &ctx.accounts.token_program,
+            &redeemed,
+        )?;
+
+        emit_cpi!(RedeemAndBurnNFTBatchEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            entries,
+            amount_burned: units_burned,
             reward_amount: total_reward,
+            tier_boost,
+            streak_bonus,
+            campaign_bonus,
+            sponsor_reward,
+            referral_reward,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_reward_curve(
+        ctx: Context<SetRewardCurve>,
+        nft_type_index: u8,
+        reward_curve: Option<RewardCurve>,
+    ) -> Result<()> {
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let nft_type = nft_config.nft_type_mut(nft_type_index)?;
+        if let Some(curve) = reward_curve.as_ref() {
+            require!(
+                curve.reference_balance > 0
+                    && curve.min_multiplier_bps as u64 <= BASIS_POINTS_DENOMINATOR
+                    && curve.exponent > 0,
+                ErrorCode::InvalidRewardCurve
+            );
+        }
+
+        nft_type.set_reward_curve(reward_curve.as_ref());
+        drop(nft_config);
+
+        emit_cpi!(SetRewardCurveEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            nft_type_index,
+            reward_curve,
         });
 
         Ok(())
     }
 
+    pub fn quote_redemption(ctx: Context<QuoteRedemption>, nft_type_index: u8) -> Result<u64> {
+        let nft_config = ctx.accounts.nft_config.load()?;
+        let nft_type = nft_config.nft_type(nft_type_index)?;
+
+        nft_type.current_reward_rate(ctx.accounts.redeemable_token_account.amount)
+    }
+
     pub fn get_user_info(ctx: Context<GetUserInfo>) -> Result<UserInfo> {
         let nft_balance = ctx.accounts.nft_token_account.amount;
         let redeemable_balance = ctx.accounts.user_redeemable_token_account.amount;
@@ -350,8 +1035,17 @@ pub mod trash4coin {
             redeemable_balance,
         };
 
+        // A read-only query: it logs instead of taking a sequence number, and reports the
+        // config's current one
+        let clock = Clock::get()?;
         emit!(UserInfoFetched {
-            user: ctx.accounts.user.key(),
+            header: EventHeader {
+                nft_config: ctx.accounts.nft_config.key(),
+                actor: ctx.accounts.user.key(),
+                seq: ctx.accounts.nft_config.load()?.event_seq,
+                slot: clock.slot,
+                unix_timestamp: clock.unix_timestamp,
+            },
             nft_mint: user_info.nft_mint,
             nft_balance: user_info.nft_balance,
             redeemable_balance: user_info.redeemable_balance,
@@ -360,123 +1054,3050 @@ pub mod trash4coin {
         Ok(user_info)
     }
 
-}
-
-#[derive(Accounts)]
-#[instruction(max_nft_types: u8)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    pub fn set_vesting_schedule(
+        ctx: Context<SetVestingSchedule>,
+        nft_type_index: u8,
+        vesting: Option<VestingSchedule>,
+    ) -> Result<()> {
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let nft_type = nft_config.nft_type_mut(nft_type_index)?;
+        if let Some(schedule) = vesting.as_ref() {
+            require!(
+                schedule.duration_seconds > 0
+                    && schedule.cliff_seconds >= 0
+                    && schedule.cliff_seconds <= schedule.duration_seconds,
+                ErrorCode::InvalidVestingSchedule
+            );
+        }
 
-    #[account(
-        init,
-        payer = authority,
-        space = NFTConfig::space(),
-        seeds = [NFT_CONFIG_SEED, authority.key().as_ref()],
-        bump
-    )]
-    pub nft_config: Account<'info, NFTConfig>,
+        nft_type.set_vesting(vesting.as_ref());
+        drop(nft_config);
 
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        emit_cpi!(SetVestingScheduleEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            nft_type_index,
+            vesting,
+        });
 
-#[derive(Accounts)]
-#[instruction(nft_type_index: u8, amount: u64)]
-pub struct MintNFT<'info> {
-    #[account(mut)]
-    pub minter: Signer<'info>,
+        Ok(())
+    }
 
-    #[account(
-        init,
-        payer = minter,
-        mint::decimals = 0,
-        mint::authority = minter.key(),
-        mint::freeze_authority = minter.key(),
-        seeds = [b"nft-mint", nft_config.key().as_ref(), &[nft_type_index]],
-        bump
-    )]
-    pub mint: Account<'info, Mint>,
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vesting_position = &mut ctx.accounts.vesting_position;
 
-    #[account(
-        init_if_needed,
-        payer = minter,
-        associated_token::mint = mint,
-        associated_token::authority = minter,
-    )]
-    pub token_account: Account<'info, TokenAccount>,
+        let claimable = vesting_position
+            .unlocked_amount(now)?
+            .checked_sub(vesting_position.claimed_amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(claimable > 0, ErrorCode::NothingToClaim);
 
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    #[account(
-        mut,
-        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
-        seeds::program = token_metadata_program.key(),
-        bump
-    )]
-    pub metadata: UncheckedAccount<'info>,
+        vesting_position.claimed_amount = vesting_position
+            .claimed_amount
+            .checked_add(claimable)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
 
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    #[account(
-        mut,
-        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
-        seeds::program = token_metadata_program.key(),
-        bump
-    )]
-    pub master_edition: UncheckedAccount<'info>,
+        let position_id = vesting_position.position_id.to_le_bytes();
+        let seeds = &[
+            VESTING_POSITION_SEED,
+            vesting_position.nft_config.as_ref(),
+            vesting_position.user.as_ref(),
+            position_id.as_ref(),
+            &[vesting_position.bump],
+        ];
 
-    #[account(
-        seeds = [NFT_CONFIG_SEED, nft_config.authority.as_ref()],
-        bump
-    )]
-    pub nft_config: Account<'info, NFTConfig>,
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.vesting_vault.to_account_info(),
+                    to: ctx.accounts.user_redeemable_token_account.to_account_info(),
+                    authority: vesting_position.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            claimable,
+        )?;
 
-    pub rent: Sysvar<'info, Rent>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub token_metadata_program: UncheckedAccount<'info>,
-}
+        emit_cpi!(ClaimVestedEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            vesting_position: vesting_position.key(),
+            amount: claimable,
+            remaining: vesting_position.total_amount - vesting_position.claimed_amount,
+        });
 
-#[derive(Accounts)]
-pub struct CreateRedeemableToken<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        Ok(())
+    }
 
+    pub fn set_staking_reward_rate(
+        ctx: Context<SetStakingRewardRate>,
+        nft_type_index: u8,
+        staking_reward_rate: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let nft_type = nft_config.nft_type_mut(nft_type_index)?;
+        // Settle the index at the old rate, so the new one only applies from now on
+        nft_type.update_staking_index(now);
+        nft_type.staking_reward_rate = staking_reward_rate;
+        drop(nft_config);
+
+        emit_cpi!(SetStakingRewardRateEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            nft_type_index,
+            staking_reward_rate,
+        });
+
+        Ok(())
+    }
+
+    pub fn stake_nft(ctx: Context<StakeNFT>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let nft_config = ctx.accounts.nft_config.load()?;
+        let nft_type_index =
+            find_nft_type_index(&nft_config, &ctx.accounts.metadata, &ctx.accounts.nft_mint.key())?;
+        require_type_mint(
+            &ctx.accounts.nft_config.key(),
+            nft_type_index,
+            &ctx.accounts.nft_mint.key(),
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        let staking_index = nft_config.nft_types[nft_type_index].staking_index_at(now);
+        drop(nft_config);
+
+        let stake_position = &mut ctx.accounts.stake_position;
+        if stake_position.user == Pubkey::default() {
+            stake_position.nft_config = ctx.accounts.nft_config.key();
+            stake_position.user = ctx.accounts.user.key();
+            stake_position.nft_mint = ctx.accounts.nft_mint.key();
+            stake_position.nft_type_index = nft_type_index as u8;
+            stake_position.escrow = ctx.accounts.stake_escrow.key();
+            stake_position.bump = ctx.bumps.stake_position;
+        }
+
+        stake_position.accrue(staking_index);
+        stake_position.amount = stake_position
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.nft_token_account.to_account_info(),
+                    to: ctx.accounts.stake_escrow.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit_cpi!(StakeNFTEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            nft_mint: ctx.accounts.nft_mint.key(),
+            nft_type_index: nft_type_index as u8,
+            amount,
+            total_staked: stake_position.amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn unstake_nft(ctx: Context<UnstakeNFT>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let stake_position = &mut ctx.accounts.stake_position;
+        let staking_index = ctx
+            .accounts
+            .nft_config
+            .load()?
+            .nft_type(stake_position.nft_type_index)?
+            .staking_index_at(Clock::get()?.unix_timestamp);
+
+        stake_position.accrue(staking_index);
+        stake_position.amount = stake_position
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientStakedAmount)?;
+
+        let seeds = &[
+            STAKE_POSITION_SEED,
+            stake_position.nft_config.as_ref(),
+            stake_position.user.as_ref(),
+            stake_position.nft_mint.as_ref(),
+            &[stake_position.bump],
+        ];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.stake_escrow.to_account_info(),
+                    to: ctx.accounts.nft_token_account.to_account_info(),
+                    authority: stake_position.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+
+        emit_cpi!(UnstakeNFTEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            nft_mint: stake_position.nft_mint,
+            nft_type_index: stake_position.nft_type_index,
+            amount,
+            total_staked: stake_position.amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
+        let nft_config = ctx.accounts.nft_config.load()?;
+        let stake_position = &mut ctx.accounts.stake_position;
+        let staking_index = nft_config
+            .nft_type(stake_position.nft_type_index)?
+            .staking_index_at(Clock::get()?.unix_timestamp);
+        let nft_config_authority = nft_config.authority;
+        let config_id = nft_config.config_id_seed();
+        // The config signs the transfer below, so it can't stay borrowed
+        drop(nft_config);
+        let nft_config = ctx.accounts.nft_config.to_account_info();
+
+        stake_position.accrue(staking_index);
+        // Whatever the vault can't cover stays accrued for a later claim
+        let claimable = stake_position
+            .accrued_rewards
+            .min(ctx.accounts.reward_vault.amount);
+        require!(claimable > 0, ErrorCode::NothingToClaim);
+        stake_position.accrued_rewards -= claimable;
+
+        let seeds = &[
+            NFT_CONFIG_SEED,
+            nft_config_authority.as_ref(),
+            config_id.as_ref(),
+            &[ctx.bumps.nft_config],
+        ];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.user_redeemable_token_account.to_account_info(),
+                    authority: nft_config.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            claimable,
+        )?;
+
+        emit_cpi!(ClaimStakingRewardsEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            stake_position: stake_position.key(),
+            nft_type_index: stake_position.nft_type_index,
+            amount: claimable,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_tier_thresholds(
+        ctx: Context<SetTierThresholds>,
+        tiers: [TierConfig; TIER_COUNT],
+    ) -> Result<()> {
+        // Thresholds must increase from Bronze to Gold; a zero threshold disables the tier
+        let enabled: Vec<&TierConfig> = tiers.iter().filter(|t| t.units_threshold > 0).collect();
+        require!(
+            enabled
+                .windows(2)
+                .all(|w| w[0].units_threshold < w[1].units_threshold),
+            ErrorCode::InvalidTierThresholds
+        );
+
+        ctx.accounts.nft_config.load_mut()?.tiers = tiers.map(|tier| TierData::from(&tier));
+
+        emit_cpi!(SetTierThresholdsEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            tiers: tiers.to_vec(),
+        });
+
+        Ok(())
+    }
+
+    pub fn create_achievement(
+        ctx: Context<CreateAchievement>,
+        achievement_id: u64,
+        kind: AchievementKind,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(!name.is_empty(), ErrorCode::EmptyName);
+        require!(!symbol.is_empty(), ErrorCode::EmptySymbol);
+        require!(!uri.is_empty(), ErrorCode::EmptyURI);
+        require!(
+            name.len() <= MAX_NAME_LENGTH
+                && symbol.len() <= MAX_SYMBOL_LENGTH
+                && uri.len() <= MAX_URI_LENGTH,
+            ErrorCode::MetadataTooLong
+        );
+
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.nft_config = ctx.accounts.nft_config.key();
+        achievement.achievement_id = achievement_id;
+        achievement.kind = kind.clone();
+        achievement.name = name.clone();
+        achievement.symbol = symbol;
+        achievement.uri = uri;
+        achievement.claims = 0;
+
+        emit_cpi!(CreateAchievementEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            achievement: achievement.key(),
+            achievement_id,
+            kind,
+            name,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_achievement(ctx: Context<ClaimAchievement>) -> Result<()> {
+        let achievement = &ctx.accounts.achievement;
+        require!(
+            achievement.is_met_by(&ctx.accounts.user_record),
+            ErrorCode::AchievementNotEarned
+        );
+
+        let (nft_config_authority, config_id) = {
+            let nft_config = ctx.accounts.nft_config.load()?;
+            (nft_config.authority, nft_config.config_id_seed())
+        };
+        let seeds = &[
+            NFT_CONFIG_SEED,
+            nft_config_authority.as_ref(),
+            config_id.as_ref(),
+            &[ctx.bumps.nft_config],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        let nft_config = ctx.accounts.nft_config.to_account_info();
+
+        // Mint the single badge token before the master edition takes over the mint authority
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.badge_mint.to_account_info(),
+                    to: ctx.accounts.badge_token_account.to_account_info(),
+                    authority: nft_config.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+
+        // Create metadata for the badge
+        let cpi_accounts = CreateMetadataAccountsV3 {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            mint: ctx.accounts.badge_mint.to_account_info(),
+            mint_authority: nft_config.to_account_info(),
+            payer: ctx.accounts.user.to_account_info(),
+            update_authority: nft_config.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        let data = DataV2 {
+            name: achievement.name.clone(),
+            symbol: achievement.symbol.clone(),
+            uri: achievement.uri.clone(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+
+        create_metadata_accounts_v3(cpi_context, data, false, true, None)?;
+
+        // Create master edition (one-of-one, no prints)
+        let cpi_accounts = CreateMasterEditionV3 {
+            edition: ctx.accounts.master_edition.to_account_info(),
+            mint: ctx.accounts.badge_mint.to_account_info(),
+            update_authority: nft_config.to_account_info(),
+            mint_authority: nft_config.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            payer: ctx.accounts.user.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.token_metadata_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        create_master_edition_v3(cpi_context, Some(0))?;
+
+        let achievement_claim = &mut ctx.accounts.achievement_claim;
+        achievement_claim.achievement = achievement.key();
+        achievement_claim.user = ctx.accounts.user.key();
+        achievement_claim.badge_mint = ctx.accounts.badge_mint.key();
+        achievement_claim.claimed_at = Clock::get()?.unix_timestamp;
+
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.claims = achievement
+            .claims
+            .checked_add(1)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        emit_cpi!(ClaimAchievementEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            achievement: achievement.key(),
+            badge_mint: ctx.accounts.badge_mint.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn set_referral_config(
+        ctx: Context<SetReferralConfig>,
+        referral_bps: u16,
+        referral_cap: u64,
+    ) -> Result<()> {
+        require!(
+            referral_bps as u64 <= BASIS_POINTS_DENOMINATOR,
+            ErrorCode::InvalidReferralConfig
+        );
+
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        nft_config.referral_bps = referral_bps;
+        nft_config.referral_cap = referral_cap;
+        drop(nft_config);
+
+        emit_cpi!(SetReferralConfigEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            referral_bps,
+            referral_cap,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_referrer(ctx: Context<SetReferrer>, referrer: Pubkey) -> Result<()> {
+        require_keys_neq!(referrer, ctx.accounts.user.key(), ErrorCode::InvalidReferrer);
+
+        let nft_config_key = ctx.accounts.nft_config.key();
+        ctx.accounts.referrer_record.init_if_new(
+            nft_config_key,
+            referrer,
+            ctx.bumps.referrer_record,
+        );
+
+        let user_record = &mut ctx.accounts.user_record;
+        user_record.init_if_new(nft_config_key, ctx.accounts.user.key(), ctx.bumps.user_record);
+        require!(user_record.referrer.is_none(), ErrorCode::ReferrerAlreadySet);
+        user_record.referrer = Some(referrer);
+
+        emit_cpi!(SetReferrerEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            referrer,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_streak_bonus(
+        ctx: Context<SetStreakBonus>,
+        streak_bonus_bps_per_day: u16,
+        max_streak_bonus_bps: u16,
+    ) -> Result<()> {
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        nft_config.streak_bonus_bps_per_day = streak_bonus_bps_per_day;
+        nft_config.max_streak_bonus_bps = max_streak_bonus_bps;
+        drop(nft_config);
+
+        emit_cpi!(SetStreakBonusEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            streak_bonus_bps_per_day,
+            max_streak_bonus_bps,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_redemption_limits(
+        ctx: Context<SetRedemptionLimits>,
+        max_reward_per_user_per_epoch: u64,
+        max_reward_per_epoch: u64,
+    ) -> Result<()> {
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        nft_config.max_reward_per_user_per_epoch = max_reward_per_user_per_epoch;
+        nft_config.max_reward_per_epoch = max_reward_per_epoch;
+        drop(nft_config);
+
+        emit_cpi!(SetRedemptionLimitsEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            max_reward_per_user_per_epoch,
+            max_reward_per_epoch,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_campaign(
+        ctx: Context<CreateCampaign>,
+        campaign_id: u64,
+        start_ts: i64,
+        end_ts: i64,
+        eligible_type_indices: Vec<u8>,
+        multiplier_bps: u16,
+        budget: u64,
+    ) -> Result<()> {
+        require!(start_ts < end_ts, ErrorCode::InvalidCampaignWindow);
+        require!(
+            multiplier_bps as u64 >= BASIS_POINTS_DENOMINATOR,
+            ErrorCode::InvalidCampaignMultiplier
+        );
+        require!(budget > 0, ErrorCode::InvalidAmount);
+        require!(
+            !eligible_type_indices.is_empty()
+                && eligible_type_indices.len() <= MAX_CAMPAIGN_TYPES,
+            ErrorCode::InvalidCampaignTypes
+        );
+        let nft_type_count = ctx.accounts.nft_config.load()?.nft_type_count;
+        for index in eligible_type_indices.iter() {
+            require!(*index < nft_type_count, ErrorCode::InvalidNFTType);
+        }
+
+        let campaign = &mut ctx.accounts.campaign;
+        campaign.nft_config = ctx.accounts.nft_config.key();
+        campaign.campaign_id = campaign_id;
+        campaign.start_ts = start_ts;
+        campaign.end_ts = end_ts;
+        campaign.eligible_type_indices = eligible_type_indices.clone();
+        campaign.multiplier_bps = multiplier_bps;
+        campaign.budget = budget;
+        campaign.remaining_budget = budget;
+
+        emit_cpi!(CreateCampaignEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            campaign: campaign.key(),
+            campaign_id,
+            start_ts,
+            end_ts,
+            eligible_type_indices,
+            multiplier_bps,
+            budget,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_reward_legs(
+        ctx: Context<SetRewardLegs>,
+        nft_type_index: u8,
+        reward_legs: Vec<RewardLeg>,
+    ) -> Result<()> {
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let nft_type = nft_config.nft_type_mut(nft_type_index)?;
+        require!(
+            reward_legs.len() <= MAX_REWARD_LEGS,
+            ErrorCode::TooManyRewardLegs
+        );
+        require!(
+            reward_legs.iter().all(|leg| leg.amount() > 0),
+            ErrorCode::InvalidRewardAmount
+        );
+
+        nft_type.set_reward_legs(&reward_legs)?;
+        drop(nft_config);
+
+        emit_cpi!(SetRewardLegsEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            nft_type_index,
+            reward_legs,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_reward_vault(ctx: Context<CreateRewardVault>) -> Result<()> {
+        emit_cpi!(CreateRewardVaultEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.authority.key())?,
+            vault: ctx.accounts.reward_vault.key(),
+            mint: ctx.accounts.reward_mint.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn redeem_and_burn_nft_multi<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RedeemAndBurnNFTMulti<'info>>,
+        amount: u64,
+        min_reward_out: Vec<u64>,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        ctx.accounts.user_record.init_if_new(
+            ctx.accounts.nft_config.key(),
+            ctx.accounts.user.key(),
+            ctx.bumps.user_record,
+        );
+
+        let mut nft_config = ctx.accounts.nft_config.load_mut()?;
+        let nft_type_index =
+            find_nft_type_index(&nft_config, &ctx.accounts.metadata, &ctx.accounts.nft_mint.key())?;
+        require_type_mint(
+            &ctx.accounts.nft_config.key(),
+            nft_type_index,
+            &ctx.accounts.nft_mint.key(),
+        )?;
+        let reward_legs = nft_config.nft_types[nft_type_index].reward_legs();
+        require!(!reward_legs.is_empty(), ErrorCode::RewardAmountNotSet);
+
+        // One minimum per leg, so a change to the legs after quoting is caught too
+        require!(
+            min_reward_out.len() == reward_legs.len(),
+            ErrorCode::RewardLegCountMismatch
+        );
+        let mut payouts = Vec::with_capacity(reward_legs.len());
+        let mut redeemable_reward: u64 = 0;
+        for (leg, min_out) in reward_legs.iter().zip(min_reward_out) {
+            let leg_amount = leg
+                .amount()
+                .checked_mul(amount)
+                .ok_or(ErrorCode::ArithmeticOverflow)?;
+            require!(leg_amount >= min_out, ErrorCode::RewardBelowMinimum);
+
+            payouts.push(match leg {
+                RewardLeg::Token { mint, .. } => {
+                    // The epoch limits are in redeemable tokens
+                    if *mint == ctx.accounts.redeemable_mint.key() {
+                        redeemable_reward = redeemable_reward
+                            .checked_add(leg_amount)
+                            .ok_or(ErrorCode::ArithmeticOverflow)?;
+                    }
+                    RewardLeg::Token {
+                        mint: *mint,
+                        amount: leg_amount,
+                    }
+                }
+                RewardLeg::Lamports { .. } => RewardLeg::Lamports { amount: leg_amount },
+            });
+        }
+        let user_record = &mut ctx.accounts.user_record;
+        consume_epoch_limits(&mut nft_config, user_record, redeemable_reward)?;
+        if let Some(tier_upgraded) = record_redemption(
+            &mut nft_config,
+            user_record,
+            1 << nft_type_index,
+            amount,
+            redeemable_reward,
+        )? {
+            emit_cpi!(tier_upgraded);
+        }
+
+        let nft_config_authority = nft_config.authority;
+        let config_id = nft_config.config_id_seed();
+        // The config signs the token leg transfers, so it can't stay borrowed
+        drop(nft_config);
+        let nft_config = ctx.accounts.nft_config.to_account_info();
+
+        // Burn the NFTs
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.nft_mint.to_account_info(),
+                    from: ctx.accounts.nft_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let nft_config_key = nft_config.key();
+        let config_seeds = &[
+            NFT_CONFIG_SEED,
+            nft_config_authority.as_ref(),
+            config_id.as_ref(),
+            &[ctx.bumps.nft_config],
+        ];
+        let lamport_vault_seeds = &[
+            LAMPORT_VAULT_SEED,
+            nft_config_key.as_ref(),
+            &[ctx.bumps.lamport_vault],
+        ];
+
+        // Token legs take a (vault, user token account) pair from remaining_accounts, in order
+        let mut remaining_accounts = ctx.remaining_accounts.iter();
+
+        for payout in payouts.iter() {
+            match payout {
+                RewardLeg::Token { mint, amount } => {
+                    let vault_info = remaining_accounts
+                        .next()
+                        .ok_or(ErrorCode::MissingRewardLegAccounts)?;
+                    let user_token_info = remaining_accounts
+                        .next()
+                        .ok_or(ErrorCode::MissingRewardLegAccounts)?;
+
+                    let vault = Account::<TokenAccount>::try_from(vault_info)?;
+                    require_keys_eq!(vault.mint, *mint, ErrorCode::InvalidRewardVault);
+                    require_keys_eq!(vault.owner, nft_config_key, ErrorCode::InvalidRewardVault);
+
+                    let user_token_account = Account::<TokenAccount>::try_from(user_token_info)?;
+                    require_keys_eq!(
+                        user_token_account.mint,
+                        *mint,
+                        ErrorCode::InvalidRewardTokenAccount
+                    );
+                    require_keys_eq!(
+                        user_token_account.owner,
+                        ctx.accounts.user.key(),
+                        ErrorCode::InvalidRewardTokenAccount
+                    );
+
+                    token::transfer(
+                        CpiContext::new_with_signer(
+                            ctx.accounts.token_program.to_account_info(),
+                            token::Transfer {
+                                from: vault_info.clone(),
+                                to: user_token_info.clone(),
+                                authority: nft_config.to_account_info(),
+                            },
+                            &[&config_seeds[..]],
+                        ),
+                        *amount,
+                    )?;
+                }
+                RewardLeg::Lamports { amount } => {
+                    system_program::transfer(
+                        CpiContext::new_with_signer(
+                            ctx.accounts.system_program.to_account_info(),
+                            system_program::Transfer {
+                                from: ctx.accounts.lamport_vault.to_account_info(),
+                                to: ctx.accounts.user.to_account_info(),
+                            },
+                            &[&lamport_vault_seeds[..]],
+                        ),
+                        *amount,
+                    )?;
+                }
+            }
+        }
+
+        emit_cpi!(RedeemAndBurnNFTMultiEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.user.key())?,
+            nft_mint: ctx.accounts.nft_mint.key(),
+            nft_type_index: nft_type_index as u8,
+            amount_burned: amount,
+            payouts,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_sponsor_pool(ctx: Context<CreateSponsorPool>) -> Result<()> {
+        let sponsor_pool = &mut ctx.accounts.sponsor_pool;
+        sponsor_pool.nft_config = ctx.accounts.nft_config.key();
+        sponsor_pool.sponsor = ctx.accounts.sponsor.key();
+        sponsor_pool.mint = ctx.accounts.sponsor_mint.key();
+        sponsor_pool.vault = ctx.accounts.sponsor_vault.key();
+        sponsor_pool.payout_rates = [0; MAX_NFT_TYPES];
+        sponsor_pool.bump = ctx.bumps.sponsor_pool;
+
+        emit_cpi!(CreateSponsorPoolEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.sponsor.key())?,
+            sponsor_pool: sponsor_pool.key(),
+            mint: sponsor_pool.mint,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_sponsor_payout_rate(
+        ctx: Context<SetSponsorPayoutRate>,
+        nft_type_index: u8,
+        payout_rate: u64,
+    ) -> Result<()> {
+        require!(
+            nft_type_index < ctx.accounts.nft_config.load()?.nft_type_count,
+            ErrorCode::InvalidNFTType
+        );
+
+        ctx.accounts.sponsor_pool.payout_rates[nft_type_index as usize] = payout_rate;
+
+        emit_cpi!(SetSponsorPayoutRateEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.sponsor.key())?,
+            sponsor_pool: ctx.accounts.sponsor_pool.key(),
+            nft_type_index,
+            payout_rate,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_sponsor_tokens(ctx: Context<DepositSponsorTokens>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.sponsor_token_account.to_account_info(),
+                    to: ctx.accounts.sponsor_vault.to_account_info(),
+                    authority: ctx.accounts.sponsor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit_cpi!(SponsorDepositEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.sponsor.key())?,
+            sponsor_pool: ctx.accounts.sponsor_pool.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_sponsor_tokens(ctx: Context<WithdrawSponsorTokens>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let sponsor_pool = &ctx.accounts.sponsor_pool;
+        let seeds = &[
+            SPONSOR_POOL_SEED,
+            sponsor_pool.nft_config.as_ref(),
+            sponsor_pool.sponsor.as_ref(),
+            sponsor_pool.mint.as_ref(),
+            &[sponsor_pool.bump],
+        ];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.sponsor_vault.to_account_info(),
+                    to: ctx.accounts.sponsor_token_account.to_account_info(),
+                    authority: sponsor_pool.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+
+        emit_cpi!(SponsorWithdrawEvent {
+            header: event_header(&ctx.accounts.nft_config, ctx.accounts.sponsor.key())?,
+            sponsor_pool: sponsor_pool.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>, config_id: u64) -> Result<()> {
+        let nft_config_info = ctx.accounts.nft_config.to_account_info();
+
+        // Only the originally deployed layout predates the zero-copy one
+        let authority = ctx.accounts.authority.key();
+        let old_config = {
+            let data = nft_config_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == NFTConfig::DISCRIMINATOR,
+                ErrorCode::InvalidConfigAccount
+            );
+            require!(
+                data.len() == NFTConfigV1::space(),
+                ErrorCode::ConfigAlreadyMigrated
+            );
+            NFTConfigV1::deserialize(&mut &data[8..])
+                .map_err(|_| ErrorCode::InvalidConfigAccount)?
+        };
+
+        // v1 configs predate config_id and keep their address, so they migrate with config_id 0
+        require!(
+            old_config.authority == authority && config_id == 0,
+            ErrorCode::InvalidConfigAccount
+        );
+        let (config_address, _) =
+            Pubkey::find_program_address(&[NFT_CONFIG_SEED, authority.as_ref()], ctx.program_id);
+        require_keys_eq!(
+            nft_config_info.key(),
+            config_address,
+            ErrorCode::InvalidConfigAccount
+        );
+
+        // Resize the account to the zero-copy layout, topping up rent from the authority
+        let new_len = NFTConfig::space();
+        let rent = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(nft_config_info.lamports());
+        if rent > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: nft_config_info.clone(),
+                    },
+                ),
+                rent,
+            )?;
+        }
+        nft_config_info.realloc(new_len, false)?;
+
+        // Rewrite it in place; the old Borsh bytes must not leak into the new fields
+        let header = {
+            let mut data = nft_config_info.try_borrow_mut_data()?;
+            data.fill(0);
+            data[..8].copy_from_slice(&NFTConfig::DISCRIMINATOR);
+            let nft_config: &mut NFTConfig = bytemuck::from_bytes_mut(&mut data[8..new_len]);
+            nft_config.copy_from_v1(&old_config);
+            nft_config.event_header(nft_config_info.key(), ctx.accounts.authority.key())?
+        };
+
+        emit_cpi!(MigrateConfigEvent {
+            header,
+            old_version: 1,
+            new_version: NFT_CONFIG_VERSION,
+        });
+
+        Ok(())
+    }
+}
+
+// Create a program-derived account the way Anchor's init constraint does. create_account
+// fails on an address that already holds lamports, and anyone can send lamports to a PDA, so
+// a funded address is topped up to rent exemption, allocated and assigned instead
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+
+    let top_up = rent.saturating_sub(account.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        owner,
+    )
+}
+
+// Header for the next event of a config, taking its sequence number
+fn event_header(nft_config: &AccountLoader<NFTConfig>, actor: Pubkey) -> Result<EventHeader> {
+    nft_config
+        .load_mut()?
+        .event_header(nft_config.key(), actor)
+}
+
+// Resolve the NFT type of a mint by matching its metadata name and uri against the config
+fn find_nft_type_index(
+    nft_config: &NFTConfig,
+    metadata_info: &AccountInfo,
+    nft_mint: &Pubkey,
+) -> Result<usize> {
+    // Deserialize the metadata account
+    let metadata = MetadataAccount::try_deserialize(&mut &metadata_info.data.borrow()[..])
+        .map_err(|_| ErrorCode::InvalidMetadata)?;
+    require_keys_eq!(metadata.mint, *nft_mint, ErrorCode::InvalidMetadata);
+
+    // Extract name and uri
+    let name = metadata.name.trim_end_matches('\0');
+    let uri = metadata.uri.trim_end_matches('\0');
+
+    // Find the matching NFT type
+    nft_config
+        .nft_types()
+        .iter()
+        .position(|t| t.name() == name && t.uri() == uri)
+        .ok_or(ErrorCode::InvalidNFTType.into())
+}
+
+// Only the mint this program created for a type counts as that type, since anyone can
+// create a mint with metadata copied from a configured type
+fn require_type_mint(nft_config: &Pubkey, nft_type_index: usize, nft_mint: &Pubkey) -> Result<()> {
+    let (type_mint, _) = Pubkey::find_program_address(
+        &[NFT_MINT_SEED, nft_config.as_ref(), &[nft_type_index as u8]],
+        &crate::ID,
+    );
+    require_keys_eq!(*nft_mint, type_mint, ErrorCode::InvalidNFTMint);
+
+    Ok(())
+}
+
+// Reset whichever epoch counters still belong to an earlier epoch
+fn start_epoch(nft_config: &mut NFTConfig, user_record: &mut UserRecord) -> Result<()> {
+    let epoch = Clock::get()?.epoch;
+    if nft_config.current_epoch != epoch {
+        nft_config.current_epoch = epoch;
+        nft_config.epoch_rewarded = 0;
+    }
+    if user_record.epoch != epoch {
+        user_record.epoch = epoch;
+        user_record.epoch_rewarded = 0;
+    }
+
+    Ok(())
+}
+
+// Charge `reward` against the per-user and global limits for the current epoch
+fn consume_epoch_limits(
+    nft_config: &mut NFTConfig,
+    user_record: &mut UserRecord,
+    reward: u64,
+) -> Result<()> {
+    start_epoch(nft_config, user_record)?;
+    user_record.epoch_rewarded = consume_allowance(
+        nft_config.max_reward_per_user_per_epoch,
+        user_record.epoch_rewarded,
+        reward,
+    )?;
+    nft_config.epoch_rewarded = consume_allowance(
+        nft_config.max_reward_per_epoch,
+        nft_config.epoch_rewarded,
+        reward,
+    )?;
+
+    Ok(())
+}
+
+// Add a redemption to the user's lifetime totals and the set of types they have redeemed
+fn record_redemption(
+    nft_config: &mut NFTConfig,
+    user_record: &mut UserRecord,
+    types_mask: u64,
+    units: u64,
+    reward: u64,
+) -> Result<Option<TierUpgraded>> {
+    user_record.lifetime_units_redeemed = user_record
+        .lifetime_units_redeemed
+        .checked_add(units)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    user_record.lifetime_rewarded = user_record
+        .lifetime_rewarded
+        .checked_add(reward)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    user_record.redeemed_types_mask |= types_mask;
+
+    apply_tier_upgrade(nft_config, user_record)
+}
+
+// Move the user up a tier once their lifetime units cross a threshold, returning the
+// event to emit when they do
+fn apply_tier_upgrade(
+    nft_config: &mut NFTConfig,
+    user_record: &mut UserRecord,
+) -> Result<Option<TierUpgraded>> {
+    let new_tier = nft_config.tier_for(user_record.lifetime_units_redeemed);
+    if new_tier <= user_record.tier {
+        return Ok(None);
+    }
+
+    let tier_upgraded = TierUpgraded {
+        header: nft_config.event_header(user_record.nft_config, user_record.user)?,
+        old_tier: user_record.tier,
+        new_tier,
+        lifetime_units_redeemed: user_record.lifetime_units_redeemed,
+    };
+    user_record.tier = new_tier;
+
+    Ok(Some(tier_upgraded))
+}
+
+// Pay the referrer's share of `reward` from the vault, returning the amount paid. Only
+// users with a registered referrer pay one, and they must pass the referrer's accounts
+fn pay_referral<'info>(
+    nft_config: &AccountLoader<'info, NFTConfig>,
+    referrer: Option<Pubkey>,
+    referrer_accounts: Option<(&mut Account<'info, UserRecord>, &Account<'info, TokenAccount>)>,
+    vault: &Account<'info, TokenAccount>,
+    authority: &Signer<'info>,
+    token_program: &Program<'info, Token>,
+    reward: u64,
+) -> Result<u64> {
+    let Some(referrer) = referrer else {
+        return Ok(0);
+    };
+    let Some((referrer_record, referrer_token_account)) = referrer_accounts else {
+        return err!(ErrorCode::ReferrerAccountsRequired);
+    };
+    require_keys_eq!(referrer_record.user, referrer, ErrorCode::InvalidReferrer);
+    require_keys_eq!(
+        referrer_record.nft_config,
+        nft_config.key(),
+        ErrorCode::InvalidReferrer
+    );
+    require_keys_eq!(
+        referrer_token_account.owner,
+        referrer,
+        ErrorCode::InvalidReferrer
+    );
+    require_keys_eq!(
+        referrer_token_account.mint,
+        vault.mint,
+        ErrorCode::InvalidReferrer
+    );
+
+    let mut config = nft_config.load_mut()?;
+    let referral_reward = config.referral_reward(reward, referrer_record.referral_earned)?;
+    let referral_reward = consume_referral_limits(&mut config, referrer_record, referral_reward)?;
+    drop(config);
+
+    if referral_reward > 0 {
+        referrer_record.referral_earned = referrer_record
+            .referral_earned
+            .checked_add(referral_reward)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+        token::transfer(
+            CpiContext::new(
+                token_program.to_account_info(),
+                token::Transfer {
+                    from: vault.to_account_info(),
+                    to: referrer_token_account.to_account_info(),
+                    authority: authority.to_account_info(),
+                },
+            ),
+            referral_reward,
+        )?;
+    }
+
+    Ok(referral_reward)
+}
+
+// Pay the sponsor tokens for the redeemed `(type index, units)` pairs from the pool vault,
+// if a sponsor pool was passed in. The payout is capped by what is left in the vault
+fn pay_sponsor<'info>(
+    sponsor_pool: Option<&Account<'info, SponsorPool>>,
+    sponsor_vault: Option<&Account<'info, TokenAccount>>,
+    user_sponsor_token_account: Option<&Account<'info, TokenAccount>>,
+    user: &Pubkey,
+    token_program: &Program<'info, Token>,
+    redeemed: &[(usize, u64)],
+) -> Result<u64> {
+    let (sponsor_pool, sponsor_vault, user_sponsor_token_account) =
+        match (sponsor_pool, sponsor_vault, user_sponsor_token_account) {
+            (Some(pool), Some(vault), Some(user_account)) => (pool, vault, user_account),
+            (None, None, None) => return Ok(0),
+            _ => return err!(ErrorCode::IncompleteSponsorAccounts),
+        };
+    require_keys_eq!(
+        sponsor_vault.key(),
+        sponsor_pool.vault,
+        ErrorCode::InvalidSponsorVault
+    );
+    require_keys_eq!(
+        user_sponsor_token_account.mint,
+        sponsor_pool.mint,
+        ErrorCode::InvalidSponsorTokenAccount
+    );
+    require_keys_eq!(
+        user_sponsor_token_account.owner,
+        *user,
+        ErrorCode::InvalidSponsorTokenAccount
+    );
+
+    let mut sponsor_reward: u64 = 0;
+    for &(nft_type_index, units) in redeemed {
+        let payout = sponsor_pool
+            .payout_rates
+            .get(nft_type_index)
+            .copied()
+            .unwrap_or(0)
+            .checked_mul(units)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        sponsor_reward = sponsor_reward
+            .checked_add(payout)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+    }
+    let sponsor_reward = sponsor_reward.min(sponsor_vault.amount);
+
+    if sponsor_reward > 0 {
+        let nft_config_key = sponsor_pool.nft_config;
+        let seeds = &[
+            SPONSOR_POOL_SEED,
+            nft_config_key.as_ref(),
+            sponsor_pool.sponsor.as_ref(),
+            sponsor_pool.mint.as_ref(),
+            &[sponsor_pool.bump],
+        ];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                token::Transfer {
+                    from: sponsor_vault.to_account_info(),
+                    to: user_sponsor_token_account.to_account_info(),
+                    authority: sponsor_pool.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            sponsor_reward,
+        )?;
+    }
+
+    Ok(sponsor_reward)
+}
+
+// Charge a referral payout to the referrer's and the global limits. Like the lifetime
+// referral cap, running out of allowance shrinks the payout instead of failing the redemption
+fn consume_referral_limits(
+    nft_config: &mut NFTConfig,
+    referrer_record: &mut UserRecord,
+    reward: u64,
+) -> Result<u64> {
+    start_epoch(nft_config, referrer_record)?;
+    let reward = reward
+        .min(remaining_allowance(
+            nft_config.max_reward_per_user_per_epoch,
+            referrer_record.epoch_rewarded,
+        ))
+        .min(remaining_allowance(
+            nft_config.max_reward_per_epoch,
+            nft_config.epoch_rewarded,
+        ));
+    consume_epoch_limits(nft_config, referrer_record, reward)?;
+
+    Ok(reward)
+}
+
+fn remaining_allowance(limit: u64, used: u64) -> u64 {
+    if limit == 0 {
+        u64::MAX
+    } else {
+        limit.saturating_sub(used)
+    }
+}
+
+// Add `amount` to the `used` allowance, failing if it would exceed `limit` (0 means unlimited)
+fn consume_allowance(limit: u64, used: u64, amount: u64) -> Result<u64> {
+    let new_used = used
+        .checked_add(amount)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    if limit > 0 && new_used > limit {
+        msg!(
+            "Redemption limit exceeded: remaining allowance {}",
+            limit.saturating_sub(used)
+        );
+        return err!(ErrorCode::RedemptionLimitExceeded);
+    }
+
+    Ok(new_used)
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(config_id: u64)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = NFTConfig::space(),
+        seeds = [NFT_CONFIG_SEED, authority.key().as_ref(), &config_id.to_le_bytes()],
+        bump
+    )]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(nft_type_index: u8, amount: u64)]
+pub struct MintNFT<'info> {
+    #[account(mut)]
+    pub minter: Signer<'info>,
+
+    #[account(
+        init,
+        payer = minter,
+        mint::decimals = 0,
+        mint::authority = minter.key(),
+        mint::freeze_authority = minter.key(),
+        seeds = [NFT_MINT_SEED, nft_config.key().as_ref(), &[nft_type_index]],
+        bump
+    )]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = minter,
+        associated_token::mint = mint,
+        associated_token::authority = minter,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            NFT_CONFIG_SEED,
+            nft_config.load()?.authority.as_ref(),
+            &nft_config.load()?.config_id_seed()
+        ],
+        bump
+    )]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub token_metadata_program: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = minter,
+        space = UserRecord::space(),
+        seeds = [USER_RECORD_SEED, nft_config.key().as_ref(), minter.key().as_ref()],
+        bump
+    )]
+    pub user_record: Account<'info, UserRecord>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MintNFTBatch<'info> {
+    #[account(mut)]
+    pub minter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            NFT_CONFIG_SEED,
+            nft_config.load()?.authority.as_ref(),
+            &nft_config.load()?.config_id_seed()
+        ],
+        bump
+    )]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+
+    #[account(
+        init_if_needed,
+        payer = minter,
+        space = UserRecord::space(),
+        seeds = [USER_RECORD_SEED, nft_config.key().as_ref(), minter.key().as_ref()],
+        bump
+    )]
+    pub user_record: Account<'info, UserRecord>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateRedeemableToken<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 9,
+        mint::authority = authority.key(),
+        seeds = [REDEEMABLE_MINT_SEED, nft_config.key().as_ref()],
+        bump
+    )]
+    pub redeemable_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = redeemable_mint,
+        associated_token::authority = authority,
+    )]
+    pub redeemable_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [
+            NFT_CONFIG_SEED,
+            authority.key().as_ref(),
+            &nft_config.load()?.config_id_seed()
+        ],
+        bump,
+        constraint = nft_config.load()?.authority == authority.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct RedeemAndBurnNFT<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = user,
+    )]
+    pub nft_token_account: Account<'info, TokenAccount>,
+    #[account(seeds = [REDEEMABLE_MINT_SEED, nft_config.key().as_ref()], bump)]
+    pub redeemable_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = redeemable_mint,
+        associated_token::authority = authority,
+    )]
+    pub redeemable_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = redeemable_mint,
+        associated_token::authority = user,
+    )]
+    pub user_redeemable_token_account: Account<'info, TokenAccount>,
+    // Owner of the vault, co-signing the payout
+    #[account(address = nft_config.load()?.authority @ ErrorCode::UnauthorizedAccess)]
+    pub authority: Signer<'info>,
+    /// CHECK: This account is used for metadata verification
+    pub metadata: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserRecord::space(),
+        seeds = [USER_RECORD_SEED, nft_config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_record: Account<'info, UserRecord>,
+    #[account(
+        init,
+        payer = user,
+        space = VestingPosition::space(),
+        seeds = [
+            VESTING_POSITION_SEED,
+            nft_config.key().as_ref(),
+            user.key().as_ref(),
+            &user_record.vesting_positions.to_le_bytes()
+        ],
+        bump
+    )]
+    pub vesting_position: Option<Account<'info, VestingPosition>>,
+    #[account(
+        init,
+        payer = user,
+        token::mint = redeemable_mint,
+        token::authority = vesting_position,
+        seeds = [
+            VESTING_VAULT_SEED,
+            nft_config.key().as_ref(),
+            user.key().as_ref(),
+            &user_record.vesting_positions.to_le_bytes()
+        ],
+        bump
+    )]
+    pub vesting_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = user @ ErrorCode::UnauthorizedAccess,
+        has_one = nft_mint @ ErrorCode::InvalidStakePosition,
+        has_one = nft_config @ ErrorCode::InvalidStakePosition
+    )]
+    pub stake_position: Option<Account<'info, StakePosition>>,
+    #[account(mut)]
+    pub stake_escrow: Option<Account<'info, TokenAccount>>,
+    #[account(mut, has_one = nft_config @ ErrorCode::CampaignConfigMismatch)]
+    pub campaign: Option<Account<'info, Campaign>>,
+    #[account(has_one = nft_config @ ErrorCode::SponsorPoolConfigMismatch)]
+    pub sponsor_pool: Option<Account<'info, SponsorPool>>,
+    #[account(mut)]
+    pub sponsor_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_sponsor_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub referrer_record: Option<Account<'info, UserRecord>>,
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RedeemAndBurnBatch<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(seeds = [REDEEMABLE_MINT_SEED, nft_config.key().as_ref()], bump)]
+    pub redeemable_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = redeemable_mint,
+        associated_token::authority = authority,
+    )]
+    pub redeemable_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = redeemable_mint,
+        associated_token::authority = user,
+    )]
+    pub user_redeemable_token_account: Account<'info, TokenAccount>,
+    // Owner of the vault, co-signing the payout
+    #[account(
+        constraint = nft_config.load()?.authority == authority.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserRecord::space(),
+        seeds = [USER_RECORD_SEED, nft_config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_record: Account<'info, UserRecord>,
+    #[account(mut, has_one = nft_config @ ErrorCode::CampaignConfigMismatch)]
+    pub campaign: Option<Account<'info, Campaign>>,
+    #[account(has_one = nft_config @ ErrorCode::SponsorPoolConfigMismatch)]
+    pub sponsor_pool: Option<Account<'info, SponsorPool>>,
+    #[account(mut)]
+    pub sponsor_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_sponsor_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub referrer_record: Option<Account<'info, UserRecord>>,
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account(zero_copy)]
+pub struct NFTConfig {
+    pub authority: Pubkey,
+    // Lets one authority run several isolated configs
+    pub config_id: u64,
+    // Redemption limits in redeemable tokens per epoch (0 means unlimited)
+    pub max_reward_per_user_per_epoch: u64,
+    pub max_reward_per_epoch: u64,
+    pub current_epoch: u64,
+    pub epoch_rewarded: u64,
+    // Lifetime cap on referral rewards per referrer
+    pub referral_cap: u64,
+    // Number of events emitted so far; each event carries its position as `seq`
+    pub event_seq: u64,
+    // Bronze, Silver and Gold thresholds and boosts
+    pub tiers: [TierData; TIER_COUNT],
+    // Only the first nft_type_count entries are in use
+    pub nft_types: [NFTTypeData; MAX_NFT_TYPES],
+    // Share of each redemption paid to the referrer
+    pub referral_bps: u16,
+    // Bonus per consecutive minting day, capped at max_streak_bonus_bps
+    pub streak_bonus_bps_per_day: u16,
+    pub max_streak_bonus_bps: u16,
+    pub max_nft_types: u8,
+    pub nft_type_count: u8,
+    // Account layout version, upgraded by migrate_config
+    pub version: u8,
+    // 1 for a config created before config_id existed, which still lives at
+    // [NFT_CONFIG_SEED, authority]
+    pub legacy_address: u8,
+    pub padding: [u8; 6],
+    // Zeroed space for future fields, so adding one does not need a realloc
+    pub reserved: [u8; 64],
+}
+
+impl NFTConfig {
+    // Calculate the total space required for the NFTConfig account
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes) + the fixed-size layout
+        8 + std::mem::size_of::<NFTConfig>()
+    }
+
+    // The NFT types added so far
+    pub fn nft_types(&self) -> &[NFTTypeData] {
+        &self.nft_types[..self.nft_type_count as usize]
+    }
+
+    pub fn nft_type(&self, nft_type_index: u8) -> Result<&NFTTypeData> {
+        self.nft_types()
+            .get(nft_type_index as usize)
+            .ok_or(error!(ErrorCode::InvalidNFTType))
+    }
+
+    pub fn nft_type_mut(&mut self, nft_type_index: u8) -> Result<&mut NFTTypeData> {
+        self.nft_types[..self.nft_type_count as usize]
+            .get_mut(nft_type_index as usize)
+            .ok_or(error!(ErrorCode::InvalidNFTType))
+    }
+
+    // Header for the next event, taking its sequence number
+    pub fn event_header(&mut self, nft_config: Pubkey, actor: Pubkey) -> Result<EventHeader> {
+        let clock = Clock::get()?;
+        Ok(EventHeader {
+            nft_config,
+            actor,
+            seq: self.next_event_seq(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        })
+    }
+
+    // Last seed of the config address. An empty seed adds nothing to the address hash, so
+    // legacy configs keep resolving to [NFT_CONFIG_SEED, authority]
+    pub fn config_id_seed(&self) -> Vec<u8> {
+        if self.legacy_address != 0 {
+            Vec::new()
+        } else {
+            self.config_id.to_le_bytes().to_vec()
+        }
+    }
+
+    // Copy an NFTConfig stored in the original Borsh layout into this one, which must be
+    // zeroed. Everything added since v1 starts out unset
+    pub fn copy_from_v1(&mut self, v1: &NFTConfigV1) {
+        self.authority = v1.authority;
+        self.max_nft_types = v1.max_nft_types;
+        for (nft_type, old) in self.nft_types.iter_mut().zip(v1.nft_types.iter()) {
+            *nft_type = NFTTypeData::from(old);
+        }
+        self.nft_type_count = v1.nft_types.len().min(MAX_NFT_TYPES) as u8;
+        // v1 configs have no config_id and still live at [NFT_CONFIG_SEED, authority]
+        self.legacy_address = 1;
+        self.version = NFT_CONFIG_VERSION;
+    }
+
+    // Sequence number for the next event, so indexers can order events and spot gaps
+    pub fn next_event_seq(&mut self) -> u64 {
+        let seq = self.event_seq;
+        self.event_seq += 1;
+        seq
+    }
+
+    pub fn streak_bonus(&self, streak: u32, base_reward: u64) -> Result<u64> {
+        let bonus_bps = (streak as u64)
+            .saturating_mul(self.streak_bonus_bps_per_day as u64)
+            .min(self.max_streak_bonus_bps as u64);
+
+        let bonus = (base_reward as u128)
+            .checked_mul(bonus_bps as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            / BASIS_POINTS_DENOMINATOR as u128;
+
+        u64::try_from(bonus).map_err(|_| ErrorCode::ArithmeticOverflow.into())
+    }
+
+    // The referrer's share of `total_reward`, limited to what is left of their cap
+    pub fn referral_reward(&self, total_reward: u64, referral_earned: u64) -> Result<u64> {
+        let share = (total_reward as u128)
+            .checked_mul(self.referral_bps as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            / BASIS_POINTS_DENOMINATOR as u128;
+
+        Ok((share as u64).min(self.referral_cap.saturating_sub(referral_earned)))
+    }
+
+    // Highest tier whose threshold is met by the given lifetime units
+    pub fn tier_for(&self, lifetime_units: u64) -> RecyclingTier {
+        let tiers = [
+            RecyclingTier::Bronze,
+            RecyclingTier::Silver,
+            RecyclingTier::Gold,
+        ];
+
+        tiers
+            .into_iter()
+            .zip(self.tiers.iter())
+            .filter(|(_, t)| t.units_threshold > 0 && lifetime_units >= t.units_threshold)
+            .map(|(tier, _)| tier)
+            .next_back()
+            .unwrap_or(RecyclingTier::None)
+    }
+
+    pub fn tier_boost(&self, tier: RecyclingTier, base_reward: u64) -> Result<u64> {
+        let boost_bps = match tier {
+            RecyclingTier::None => return Ok(0),
+            RecyclingTier::Bronze => self.tiers[0].boost_bps,
+            RecyclingTier::Silver => self.tiers[1].boost_bps,
+            RecyclingTier::Gold => self.tiers[2].boost_bps,
+        };
+
+        let boost = (base_reward as u128)
+            .checked_mul(boost_bps as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            / BASIS_POINTS_DENOMINATOR as u128;
+
+        u64::try_from(boost).map_err(|_| ErrorCode::ArithmeticOverflow.into())
+    }
+}
+
+// NFTConfig as originally deployed, before config_id namespacing. These accounts live at
+// [NFT_CONFIG_SEED, authority] and were allocated for 10 NFT types
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct NFTConfigV1 {
+    pub authority: Pubkey,
+    pub max_nft_types: u8,
+    pub nft_types: Vec<NFTTypeV1>,
+}
+
+impl NFTConfigV1 {
+    // Space the original Initialize allocated
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes)
+        8 +
+        // Pubkey (32 bytes)
+        32 +
+        // max_nft_types (1 byte)
+        1 +
+        // Vec discriminator (4 bytes for storing length)
+        4 +
+        // Space for 10 NFT types
+        10 * NFTTypeV1::space()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct NFTTypeV1 {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub reward_amount: Option<u64>,
+}
+
+impl NFTTypeV1 {
+    pub const fn space() -> usize {
+        // name, symbol and uri: 4 bytes for length + content each
+        4 + MAX_NAME_LENGTH + 4 + MAX_SYMBOL_LENGTH + 4 + MAX_URI_LENGTH +
+        // Option<u64>: 1 byte for discriminator + 8 bytes for u64
+        9
+    }
+}
+
+impl From<&NFTTypeV1> for NFTTypeData {
+    fn from(v1: &NFTTypeV1) -> Self {
+        NFTTypeData::from(&NFTType {
+            name: v1.name.clone(),
+            symbol: v1.symbol.clone(),
+            uri: v1.uri.clone(),
+            reward_amount: v1.reward_amount,
+            ..NFTType::default()
+        })
+    }
+}
+
+// What one NFT mint contributed to a redeem_and_burn_batch, before boosts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct RedeemBatchEntry {
+    pub nft_mint: Pubkey,
+    pub nft_type_index: u8,
+    pub amount_burned: u64,
+    pub reward_amount: u64,
+}
+
+// One NFT type to mint in mint_nft_batch. The master edition limits every type's mint to a
+// single NFT, so there is no amount
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct MintBatchEntry {
+    pub nft_type_index: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct TierConfig {
+    pub units_threshold: u64,
+    pub boost_bps: u16,
+}
+
+impl TierConfig {
+    // Calculate space for a TierConfig
+    pub const fn space() -> usize {
+        // units_threshold (8 bytes) + boost_bps (2 bytes)
+        8 + 2
+    }
+}
+
+// TierConfig as stored in the zero-copy NFTConfig
+#[zero_copy]
+pub struct TierData {
+    pub units_threshold: u64,
+    pub boost_bps: u16,
+    pub padding: [u8; 6],
+}
+
+impl From<&TierConfig> for TierData {
+    fn from(tier: &TierConfig) -> Self {
+        TierData {
+            units_threshold: tier.units_threshold,
+            boost_bps: tier.boost_bps,
+            padding: [0; 6],
+        }
+    }
+}
+
+impl From<&TierData> for TierConfig {
+    fn from(tier: &TierData) -> Self {
+        TierConfig {
+            units_threshold: tier.units_threshold,
+            boost_bps: tier.boost_bps,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RecyclingTier {
+    #[default]
+    None,
+    Bronze,
+    Silver,
+    Gold,
+}
+
+#[account]
+pub struct UserRecord {
+    pub nft_config: Pubkey,
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub epoch_rewarded: u64,
+    pub bump: u8,
+    // Number of vesting positions created, used to seed the next one
+    pub vesting_positions: u64,
+    pub lifetime_units_redeemed: u64,
+    pub lifetime_rewarded: u64,
+    pub tier: RecyclingTier,
+    // Bit i is set once NFT type i has been redeemed
+    pub redeemed_types_mask: u64,
+    pub referrer: Option<Pubkey>,
+    pub referral_earned: u64,
+    // Day number (unix timestamp / 86400) of the last mint
+    pub last_mint_day: i64,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+impl UserRecord {
+    // Calculate the total space required for the UserRecord account
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes)
+        8 +
+        // nft_config and user (32 bytes each)
+        32 + 32 +
+        // epoch and epoch_rewarded (8 bytes each)
+        8 + 8 +
+        // bump (1 byte)
+        1 +
+        // vesting_positions (8 bytes)
+        8 +
+        // lifetime_units_redeemed and lifetime_rewarded (8 bytes each)
+        8 + 8 +
+        // tier (1 byte)
+        1 +
+        // redeemed_types_mask (8 bytes)
+        8 +
+        // referrer: 1 byte for discriminator + 32 bytes for Pubkey
+        1 + 32 +
+        // referral_earned (8 bytes)
+        8 +
+        // last_mint_day (8 bytes)
+        8 +
+        // current_streak and longest_streak (4 bytes each)
+        4 + 4
+    }
+
+    // The current streak, or 0 if the user missed a day since their last mint
+    pub fn active_streak(&self, today: i64) -> u32 {
+        if self.last_mint_day + 1 >= today {
+            self.current_streak
+        } else {
+            0
+        }
+    }
+
+    // Count a mint on `today` towards the daily streak. Returns the streak that was broken, if
+    // any, and the new streak if this is the first mint of the day
+    pub fn record_mint(&mut self, today: i64) -> (Option<u32>, Option<u32>) {
+        let mut broken = None;
+        let mut extended = None;
+
+        if self.current_streak == 0 || self.last_mint_day + 1 < today {
+            if self.current_streak > 0 {
+                broken = Some(self.current_streak);
+            }
+            self.current_streak = 1;
+            self.last_mint_day = today;
+            extended = Some(1);
+        } else if self.last_mint_day + 1 == today {
+            self.current_streak = self.current_streak.saturating_add(1);
+            self.last_mint_day = today;
+            extended = Some(self.current_streak);
+        }
+        self.longest_streak = self.longest_streak.max(self.current_streak);
+
+        (broken, extended)
+    }
+
+    // Fill in the identity fields the first time the record is used
+    pub fn init_if_new(&mut self, nft_config: Pubkey, user: Pubkey, bump: u8) {
+        if self.user == Pubkey::default() {
+            self.nft_config = nft_config;
+            self.user = user;
+            self.bump = bump;
+        }
+    }
+}
+
+#[account]
+pub struct Achievement {
+    pub nft_config: Pubkey,
+    pub achievement_id: u64,
+    pub kind: AchievementKind,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub claims: u64,
+}
+
+impl Achievement {
+    // Calculate the total space required for the Achievement account
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes)
+        8 +
+        // nft_config (32 bytes)
+        32 +
+        // achievement_id (8 bytes)
+        8 +
+        // kind: 1 byte for discriminator + 8 bytes for the threshold
+        1 + 8 +
+        // name, symbol and uri: 4 bytes for length + actual content
+        4 + MAX_NAME_LENGTH +
+        4 + MAX_SYMBOL_LENGTH +
+        4 + MAX_URI_LENGTH +
+        // claims (8 bytes)
+        8
+    }
+
+    pub fn is_met_by(&self, user_record: &UserRecord) -> bool {
+        match self.kind {
+            AchievementKind::LifetimeUnits { threshold } => {
+                user_record.lifetime_units_redeemed >= threshold
+            }
+            AchievementKind::DistinctTypes { threshold } => {
+                user_record.redeemed_types_mask.count_ones() >= threshold as u32
+            }
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum AchievementKind {
+    // Total NFT units redeemed across all types
+    LifetimeUnits { threshold: u64 },
+    // Number of different NFT types redeemed
+    DistinctTypes { threshold: u8 },
+}
+
+#[account]
+pub struct AchievementClaim {
+    pub achievement: Pubkey,
+    pub user: Pubkey,
+    pub badge_mint: Pubkey,
+    pub claimed_at: i64,
+}
+
+impl AchievementClaim {
+    // Calculate the total space required for the AchievementClaim account
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes)
+        8 +
+        // achievement, user and badge_mint (32 bytes each)
+        32 * 3 +
+        // claimed_at (8 bytes)
+        8
+    }
+}
+
+#[account]
+pub struct StakePosition {
+    pub nft_config: Pubkey,
+    pub user: Pubkey,
+    pub nft_mint: Pubkey,
+    pub nft_type_index: u8,
+    pub escrow: Pubkey,
+    pub amount: u64,
+    pub accrued_rewards: u64,
+    // The type's staking reward index as of the last accrual
+    pub reward_index: u64,
+    pub bump: u8,
+}
+
+impl StakePosition {
+    // Calculate the total space required for the StakePosition account
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes)
+        8 +
+        // nft_config, user and nft_mint (32 bytes each)
+        32 * 3 +
+        // nft_type_index (1 byte)
+        1 +
+        // escrow (32 bytes)
+        32 +
+        // amount and accrued_rewards (8 bytes each)
+        8 + 8 +
+        // reward_index (8 bytes)
+        8 +
+        // bump (1 byte)
+        1
+    }
+
+    // Add the rewards earned per staked unit since the last accrual, given the type's
+    // current staking reward index. Saturates at u64::MAX rather than failing, so the
+    // position can always be unstaked; claims are capped by the reward vault balance anyway
+    pub fn accrue(&mut self, staking_index: u64) {
+        let earned = self
+            .amount
+            .saturating_mul(staking_index.saturating_sub(self.reward_index));
+
+        self.accrued_rewards = self.accrued_rewards.saturating_add(earned);
+        self.reward_index = staking_index;
+    }
+}
+
+#[account]
+pub struct VestingPosition {
+    pub nft_config: Pubkey,
+    pub user: Pubkey,
+    pub position_id: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+    pub bump: u8,
+}
+
+impl VestingPosition {
+    // Calculate the total space required for the VestingPosition account
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes)
+        8 +
+        // nft_config and user (32 bytes each)
+        32 + 32 +
+        // position_id (8 bytes)
+        8 +
+        // mint and vault (32 bytes each)
+        32 + 32 +
+        // total_amount and claimed_amount (8 bytes each)
+        8 + 8 +
+        // start_ts, cliff_ts and end_ts (8 bytes each)
+        8 * 3 +
+        // bump (1 byte)
+        1
+    }
+
+    // Amount released so far: nothing before the cliff, then linear until end_ts
+    pub fn unlocked_amount(&self, now: i64) -> Result<u64> {
+        if now < self.cliff_ts {
+            return Ok(0);
+        }
+        if now >= self.end_ts {
+            return Ok(self.total_amount);
+        }
+
+        let elapsed = (now - self.start_ts) as u128;
+        let duration = (self.end_ts - self.start_ts) as u128;
+        let unlocked = (self.total_amount as u128)
+            .checked_mul(elapsed)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            / duration;
+
+        Ok(unlocked as u64)
+    }
+}
+
+#[account]
+pub struct Campaign {
+    pub nft_config: Pubkey,
+    pub campaign_id: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub eligible_type_indices: Vec<u8>,
+    pub multiplier_bps: u16,
+    pub budget: u64,
+    pub remaining_budget: u64,
+}
+
+impl Campaign {
+    // Calculate the total space required for the Campaign account
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes)
+        8 +
+        // nft_config (32 bytes)
+        32 +
+        // campaign_id (8 bytes)
+        8 +
+        // start_ts and end_ts (8 bytes each)
+        8 + 8 +
+        // eligible_type_indices: 4 bytes for length + one byte per index
+        4 + MAX_CAMPAIGN_TYPES +
+        // multiplier_bps (2 bytes)
+        2 +
+        // budget and remaining_budget (8 bytes each)
+        8 + 8
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        now >= self.start_ts && now < self.end_ts
+    }
+
+    // Bonus on top of `base_reward` for the given type, capped by the remaining budget
+    pub fn bonus_for(&self, nft_type_index: u8, base_reward: u64, now: i64) -> Result<u64> {
+        if !self.is_active(now) || !self.eligible_type_indices.contains(&nft_type_index) {
+            return Ok(0);
+        }
+
+        let boosted = (base_reward as u128)
+            .checked_mul(self.multiplier_bps as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            / BASIS_POINTS_DENOMINATOR as u128;
+        let bonus = u64::try_from(boosted - base_reward as u128)
+            .map_err(|_| ErrorCode::ArithmeticOverflow)?;
+
+        Ok(bonus.min(self.remaining_budget))
+    }
+}
+
+#[account]
+pub struct SponsorPool {
+    pub nft_config: Pubkey,
+    pub sponsor: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    // Sponsor tokens paid per redeemed unit, indexed by NFT type
+    pub payout_rates: [u64; MAX_NFT_TYPES],
+    pub bump: u8,
+}
+
+impl SponsorPool {
+    // Calculate the total space required for the SponsorPool account
+    pub const fn space() -> usize {
+        // Account discriminator (8 bytes)
+        8 +
+        // nft_config, sponsor, mint and vault (32 bytes each)
+        32 * 4 +
+        // payout_rates (8 bytes per NFT type)
+        8 * MAX_NFT_TYPES +
+        // bump (1 byte)
+        1
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct NFTType {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub reward_amount: Option<u64>,
+    pub reward_legs: Vec<RewardLeg>,
+    pub vesting: Option<VestingSchedule>,
+    // Redeemable tokens accrued per staked unit per second
+    pub staking_reward_rate: u64,
+    pub reward_curve: Option<RewardCurve>,
+}
+
+impl NFTType {
+    // Calculate space for a single NFTType
+    pub const fn space() -> usize {
+        // String format: 4 bytes for length + actual content
+
+        // name: 4 + MAX_NAME_LENGTH
+        4 + MAX_NAME_LENGTH +
+        
+        // symbol: 4 + MAX_SYMBOL_LENGTH
+        4 + MAX_SYMBOL_LENGTH +
+        
+        // uri: 4 + MAX_URI_LENGTH
+        4 + MAX_URI_LENGTH +
+        
+        // Option<u64>: 1 byte for discriminator + 8 bytes for u64
+        9 +
+
+        // reward_legs: 4 bytes for length + MAX_REWARD_LEGS legs
+        4 + MAX_REWARD_LEGS * RewardLeg::space() +
+
+        // Option<VestingSchedule>: 1 byte for discriminator + schedule
+        1 + VestingSchedule::space() +
+
+        // staking_reward_rate (8 bytes)
+        8 +
+
+        // Option<RewardCurve>: 1 byte for discriminator + curve
+        1 + RewardCurve::space()
+    }
+}
+
+// NFTType as stored in the zero-copy NFTConfig. Strings are zero-padded, and unset
+// options are zeroed: a reward amount, vesting duration or curve reference balance of 0
+// is never valid, so it stands for None
+#[zero_copy]
+pub struct NFTTypeData {
+    pub reward_amount: u64,
+    // Redeemable tokens accrued per staked unit per second
+    pub staking_reward_rate: u64,
+    // Redeemable tokens accrued per staked unit since the type was added, as of
+    // staking_index_ts. Positions accrue against it, so rate changes are never retroactive
+    pub staking_reward_index: u64,
+    pub staking_index_ts: i64,
+    pub vesting: VestingScheduleData,
+    pub reward_curve: RewardCurveData,
+    pub reward_legs: [RewardLegData; MAX_REWARD_LEGS],
+    pub name: [u8; MAX_NAME_LENGTH],
+    pub symbol: [u8; MAX_SYMBOL_LENGTH],
+    pub uri: [u8; MAX_URI_LENGTH],
+    pub reward_leg_count: u8,
+    pub padding: [u8; 5],
+}
+
+impl NFTTypeData {
+    pub fn new(name: &str, symbol: &str, uri: &str) -> Result<Self> {
+        require!(
+            name.len() <= MAX_NAME_LENGTH
+                && symbol.len() <= MAX_SYMBOL_LENGTH
+                && uri.len() <= MAX_URI_LENGTH,
+            ErrorCode::MetadataTooLong
+        );
+
+        let mut nft_type = NFTTypeData::zeroed();
+        nft_type.name[..name.len()].copy_from_slice(name.as_bytes());
+        nft_type.symbol[..symbol.len()].copy_from_slice(symbol.as_bytes());
+        nft_type.uri[..uri.len()].copy_from_slice(uri.as_bytes());
+        Ok(nft_type)
+    }
+
+    pub fn name(&self) -> &str {
+        padded_str(&self.name)
+    }
+
+    pub fn symbol(&self) -> &str {
+        padded_str(&self.symbol)
+    }
+
+    pub fn uri(&self) -> &str {
+        padded_str(&self.uri)
+    }
+
+    pub fn reward_amount(&self) -> Option<u64> {
+        (self.reward_amount > 0).then_some(self.reward_amount)
+    }
+
+    // The staking reward index brought forward to `now` at the current rate
+    pub fn staking_index_at(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.staking_index_ts).max(0) as u64;
+        self.staking_reward_index
+            .saturating_add(self.staking_reward_rate.saturating_mul(elapsed))
+    }
+
+    // Fold what has accrued at the current rate into the index, before the rate changes
+    pub fn update_staking_index(&mut self, now: i64) {
+        self.staking_reward_index = self.staking_index_at(now);
+        self.staking_index_ts = now;
+    }
+
+    pub fn vesting(&self) -> Option<VestingSchedule> {
+        (self.vesting.duration_seconds > 0).then_some(VestingSchedule {
+            min_reward: self.vesting.min_reward,
+            cliff_seconds: self.vesting.cliff_seconds,
+            duration_seconds: self.vesting.duration_seconds,
+        })
+    }
+
+    pub fn set_vesting(&mut self, vesting: Option<&VestingSchedule>) {
+        self.vesting = match vesting {
+            Some(schedule) => VestingScheduleData {
+                min_reward: schedule.min_reward,
+                cliff_seconds: schedule.cliff_seconds,
+                duration_seconds: schedule.duration_seconds,
+            },
+            None => VestingScheduleData::zeroed(),
+        };
+    }
+
+    pub fn reward_curve(&self) -> Option<RewardCurve> {
+        let curve = &self.reward_curve;
+        (curve.reference_balance > 0).then_some(RewardCurve {
+            mode: if curve.mode == 0 {
+                CurveMode::Linear
+            } else {
+                CurveMode::Exponential
+            },
+            reference_balance: curve.reference_balance,
+            min_multiplier_bps: curve.min_multiplier_bps,
+            exponent: curve.exponent,
+        })
+    }
+
+    pub fn set_reward_curve(&mut self, reward_curve: Option<&RewardCurve>) {
+        self.reward_curve = match reward_curve {
+            Some(curve) => RewardCurveData {
+                reference_balance: curve.reference_balance,
+                min_multiplier_bps: curve.min_multiplier_bps,
+                mode: curve.mode as u8,
+                exponent: curve.exponent,
+                padding: [0; 4],
+            },
+            None => RewardCurveData::zeroed(),
+        };
+    }
+
+    pub fn reward_legs(&self) -> Vec<RewardLeg> {
+        self.reward_legs[..self.reward_leg_count as usize]
+            .iter()
+            .map(RewardLeg::from)
+            .collect()
+    }
+
+    pub fn set_reward_legs(&mut self, reward_legs: &[RewardLeg]) -> Result<()> {
+        require!(
+            reward_legs.len() <= MAX_REWARD_LEGS,
+            ErrorCode::TooManyRewardLegs
+        );
+
+        self.reward_legs = [RewardLegData::zeroed(); MAX_REWARD_LEGS];
+        for (slot, leg) in self.reward_legs.iter_mut().zip(reward_legs) {
+            *slot = RewardLegData::from(leg);
+        }
+        self.reward_leg_count = reward_legs.len() as u8;
+        Ok(())
+    }
+
+    // Reward per redeemed unit, scaled by the reward curve when one is set
+    pub fn current_reward_rate(&self, vault_balance: u64) -> Result<u64> {
+        let reward_amount = self.reward_amount().ok_or(ErrorCode::RewardAmountNotSet)?;
+
+        match self.reward_curve() {
+            Some(curve) => {
+                let scaled = (reward_amount as u128)
+                    .checked_mul(curve.multiplier_bps(vault_balance) as u128)
+                    .ok_or(ErrorCode::ArithmeticOverflow)?
+                    / BASIS_POINTS_DENOMINATOR as u128;
+                Ok(scaled as u64)
+            }
+            None => Ok(reward_amount),
+        }
+    }
+}
+
+impl From<&NFTType> for NFTTypeData {
+    fn from(nft_type: &NFTType) -> Self {
+        let mut data = NFTTypeData::zeroed();
+        // Strings longer than the fixed fields could only come from an unchecked add_nft_type
+        for (dst, src) in [
+            (&mut data.name[..], &nft_type.name),
+            (&mut data.symbol[..], &nft_type.symbol),
+            (&mut data.uri[..], &nft_type.uri),
+        ] {
+            let len = src.len().min(dst.len());
+            dst[..len].copy_from_slice(&src.as_bytes()[..len]);
+        }
+        data.reward_amount = nft_type.reward_amount.unwrap_or(0);
+        data.staking_reward_rate = nft_type.staking_reward_rate;
+        data.set_vesting(nft_type.vesting.as_ref());
+        data.set_reward_curve(nft_type.reward_curve.as_ref());
+        let legs = &nft_type.reward_legs[..nft_type.reward_legs.len().min(MAX_REWARD_LEGS)];
+        data.set_reward_legs(legs).unwrap();
+        data
+    }
+}
+
+impl From<&NFTTypeData> for NFTType {
+    fn from(data: &NFTTypeData) -> Self {
+        NFTType {
+            name: data.name().to_string(),
+            symbol: data.symbol().to_string(),
+            uri: data.uri().to_string(),
+            reward_amount: data.reward_amount(),
+            reward_legs: data.reward_legs(),
+            vesting: data.vesting(),
+            staking_reward_rate: data.staking_reward_rate,
+            reward_curve: data.reward_curve(),
+        }
+    }
+}
+
+// Contents of a zero-padded string field
+fn padded_str(bytes: &[u8]) -> &str {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..len]).unwrap_or_default()
+}
+
+#[zero_copy]
+pub struct VestingScheduleData {
+    pub min_reward: u64,
+    pub cliff_seconds: i64,
+    pub duration_seconds: i64,
+}
+
+#[zero_copy]
+pub struct RewardCurveData {
+    pub reference_balance: u64,
+    pub min_multiplier_bps: u16,
+    // CurveMode as its variant index
+    pub mode: u8,
+    pub exponent: u8,
+    pub padding: [u8; 4],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CurveMode {
+    Linear,
+    Exponential,
+}
+
+// Scales the reward down as the vault balance drops below `reference_balance`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct RewardCurve {
+    pub mode: CurveMode,
+    pub reference_balance: u64,
+    pub min_multiplier_bps: u16,
+    // Only used by the exponential mode
+    pub exponent: u8,
+}
+
+impl RewardCurve {
+    // Calculate space for a RewardCurve
+    pub const fn space() -> usize {
+        // mode (1 byte)
+        1 +
+        // reference_balance (8 bytes)
+        8 +
+        // min_multiplier_bps (2 bytes) and exponent (1 byte)
+        2 + 1
+    }
+
+    // Multiplier in basis points for the given vault balance
+    pub fn multiplier_bps(&self, vault_balance: u64) -> u64 {
+        let ratio_bps = ((vault_balance as u128 * BASIS_POINTS_DENOMINATOR as u128)
+            / self.reference_balance as u128)
+            .min(BASIS_POINTS_DENOMINATOR as u128) as u64;
+        let min_bps = self.min_multiplier_bps as u64;
+
+        match self.mode {
+            // min + (1 - min) * ratio
+            CurveMode::Linear => {
+                min_bps
+                    + (BASIS_POINTS_DENOMINATOR - min_bps) * ratio_bps / BASIS_POINTS_DENOMINATOR
+            }
+            // ratio ^ exponent, floored at min
+            CurveMode::Exponential => {
+                let mut multiplier_bps = BASIS_POINTS_DENOMINATOR;
+                for _ in 0..self.exponent {
+                    multiplier_bps = multiplier_bps * ratio_bps / BASIS_POINTS_DENOMINATOR;
+                }
+                multiplier_bps.max(min_bps)
+            }
+        }
+    }
+}
+
+// Redemptions of at least `min_reward` vest from the redemption time
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct VestingSchedule {
+    pub min_reward: u64,
+    pub cliff_seconds: i64,
+    pub duration_seconds: i64,
+}
+
+impl VestingSchedule {
+    // Calculate space for a VestingSchedule
+    pub const fn space() -> usize {
+        // min_reward, cliff_seconds and duration_seconds (8 bytes each)
+        8 * 3
+    }
+}
+
+// A single payout made when an NFT is redeemed through `redeem_and_burn_nft_multi`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum RewardLeg {
+    // SPL tokens paid from the config's reward vault for `mint`
+    Token { mint: Pubkey, amount: u64 },
+    // Lamports paid from the config's lamport vault
+    Lamports { amount: u64 },
+}
+
+impl RewardLeg {
+    // Calculate space for the largest variant
+    pub const fn space() -> usize {
+        // Enum discriminator (1 byte) + mint (32 bytes) + amount (8 bytes)
+        1 + 32 + 8
+    }
+
+    pub fn amount(&self) -> u64 {
+        match self {
+            RewardLeg::Token { amount, .. } | RewardLeg::Lamports { amount } => *amount,
+        }
+    }
+}
+
+// RewardLeg as stored in the zero-copy NFTConfig
+#[zero_copy]
+pub struct RewardLegData {
+    pub amount: u64,
+    // Unused for lamport legs
+    pub mint: Pubkey,
+    // 0 for a token leg, 1 for a lamport leg
+    pub kind: u8,
+    pub padding: [u8; 7],
+}
+
+impl From<&RewardLeg> for RewardLegData {
+    fn from(leg: &RewardLeg) -> Self {
+        let (kind, mint) = match leg {
+            RewardLeg::Token { mint, .. } => (0, *mint),
+            RewardLeg::Lamports { .. } => (1, Pubkey::default()),
+        };
+        RewardLegData {
+            amount: leg.amount(),
+            mint,
+            kind,
+            padding: [0; 7],
+        }
+    }
+}
+
+impl From<&RewardLegData> for RewardLeg {
+    fn from(leg: &RewardLegData) -> Self {
+        match leg.kind {
+            0 => RewardLeg::Token {
+                mint: leg.mint,
+                amount: leg.amount,
+            },
+            _ => RewardLeg::Lamports { amount: leg.amount },
+        }
+    }
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AddNFTType<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetRewardAmount<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct IncreaseTokenSupply<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub redeemable_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = redeemable_mint,
+        associated_token::authority = authority,
+    )]
+    pub redeemable_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = nft_config.load()?.authority == authority.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct IncreaseMaxNFTTypes<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EditRewardAmount<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[derive(Accounts)]
+pub struct GetUserInfo<'info> {
+    pub user: Signer<'info>,
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        associated_token::mint = nft_mint,
+        associated_token::authority = user,
+    )]
+    pub nft_token_account: Account<'info, TokenAccount>,
+    // The config's redeemable mint, so the balance matches the config the event reports
+    #[account(seeds = [REDEEMABLE_MINT_SEED, nft_config.key().as_ref()], bump)]
+    pub redeemable_mint: Account<'info, Mint>,
+    #[account(
+        associated_token::mint = redeemable_mint,
+        associated_token::authority = user,
+    )]
+    pub user_redeemable_token_account: Account<'info, TokenAccount>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetVestingSchedule<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        mut,
+        has_one = user @ ErrorCode::UnauthorizedAccess,
+        has_one = nft_config @ ErrorCode::UnauthorizedAccess
+    )]
+    pub vesting_position: Account<'info, VestingPosition>,
+    #[account(
+        mut,
+        address = vesting_position.vault @ ErrorCode::InvalidVestingVault
+    )]
+    pub vesting_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = vesting_position.mint,
+        token::authority = user,
+    )]
+    pub user_redeemable_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetStakingRewardRate<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct StakeNFT<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = user,
+    )]
+    pub nft_token_account: Account<'info, TokenAccount>,
+    /// CHECK: This account is used for metadata verification
+    pub metadata: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = StakePosition::space(),
+        seeds = [
+            STAKE_POSITION_SEED,
+            nft_config.key().as_ref(),
+            user.key().as_ref(),
+            nft_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        token::mint = nft_mint,
+        token::authority = stake_position,
+        seeds = [STAKE_ESCROW_SEED, stake_position.key().as_ref()],
+        bump
+    )]
+    pub stake_escrow: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UnstakeNFT<'info> {
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        mut,
+        has_one = user @ ErrorCode::UnauthorizedAccess,
+        has_one = nft_config @ ErrorCode::InvalidStakePosition
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    #[account(
+        mut,
+        address = stake_position.escrow @ ErrorCode::InvalidStakeEscrow
+    )]
+    pub stake_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = stake_position.nft_mint,
+        token::authority = user,
+    )]
+    pub nft_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimStakingRewards<'info> {
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            NFT_CONFIG_SEED,
+            nft_config.load()?.authority.as_ref(),
+            &nft_config.load()?.config_id_seed()
+        ],
+        bump
+    )]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        mut,
+        has_one = user @ ErrorCode::UnauthorizedAccess,
+        has_one = nft_config @ ErrorCode::InvalidStakePosition
+    )]
+    pub stake_position: Account<'info, StakePosition>,
     #[account(
-        init,
-        payer = authority,
-        mint::decimals = 9,
-        mint::authority = authority.key(),
         seeds = [REDEEMABLE_MINT_SEED, nft_config.key().as_ref()],
         bump
     )]
     pub redeemable_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [REWARD_VAULT_SEED, nft_config.key().as_ref(), redeemable_mint.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = redeemable_mint,
+        token::authority = user,
+    )]
+    pub user_redeemable_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetTierThresholds<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
 
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(achievement_id: u64)]
+pub struct CreateAchievement<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
     #[account(
         init,
         payer = authority,
-        associated_token::mint = redeemable_mint,
-        associated_token::authority = authority,
+        space = Achievement::space(),
+        seeds = [ACHIEVEMENT_SEED, nft_config.key().as_ref(), &achievement_id.to_le_bytes()],
+        bump
     )]
-    pub redeemable_token_account: Account<'info, TokenAccount>,
+    pub achievement: Account<'info, Achievement>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimAchievement<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            NFT_CONFIG_SEED,
+            nft_config.load()?.authority.as_ref(),
+            &nft_config.load()?.config_id_seed()
+        ],
+        bump
+    )]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(mut, has_one = nft_config @ ErrorCode::UnauthorizedAccess)]
+    pub achievement: Account<'info, Achievement>,
+    #[account(
+        seeds = [USER_RECORD_SEED, nft_config.key().as_ref(), user.key().as_ref()],
+        bump = user_record.bump
+    )]
+    pub user_record: Account<'info, UserRecord>,
+    // Exists once the badge has been claimed, so a second claim fails here
+    #[account(
+        init,
+        payer = user,
+        space = AchievementClaim::space(),
+        seeds = [ACHIEVEMENT_CLAIM_SEED, achievement.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub achievement_claim: Account<'info, AchievementClaim>,
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = 0,
+        mint::authority = nft_config,
+        mint::freeze_authority = nft_config,
+        seeds = [BADGE_MINT_SEED, achievement.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub badge_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = badge_mint,
+        associated_token::authority = user,
+    )]
+    pub badge_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Created by the token metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), badge_mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: Created by the token metadata program
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            badge_mint.key().as_ref(),
+            b"edition"
+        ],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetReferralConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(referrer: Pubkey)]
+pub struct SetReferrer<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserRecord::space(),
+        seeds = [USER_RECORD_SEED, nft_config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_record: Account<'info, UserRecord>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserRecord::space(),
+        seeds = [USER_RECORD_SEED, nft_config.key().as_ref(), referrer.as_ref()],
+        bump
+    )]
+    pub referrer_record: Account<'info, UserRecord>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetStreakBonus<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetRedemptionLimits<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(campaign_id: u64)]
+pub struct CreateCampaign<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = Campaign::space(),
+        seeds = [CAMPAIGN_SEED, nft_config.key().as_ref(), &campaign_id.to_le_bytes()],
+        bump
+    )]
+    pub campaign: Account<'info, Campaign>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetRewardLegs<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+}
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateRewardVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    pub reward_mint: Account<'info, Mint>,
     #[account(
-        seeds = [NFT_CONFIG_SEED, authority.key().as_ref()],
-        bump,
-        constraint = nft_config.authority == authority.key() @ ErrorCode::UnauthorizedAccess
+        init,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = nft_config,
+        seeds = [REWARD_VAULT_SEED, nft_config.key().as_ref(), reward_mint.key().as_ref()],
+        bump
     )]
-    pub nft_config: Account<'info, NFTConfig>,
-
+    pub reward_vault: Account<'info, TokenAccount>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(amount: u64)]
-pub struct RedeemAndBurnNFT<'info> {
+pub struct RedeemAndBurnNFTMulti<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut)]
@@ -487,144 +4108,168 @@ pub struct RedeemAndBurnNFT<'info> {
         associated_token::authority = user,
     )]
     pub nft_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
+    /// CHECK: This account is used for metadata verification
+    pub metadata: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            NFT_CONFIG_SEED,
+            nft_config.load()?.authority.as_ref(),
+            &nft_config.load()?.config_id_seed()
+        ],
+        bump
+    )]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        mut,
+        seeds = [LAMPORT_VAULT_SEED, nft_config.key().as_ref()],
+        bump
+    )]
+    pub lamport_vault: SystemAccount<'info>,
+    #[account(seeds = [REDEEMABLE_MINT_SEED, nft_config.key().as_ref()], bump)]
     pub redeemable_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub redeemable_token_account: Account<'info, TokenAccount>,
     #[account(
         init_if_needed,
         payer = user,
-        associated_token::mint = redeemable_mint,
-        associated_token::authority = user,
+        space = UserRecord::space(),
+        seeds = [USER_RECORD_SEED, nft_config.key().as_ref(), user.key().as_ref()],
+        bump
     )]
-    pub user_redeemable_token_account: Account<'info, TokenAccount>,
-    /// CHECK: This account is verified in the instruction logic
-    #[account(mut)]
-    pub authority: UncheckedAccount<'info>,
-    /// CHECK: This account is used for metadata verification
-    pub metadata: UncheckedAccount<'info>,
-    pub nft_config: Account<'info, NFTConfig>,
+    pub user_record: Account<'info, UserRecord>,
     pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
-#[account]
-pub struct NFTConfig {
-    pub authority: Pubkey,
-    pub max_nft_types: u8,
-    pub nft_types: Vec<NFTType>,
-}
-
-impl NFTConfig {
-    // Calculate the total space required for the NFTConfig account
-    pub const fn space() -> usize {
-        // Account discriminator (8 bytes)
-        8 + 
-        // Pubkey (32 bytes)
-        32 +
-        // max_nft_types (1 byte)
-        1 +
-        // Vec discriminator (4 bytes for storing length)
-        4 +
-        // Maximum space for Vec contents
-        // Calculate space for 10 NFT types initially
-        10 * NFTType::space()
-    }
-}
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
-pub struct NFTType {
-    pub name: String,
-    pub symbol: String,
-    pub uri: String,
-    pub reward_amount: Option<u64>,
-}
-
-impl NFTType {
-    // Calculate space for a single NFTType
-    pub const fn space() -> usize {
-        // String format: 4 bytes for length + actual content
-
-        // name: 4 + MAX_NAME_LENGTH
-        4 + MAX_NAME_LENGTH +
-        
-        // symbol: 4 + MAX_SYMBOL_LENGTH
-        4 + MAX_SYMBOL_LENGTH +
-        
-        // uri: 4 + MAX_URI_LENGTH
-        4 + MAX_URI_LENGTH +
-        
-        // Option<u64>: 1 byte for discriminator + 8 bytes for u64
-        9
-    }
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateSponsorPool<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    #[account(mut)]
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    pub sponsor_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = sponsor,
+        space = SponsorPool::space(),
+        seeds = [
+            SPONSOR_POOL_SEED,
+            nft_config.key().as_ref(),
+            sponsor.key().as_ref(),
+            sponsor_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub sponsor_pool: Account<'info, SponsorPool>,
+    #[account(
+        init,
+        payer = sponsor,
+        token::mint = sponsor_mint,
+        token::authority = sponsor_pool,
+        seeds = [SPONSOR_VAULT_SEED, sponsor_pool.key().as_ref()],
+        bump
+    )]
+    pub sponsor_vault: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct AddNFTType<'info> {
+pub struct SetSponsorPayoutRate<'info> {
+    pub sponsor: Signer<'info>,
     #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(mut, has_one = authority)]
-    pub nft_config: Account<'info, NFTConfig>,
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        mut,
+        has_one = sponsor @ ErrorCode::UnauthorizedAccess,
+        has_one = nft_config @ ErrorCode::SponsorPoolConfigMismatch
+    )]
+    pub sponsor_pool: Account<'info, SponsorPool>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct SetRewardAmount<'info> {
+pub struct DepositSponsorTokens<'info> {
+    pub sponsor: Signer<'info>,
     #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(mut, has_one = authority)]
-    pub nft_config: Account<'info, NFTConfig>,
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        has_one = sponsor @ ErrorCode::UnauthorizedAccess,
+        has_one = nft_config @ ErrorCode::SponsorPoolConfigMismatch
+    )]
+    pub sponsor_pool: Account<'info, SponsorPool>,
+    #[account(
+        mut,
+        address = sponsor_pool.vault @ ErrorCode::InvalidSponsorVault
+    )]
+    pub sponsor_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = sponsor_pool.mint,
+        token::authority = sponsor,
+    )]
+    pub sponsor_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct IncreaseTokenSupply<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+pub struct WithdrawSponsorTokens<'info> {
+    pub sponsor: Signer<'info>,
     #[account(mut)]
-    pub redeemable_mint: Account<'info, Mint>,
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    #[account(
+        has_one = sponsor @ ErrorCode::UnauthorizedAccess,
+        has_one = nft_config @ ErrorCode::SponsorPoolConfigMismatch
+    )]
+    pub sponsor_pool: Account<'info, SponsorPool>,
     #[account(
         mut,
-        associated_token::mint = redeemable_mint,
-        associated_token::authority = authority,
+        address = sponsor_pool.vault @ ErrorCode::InvalidSponsorVault
     )]
-    pub redeemable_token_account: Account<'info, TokenAccount>,
-    #[account(constraint = nft_config.authority == authority.key() @ ErrorCode::UnauthorizedAccess)]
-    pub nft_config: Account<'info, NFTConfig>,
+    pub sponsor_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = sponsor_pool.mint,
+        token::authority = sponsor,
+    )]
+    pub sponsor_token_account: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct IncreaseMaxNFTTypes<'info> {
+pub struct MigrateConfig<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
-    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
-    pub nft_config: Account<'info, NFTConfig>,
+    /// CHECK: read by hand, since an old layout does not deserialize as NFTConfig; the
+    /// handler checks the address, which depends on the layout
+    #[account(mut, owner = ID)]
+    pub nft_config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct EditRewardAmount<'info> {
-    #[account(mut)]
+pub struct SetRewardCurve<'info> {
     pub authority: Signer<'info>,
     #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAccess)]
-    pub nft_config: Account<'info, NFTConfig>,
+    pub nft_config: AccountLoader<'info, NFTConfig>,
 }
 
 #[derive(Accounts)]
-pub struct GetUserInfo<'info> {
-    pub user: Signer<'info>,
-    pub nft_mint: Account<'info, Mint>,
-    #[account(
-        associated_token::mint = nft_mint,
-        associated_token::authority = user,
-    )]
-    pub nft_token_account: Account<'info, TokenAccount>,
+pub struct QuoteRedemption<'info> {
+    pub nft_config: AccountLoader<'info, NFTConfig>,
+    // The config's redeemable mint, so the quote prices against the config's own vault
+    #[account(seeds = [REDEEMABLE_MINT_SEED, nft_config.key().as_ref()], bump)]
     pub redeemable_mint: Account<'info, Mint>,
     #[account(
         associated_token::mint = redeemable_mint,
-        associated_token::authority = user,
+        associated_token::authority = nft_config.load()?.authority,
     )]
-    pub user_redeemable_token_account: Account<'info, TokenAccount>,
+    pub redeemable_token_account: Account<'info, TokenAccount>,
 }
 
 // struct to represent the return value
@@ -663,16 +4308,111 @@ pub enum ErrorCode {
     ArithmeticOverflow,
     #[msg("Invalid maximum number of NFT types (must be between 1 and 10)")]
     InvalidMaxNFTTypes,
+    #[msg("Campaign start must be before its end")]
+    InvalidCampaignWindow,
+    #[msg("Campaign multiplier must be at least 10000 basis points")]
+    InvalidCampaignMultiplier,
+    #[msg("Campaign must list between 1 and 10 eligible NFT types")]
+    InvalidCampaignTypes,
+    #[msg("Campaign does not belong to this NFT config")]
+    CampaignConfigMismatch,
+    #[msg("Sponsor pool does not belong to this NFT config")]
+    SponsorPoolConfigMismatch,
+    #[msg("Sponsor vault does not match the sponsor pool")]
+    InvalidSponsorVault,
+    #[msg("Sponsor token account must hold the pool mint and belong to the user")]
+    InvalidSponsorTokenAccount,
+    #[msg("Sponsor pool, sponsor vault and user sponsor token account must be passed together")]
+    IncompleteSponsorAccounts,
+    #[msg("Too many reward legs for one NFT type")]
+    TooManyRewardLegs,
+    #[msg("Missing vault or token account for a reward leg")]
+    MissingRewardLegAccounts,
+    #[msg("Reward vault does not match the reward leg")]
+    InvalidRewardVault,
+    #[msg("Reward token account must hold the leg mint and belong to the user")]
+    InvalidRewardTokenAccount,
+    #[msg("Redemption limit for this epoch exceeded")]
+    RedemptionLimitExceeded,
+    #[msg("Invalid vesting schedule (cliff must not exceed a non-zero duration)")]
+    InvalidVestingSchedule,
+    #[msg("Vesting position and vesting vault are required for this redemption")]
+    VestingAccountsRequired,
+    #[msg("Vesting accounts were passed but this redemption does not vest")]
+    VestingNotApplicable,
+    #[msg("Vesting vault does not match the vesting position")]
+    InvalidVestingVault,
+    #[msg("Nothing to claim yet")]
+    NothingToClaim,
+    #[msg("Stake escrow does not match the stake position")]
+    InvalidStakeEscrow,
+    #[msg("Stake position does not match this mint or NFT config")]
+    InvalidStakePosition,
+    #[msg("Stake position and stake escrow must be passed together")]
+    IncompleteStakeAccounts,
+    #[msg("Amount exceeds the staked balance")]
+    InsufficientStakedAmount,
+    #[msg("Total reward is below the requested minimum")]
+    RewardBelowMinimum,
+    #[msg("Reward rate differs from the expected rate")]
+    RewardRateMismatch,
+    #[msg("Invalid reward curve parameters")]
+    InvalidRewardCurve,
+    #[msg("Tier thresholds must increase from Bronze to Gold")]
+    InvalidTierThresholds,
+    #[msg("Name, symbol or URI exceeds the maximum length")]
+    MetadataTooLong,
+    #[msg("Achievement requirements not met")]
+    AchievementNotEarned,
+    #[msg("Referral share cannot exceed 10000 basis points")]
+    InvalidReferralConfig,
+    #[msg("Invalid referrer")]
+    InvalidReferrer,
+    #[msg("Referrer has already been set")]
+    ReferrerAlreadySet,
+    #[msg("Referrer record and token account are required for this user")]
+    ReferrerAccountsRequired,
+    #[msg("Account is not an NFT config")]
+    InvalidConfigAccount,
+    #[msg("NFT config already uses the current layout")]
+    ConfigAlreadyMigrated,
+    #[msg("Batch must contain at least one entry")]
+    EmptyBatch,
+    #[msg("NFT type appears more than once in the batch")]
+    DuplicateBatchEntry,
+    #[msg("Batch accounts do not match the batch entries")]
+    InvalidBatchAccounts,
+    #[msg("Types with a vesting schedule must be redeemed individually")]
+    VestingTypeInBatch,
+    #[msg("NFT mint was not created by this program for its type")]
+    InvalidNFTMint,
+    #[msg("Expected one minimum reward per reward leg")]
+    RewardLegCountMismatch,
+}
+
+// Context shared by every event: which config, who acted, and when
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct EventHeader {
+    pub nft_config: Pubkey,
+    // The instruction's signer: the config authority, user, minter or sponsor
+    pub actor: Pubkey,
+    // Position in the config's event stream, so indexers can order events and spot gaps
+    pub seq: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 #[event]
 pub struct InitializeEvent {
-    pub authority: Pubkey,
+    pub header: EventHeader,
+    pub config_id: u64,
     pub max_nft_types: u8,
 }
 
 #[event]
 pub struct AddNFTTypeEvent {
+    pub header: EventHeader,
+    pub nft_type_index: u8,
     pub name: String,
     pub symbol: String,
     pub uri: String,
@@ -680,58 +4420,285 @@ pub struct AddNFTTypeEvent {
 
 #[event]
 pub struct SetRewardAmountEvent {
+    pub header: EventHeader,
     pub nft_type_index: u8,
-    pub reward_amount: u64,
+    pub old_reward_amount: Option<u64>,
+    pub new_reward_amount: u64,
 }
 
 #[event]
 pub struct MintNFTEvent {
+    pub header: EventHeader,
     pub mint: Pubkey,
-    pub owner: Pubkey,
     pub nft_type_index: u8,
     pub amount: u64,
 }
 
+#[event]
+pub struct MintNFTBatchEvent {
+    pub header: EventHeader,
+    pub entries: Vec<MintBatchEntry>,
+    pub total_amount: u64,
+}
+
 #[event]
 pub struct CreateRedeemableTokenEvent {
+    pub header: EventHeader,
     pub mint: Pubkey,
     pub amount: u64,
-    pub authority: Pubkey,
 }
 
 #[event]
 pub struct RedeemAndBurnNFTEvent {
-    pub user: Pubkey,
+    pub header: EventHeader,
     pub nft_mint: Pubkey,
+    pub nft_type_index: u8,
+    pub amount_burned: u64,
+    pub reward_amount: u64,
+    pub tier_boost: u64,
+    pub streak_bonus: u64,
+    pub campaign_bonus: u64,
+    pub sponsor_reward: u64,
+    pub referral_reward: u64,
+    pub vesting_position: Option<Pubkey>,
+}
+
+#[event]
+pub struct RedeemAndBurnNFTBatchEvent {
+    pub header: EventHeader,
+    pub entries: Vec<RedeemBatchEntry>,
+    // Totals across the batch; reward_amount includes the tier boost, streak bonus and
+    // campaign bonus
     pub amount_burned: u64,
     pub reward_amount: u64,
+    pub tier_boost: u64,
+    pub streak_bonus: u64,
+    pub campaign_bonus: u64,
+    pub sponsor_reward: u64,
+    pub referral_reward: u64,
 }
 
 #[event]
 pub struct IncreaseTokenSupplyEvent {
+    pub header: EventHeader,
     pub mint: Pubkey,
     pub amount: u64,
-    pub authority: Pubkey,
 }
 
 #[event]
 pub struct IncreaseMaxNFTTypesEvent {
-    pub authority: Pubkey,
+    pub header: EventHeader,
     pub old_max: u8,
     pub new_max: u8,
 }
 
 #[event]
 pub struct EditRewardAmountEvent {
+    pub header: EventHeader,
     pub nft_type_index: u8,
-    pub old_reward_amount: u64,
+    pub old_reward_amount: Option<u64>,
     pub new_reward_amount: u64,
 }
 
 #[event]
 pub struct UserInfoFetched {
-    pub user: Pubkey,
+    pub header: EventHeader,
     pub nft_mint: Pubkey,
     pub nft_balance: u64,
     pub redeemable_balance: u64,
 }
+
+#[event]
+pub struct CreateCampaignEvent {
+    pub header: EventHeader,
+    pub campaign: Pubkey,
+    pub campaign_id: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub eligible_type_indices: Vec<u8>,
+    pub multiplier_bps: u16,
+    pub budget: u64,
+}
+
+#[event]
+pub struct CreateSponsorPoolEvent {
+    pub header: EventHeader,
+    pub sponsor_pool: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct SetSponsorPayoutRateEvent {
+    pub header: EventHeader,
+    pub sponsor_pool: Pubkey,
+    pub nft_type_index: u8,
+    pub payout_rate: u64,
+}
+
+#[event]
+pub struct SponsorDepositEvent {
+    pub header: EventHeader,
+    pub sponsor_pool: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SponsorWithdrawEvent {
+    pub header: EventHeader,
+    pub sponsor_pool: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SetRewardLegsEvent {
+    pub header: EventHeader,
+    pub nft_type_index: u8,
+    pub reward_legs: Vec<RewardLeg>,
+}
+
+#[event]
+pub struct CreateRewardVaultEvent {
+    pub header: EventHeader,
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct RedeemAndBurnNFTMultiEvent {
+    pub header: EventHeader,
+    pub nft_mint: Pubkey,
+    pub nft_type_index: u8,
+    pub amount_burned: u64,
+    pub payouts: Vec<RewardLeg>,
+}
+
+#[event]
+pub struct SetRedemptionLimitsEvent {
+    pub header: EventHeader,
+    pub max_reward_per_user_per_epoch: u64,
+    pub max_reward_per_epoch: u64,
+}
+
+#[event]
+pub struct SetVestingScheduleEvent {
+    pub header: EventHeader,
+    pub nft_type_index: u8,
+    pub vesting: Option<VestingSchedule>,
+}
+
+#[event]
+pub struct ClaimVestedEvent {
+    pub header: EventHeader,
+    pub vesting_position: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct SetStakingRewardRateEvent {
+    pub header: EventHeader,
+    pub nft_type_index: u8,
+    pub staking_reward_rate: u64,
+}
+
+#[event]
+pub struct StakeNFTEvent {
+    pub header: EventHeader,
+    pub nft_mint: Pubkey,
+    pub nft_type_index: u8,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct UnstakeNFTEvent {
+    pub header: EventHeader,
+    pub nft_mint: Pubkey,
+    pub nft_type_index: u8,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct ClaimStakingRewardsEvent {
+    pub header: EventHeader,
+    pub stake_position: Pubkey,
+    pub nft_type_index: u8,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SetRewardCurveEvent {
+    pub header: EventHeader,
+    pub nft_type_index: u8,
+    pub reward_curve: Option<RewardCurve>,
+}
+
+#[event]
+pub struct SetTierThresholdsEvent {
+    pub header: EventHeader,
+    pub tiers: Vec<TierConfig>,
+}
+
+#[event]
+pub struct TierUpgraded {
+    pub header: EventHeader,
+    pub old_tier: RecyclingTier,
+    pub new_tier: RecyclingTier,
+    pub lifetime_units_redeemed: u64,
+}
+
+#[event]
+pub struct CreateAchievementEvent {
+    pub header: EventHeader,
+    pub achievement: Pubkey,
+    pub achievement_id: u64,
+    pub kind: AchievementKind,
+    pub name: String,
+}
+
+#[event]
+pub struct ClaimAchievementEvent {
+    pub header: EventHeader,
+    pub achievement: Pubkey,
+    pub badge_mint: Pubkey,
+}
+
+#[event]
+pub struct SetReferralConfigEvent {
+    pub header: EventHeader,
+    pub referral_bps: u16,
+    pub referral_cap: u64,
+}
+
+#[event]
+pub struct SetReferrerEvent {
+    pub header: EventHeader,
+    pub referrer: Pubkey,
+}
+
+#[event]
+pub struct SetStreakBonusEvent {
+    pub header: EventHeader,
+    pub streak_bonus_bps_per_day: u16,
+    pub max_streak_bonus_bps: u16,
+}
+
+#[event]
+pub struct StreakExtended {
+    pub header: EventHeader,
+    pub current_streak: u32,
+}
+
+#[event]
+pub struct StreakBroken {
+    pub header: EventHeader,
+    pub previous_streak: u32,
+}
+
+#[event]
+pub struct MigrateConfigEvent {
+    pub header: EventHeader,
+    pub old_version: u8,
+    pub new_version: u8,
+}
@@ -0,0 +1,589 @@
+//! Compute-unit benchmarks for every program instruction, checked against a recorded baseline.
+//!
+//! Each instruction is simulated in the in-process bank and the compute units it consumed are
+//! compared with `baseline-sbf.txt` next to this file. The run fails when an instruction uses
+//! more than `BENCH_THRESHOLD_PCT` percent (default 5) over its baseline, or when the measured
+//! instructions and the baseline disagree.
+//!
+//! The program's own compute is only metered when it runs as SBF, so the benchmark needs
+//! `SBF_OUT_DIR` pointing at a build of `trash.so`. It is left out of a plain `cargo test`
+//! (`test = false` in Cargo.toml) and run by name, as the `bench` CI workflow does:
+//!
+//! ```text
+//! cargo build-sbf --manifest-path programs/trash/Cargo.toml
+//! SBF_OUT_DIR=$PWD/target/deploy cargo test -p trash --test bench -- --nocapture
+//! ```
+//!
+//! Running it without an SBF build, or without a recorded baseline, fails. After an intended
+//! change, record a new baseline by running the same command with `BENCH_UPDATE=1`, or by
+//! starting the workflow by hand with `update_baseline` and committing the file it uploads.
+
+// The harness is shared with the integration tests, which use more of it
+#![allow(dead_code)]
+
+#[path = "../integration/harness.rs"]
+mod harness;
+#[path = "../integration/metadata_stub.rs"]
+mod metadata_stub;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+use trash::{
+    AchievementKind, CurveMode, MintBatchEntry, RewardCurve, RewardLeg, TierConfig,
+    VestingSchedule, MAX_NFT_TYPES,
+};
+use trash4coin_client::instructions::{self, RedeemBatchExtras, RedeemExtras};
+use trash4coin_client::pda;
+
+use harness::{runs_as_sbf, type_metadata, TestEnv, CONFIG_ID, REDEEMABLE_SUPPLY};
+
+const DEFAULT_THRESHOLD_PCT: u64 = 5;
+
+// A config account written by the originally deployed program, whose authority is
+// keypair(BASELINE_AUTHORITY_SEED)
+const BASELINE_CONFIG: &[u8] = include_bytes!("../fixtures/nft_config_baseline.bin");
+const BASELINE_AUTHORITY_SEED: u8 = 7;
+
+struct Bench {
+    env: TestEnv,
+    results: Vec<(&'static str, u64)>,
+}
+
+// Bump searches cost compute, so every address the instructions derive from has to be fixed
+fn keypair(seed: u8) -> Keypair {
+    keypair_from_seed(&[seed; 32]).unwrap()
+}
+
+impl Bench {
+    async fn user(&mut self, seed: u8) -> Keypair {
+        let user = keypair(seed);
+        self.env.fund(&user.pubkey()).await;
+        user
+    }
+
+    // Record an instruction's compute units, then run it for real so later ones can build on it
+    async fn measure(&mut self, name: &'static str, ix: Instruction, signers: &[&Keypair]) {
+        let units = self
+            .env
+            .compute_units(std::slice::from_ref(&ix), signers)
+            .await;
+        self.env.send(&[ix], signers).await.unwrap();
+        self.results.push((name, units));
+    }
+
+    async fn measure_as_authority(&mut self, name: &'static str, ix: Instruction) {
+        let authority = self.env.authority.insecure_clone();
+        self.measure(name, ix, &[&authority]).await;
+    }
+
+    async fn measure_redeem(
+        &mut self,
+        name: &'static str,
+        user: &Keypair,
+        nft_mint: &solana_sdk::pubkey::Pubkey,
+        extras: &RedeemExtras,
+    ) {
+        let ix = self.env.redeem_ix(user, nft_mint, 1, extras);
+        let authority = self.env.authority.insecure_clone();
+        self.measure(name, ix, &[user, &authority]).await;
+    }
+}
+
+async fn run() -> Vec<(&'static str, u64)> {
+    let mut bench = Bench {
+        env: TestEnv::start_with_authority(keypair(0)).await,
+        results: vec![],
+    };
+    let authority = bench.env.authority.pubkey();
+    let nft_config = bench.env.nft_config;
+    let redeemable_mint = bench.env.redeemable_mint;
+
+    // Config administration
+    bench
+        .measure_as_authority(
+            "initialize",
+            instructions::initialize(&authority, CONFIG_ID, 5),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "create_redeemable_token",
+            instructions::create_redeemable_token(&authority, &nft_config, REDEEMABLE_SUPPLY),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "increase_token_supply",
+            instructions::increase_token_supply(&authority, &nft_config, 1),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "increase_max_nft_types",
+            instructions::increase_max_nft_types(&authority, &nft_config, MAX_NFT_TYPES as u8),
+        )
+        .await;
+    let (name, symbol, uri) = type_metadata(0);
+    bench
+        .measure_as_authority(
+            "add_nft_type",
+            instructions::add_nft_type(&authority, &nft_config, name, symbol, uri),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "set_reward_amount",
+            instructions::set_reward_amount(&authority, &nft_config, 0, 100),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "edit_reward_amount",
+            instructions::edit_reward_amount(&authority, &nft_config, 0, 150),
+        )
+        .await;
+    let tiers = [(1, 100), (10, 200), (100, 300)].map(|(units_threshold, boost_bps)| TierConfig {
+        units_threshold,
+        boost_bps,
+    });
+    bench
+        .measure_as_authority(
+            "set_tier_thresholds",
+            instructions::set_tier_thresholds(&authority, &nft_config, tiers),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "set_referral_config",
+            instructions::set_referral_config(&authority, &nft_config, 1_000, 1_000_000),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "set_streak_bonus",
+            instructions::set_streak_bonus(&authority, &nft_config, 10, 100),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "set_redemption_limits",
+            instructions::set_redemption_limits(&authority, &nft_config, 1_000_000, 10_000_000),
+        )
+        .await;
+    let curve = RewardCurve {
+        mode: CurveMode::Linear,
+        reference_balance: 2 * REDEEMABLE_SUPPLY,
+        min_multiplier_bps: 5_000,
+        exponent: 1,
+    };
+    bench
+        .measure_as_authority(
+            "set_reward_curve",
+            instructions::set_reward_curve(&authority, &nft_config, 0, Some(curve)),
+        )
+        .await;
+    bench
+        .measure(
+            "quote_redemption",
+            instructions::quote_redemption(&authority, &nft_config, 0),
+            &[],
+        )
+        .await;
+
+    // Minting and redemption
+    let user = bench.user(1).await;
+    bench
+        .measure(
+            "mint_nft",
+            instructions::mint_nft(&user.pubkey(), &nft_config, 0, 1),
+            &[&user],
+        )
+        .await;
+    let batch_types = [
+        bench.env.add_type(Some(100)).await,
+        bench.env.add_type(Some(100)).await,
+    ];
+    bench
+        .measure(
+            "mint_nft_batch",
+            instructions::mint_nft_batch(
+                &user.pubkey(),
+                &nft_config,
+                batch_types
+                    .map(|nft_type_index| MintBatchEntry { nft_type_index })
+                    .to_vec(),
+            ),
+            &[&user],
+        )
+        .await;
+    let nft_mint = pda::nft_mint(&nft_config, 0).0;
+    bench
+        .measure_redeem(
+            "redeem_and_burn_nft",
+            &user,
+            &nft_mint,
+            &RedeemExtras::default(),
+        )
+        .await;
+    let batch_mints = batch_types.map(|index| pda::nft_mint(&nft_config, index).0);
+    let authority_signer = bench.env.authority.insecure_clone();
+    bench
+        .measure(
+            "redeem_and_burn_batch",
+            instructions::redeem_and_burn_batch(
+                &user.pubkey(),
+                &authority,
+                &nft_config,
+                &batch_mints,
+                0,
+                &RedeemBatchExtras::default(),
+            ),
+            &[&user, &authority_signer],
+        )
+        .await;
+    bench
+        .measure(
+            "get_user_info",
+            instructions::get_user_info(&user.pubkey(), &nft_config, &nft_mint),
+            &[&user],
+        )
+        .await;
+
+    // Vesting
+    let vesting_index = bench.env.add_type(Some(100)).await;
+    bench
+        .measure_as_authority(
+            "set_vesting_schedule",
+            instructions::set_vesting_schedule(
+                &authority,
+                &nft_config,
+                vesting_index,
+                Some(VestingSchedule {
+                    min_reward: 1,
+                    cliff_seconds: 0,
+                    duration_seconds: 100,
+                }),
+            ),
+        )
+        .await;
+    let vesting_mint = bench.env.seed_nft(&user.pubkey(), vesting_index, 1).await;
+    let extras = RedeemExtras {
+        vesting_position_id: Some(0),
+        ..RedeemExtras::default()
+    };
+    bench
+        .measure_redeem("redeem_and_burn_nft/vesting", &user, &vesting_mint, &extras)
+        .await;
+    bench.env.advance_time(50).await;
+    bench
+        .measure(
+            "claim_vested",
+            instructions::claim_vested(&user.pubkey(), &nft_config, 0),
+            &[&user],
+        )
+        .await;
+
+    // Staking
+    bench
+        .measure_as_authority(
+            "set_staking_reward_rate",
+            instructions::set_staking_reward_rate(&authority, &nft_config, 0, 2),
+        )
+        .await;
+    bench
+        .measure_as_authority(
+            "create_reward_vault",
+            instructions::create_reward_vault(&authority, &nft_config, &redeemable_mint),
+        )
+        .await;
+    let reward_vault = pda::reward_vault(&nft_config, &redeemable_mint).0;
+    bench
+        .env
+        .mint_to(&redeemable_mint, &reward_vault, 10_000)
+        .await;
+    // mint_nft only mints a single NFT, which was redeemed above
+    bench.env.seed_nft(&user.pubkey(), 0, 3).await;
+    bench
+        .measure(
+            "stake_nft",
+            instructions::stake_nft(&user.pubkey(), &nft_config, &nft_mint, 2),
+            &[&user],
+        )
+        .await;
+    bench.env.advance_time(10).await;
+    bench
+        .measure(
+            "claim_staking_rewards",
+            instructions::claim_staking_rewards(&user.pubkey(), &nft_config, &nft_mint),
+            &[&user],
+        )
+        .await;
+    let extras = RedeemExtras {
+        from_stake: true,
+        ..RedeemExtras::default()
+    };
+    bench
+        .measure_redeem("redeem_and_burn_nft/staked", &user, &nft_mint, &extras)
+        .await;
+    bench
+        .measure(
+            "unstake_nft",
+            instructions::unstake_nft(&user.pubkey(), &nft_config, &nft_mint, 1),
+            &[&user],
+        )
+        .await;
+
+    // Achievements
+    bench
+        .measure_as_authority(
+            "create_achievement",
+            instructions::create_achievement(
+                &authority,
+                &nft_config,
+                1,
+                AchievementKind::LifetimeUnits { threshold: 1 },
+                "Recycler".to_string(),
+                "RCY".to_string(),
+                "https://example.com/badges/recycler.json".to_string(),
+            ),
+        )
+        .await;
+    bench
+        .measure(
+            "claim_achievement",
+            instructions::claim_achievement(&user.pubkey(), &nft_config, 1),
+            &[&user],
+        )
+        .await;
+
+    // Referrals and campaigns
+    let referrer = bench.user(2).await;
+    let referred = bench.user(3).await;
+    bench
+        .measure(
+            "set_referrer",
+            instructions::set_referrer(&referred.pubkey(), &nft_config, &referrer.pubkey()),
+            &[&referred],
+        )
+        .await;
+    let now = bench.env.clock().await.unix_timestamp;
+    bench
+        .measure_as_authority(
+            "create_campaign",
+            instructions::create_campaign(
+                &authority,
+                &nft_config,
+                1,
+                now - 10,
+                now + 1_000,
+                vec![0],
+                15_000,
+                1_000,
+            ),
+        )
+        .await;
+
+    // Multi-leg rewards
+    let legs_index = bench.env.add_type(None).await;
+    let reward_mint = bench.env.create_mint(6);
+    bench
+        .env
+        .send_as_authority(&[instructions::create_reward_vault(
+            &authority,
+            &nft_config,
+            &reward_mint,
+        )])
+        .await
+        .unwrap();
+    let legs = vec![
+        RewardLeg::Token {
+            mint: reward_mint,
+            amount: 10,
+        },
+        RewardLeg::Lamports { amount: 5_000 },
+    ];
+    bench
+        .measure_as_authority(
+            "set_reward_legs",
+            instructions::set_reward_legs(&authority, &nft_config, legs_index, legs),
+        )
+        .await;
+    let reward_vault = pda::reward_vault(&nft_config, &reward_mint).0;
+    bench.env.mint_to(&reward_mint, &reward_vault, 1_000).await;
+    bench.env.fund(&pda::lamport_vault(&nft_config).0).await;
+    bench
+        .env
+        .create_token_account(&reward_mint, &user.pubkey())
+        .await;
+    let legs_mint = bench.env.seed_nft(&user.pubkey(), legs_index, 1).await;
+    bench
+        .measure(
+            "redeem_and_burn_nft_multi",
+            instructions::redeem_and_burn_nft_multi(
+                &user.pubkey(),
+                &nft_config,
+                &legs_mint,
+                1,
+                vec![10, 5_000],
+                &[reward_mint],
+            ),
+            &[&user],
+        )
+        .await;
+
+    // Sponsors
+    let sponsor = bench.user(4).await;
+    let sponsor_key = sponsor.pubkey();
+    let sponsor_mint = bench.env.create_mint(0);
+    let sponsor_tokens = bench
+        .env
+        .create_token_account(&sponsor_mint, &sponsor_key)
+        .await;
+    bench
+        .env
+        .mint_to(&sponsor_mint, &sponsor_tokens, 1_000)
+        .await;
+    bench
+        .measure(
+            "create_sponsor_pool",
+            instructions::create_sponsor_pool(&sponsor_key, &nft_config, &sponsor_mint),
+            &[&sponsor],
+        )
+        .await;
+    bench
+        .measure(
+            "set_sponsor_payout_rate",
+            instructions::set_sponsor_payout_rate(&sponsor_key, &nft_config, &sponsor_mint, 0, 5),
+            &[&sponsor],
+        )
+        .await;
+    bench
+        .measure(
+            "deposit_sponsor_tokens",
+            instructions::deposit_sponsor_tokens(&sponsor_key, &nft_config, &sponsor_mint, 500),
+            &[&sponsor],
+        )
+        .await;
+    bench
+        .measure(
+            "withdraw_sponsor_tokens",
+            instructions::withdraw_sponsor_tokens(&sponsor_key, &nft_config, &sponsor_mint, 100),
+            &[&sponsor],
+        )
+        .await;
+
+    // Migrating a config out of the originally deployed layout, on a fresh bank
+    bench.env = TestEnv::start_with_authority(keypair(BASELINE_AUTHORITY_SEED)).await;
+    let authority = bench.env.authority.pubkey();
+    let nft_config = pda::legacy_nft_config(&authority).0;
+    bench
+        .env
+        .set_account(&nft_config, BASELINE_CONFIG.to_vec(), trash::ID);
+    bench
+        .measure_as_authority(
+            "migrate_config",
+            instructions::migrate_legacy_config(&authority),
+        )
+        .await;
+
+    bench.results
+}
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/bench/baseline-sbf.txt")
+}
+
+fn parse_baseline(contents: &str) -> BTreeMap<String, u64> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, units) = line
+                .split_once(char::is_whitespace)
+                .unwrap_or_else(|| panic!("malformed baseline line: {line}"));
+            let units = units
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("malformed baseline line: {line}"));
+            (name.to_string(), units)
+        })
+        .collect()
+}
+
+fn format_baseline(results: &[(&str, u64)]) -> String {
+    let mut contents = String::from(
+        "# Compute units per instruction on the SBF runtime, recorded with\n\
+         # BENCH_UPDATE=1 SBF_OUT_DIR=$PWD/target/deploy cargo test -p trash --test bench\n",
+    );
+    for (name, units) in results {
+        contents.push_str(&format!("{name} {units}\n"));
+    }
+    contents
+}
+
+#[tokio::test]
+async fn compute_units_stay_within_baseline() {
+    // The native build only meters the CPIs it makes, which says nothing about the program
+    assert!(
+        runs_as_sbf(),
+        "compute units are only meaningful on SBF; set SBF_OUT_DIR to a build of trash.so"
+    );
+    let results = run().await;
+    let path = baseline_path();
+
+    if env::var("BENCH_UPDATE").is_ok() {
+        fs::write(&path, format_baseline(&results)).unwrap();
+        println!("recorded {} instructions to {path:?}", results.len());
+        return;
+    }
+
+    let contents = fs::read_to_string(&path).unwrap_or_else(|_| {
+        for (name, units) in &results {
+            println!("{name:<32} {units:>8}");
+        }
+        panic!("no SBF baseline at {path:?}; record one with BENCH_UPDATE=1")
+    });
+    let mut baseline = parse_baseline(&contents);
+    let threshold: u64 = env::var("BENCH_THRESHOLD_PCT")
+        .map(|pct| {
+            pct.parse()
+                .expect("BENCH_THRESHOLD_PCT must be a whole percentage")
+        })
+        .unwrap_or(DEFAULT_THRESHOLD_PCT);
+
+    let mut failures = vec![];
+    println!(
+        "{:<32} {:>8} {:>8} {:>8}",
+        "instruction", "baseline", "units", "change"
+    );
+    for (name, units) in &results {
+        let Some(base) = baseline.remove(*name) else {
+            println!("{name:<32} {:>8} {units:>8}", "-");
+            failures.push(format!("{name} is not in the baseline"));
+            continue;
+        };
+        let change = *units as i64 - base as i64;
+        println!("{name:<32} {base:>8} {units:>8} {change:>+8}");
+        if units * 100 > base * (100 + threshold) {
+            failures.push(format!(
+                "{name} uses {units} compute units, {change} more than its baseline of {base}"
+            ));
+        }
+    }
+    for name in baseline.keys() {
+        failures.push(format!("{name} is in the baseline but was not measured"));
+    }
+
+    assert!(
+        failures.is_empty(),
+        "compute units regressed beyond {threshold}% \
+         (rerun with BENCH_UPDATE=1 if this is intended):\n{}",
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,472 @@
+//! Random instruction sequences against the program, checking invariants after every step.
+//!
+//! Each run replays `FUZZ_RUNS` sequences of `FUZZ_STEPS` instructions. A failing sequence
+//! reports its seed; rerun it alone with `FUZZ_SEED=<seed> cargo test -p trash --test fuzz`.
+
+// The harness is shared with the integration tests, which use more of it
+#![allow(dead_code)]
+
+#[path = "../integration/harness.rs"]
+mod harness;
+#[path = "../integration/metadata_stub.rs"]
+mod metadata_stub;
+
+use std::env;
+use std::fmt;
+
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::metadata::mpl_token_metadata::accounts::Metadata;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use trash::MintBatchEntry;
+use trash4coin_client::instructions::{self, RedeemBatchExtras, RedeemExtras};
+use trash4coin_client::pda;
+
+use harness::{type_metadata, TestEnv, CONFIG_ID, REDEEMABLE_SUPPLY};
+
+const DEFAULT_RUNS: u64 = 8;
+const DEFAULT_STEPS: usize = 32;
+// Type indices are drawn past the configured ones so invalid indices come up too
+const TYPE_RANGE: u8 = 4;
+
+#[derive(Clone, Copy)]
+enum Op {
+    Initialize {
+        signer: usize,
+        config_id: u64,
+        max_nft_types: u8,
+    },
+    AddNftType {
+        signer: usize,
+        nft_type_index: u8,
+    },
+    SetRewardAmount {
+        signer: usize,
+        nft_type_index: u8,
+        reward_amount: u64,
+    },
+    MintNft {
+        signer: usize,
+        nft_type_index: u8,
+        amount: u64,
+    },
+    // Two types, which may repeat
+    MintNftBatch {
+        signer: usize,
+        entries: [u8; 2],
+    },
+    Redeem {
+        signer: usize,
+        authority: usize,
+        nft_type_index: u8,
+        amount: u64,
+    },
+    // Redeems the signer's whole balance of every type, co-signed by the authority
+    RedeemBatch {
+        signer: usize,
+    },
+}
+
+impl Op {
+    fn random(rng: &mut StdRng, actors: usize) -> Self {
+        let signer = rng.gen_range(0..actors);
+        let nft_type_index = rng.gen_range(0..TYPE_RANGE);
+
+        match rng.gen_range(0..7) {
+            0 => Op::Initialize {
+                signer,
+                config_id: rng.gen_range(CONFIG_ID..CONFIG_ID + 2),
+                max_nft_types: rng.gen_range(0..12),
+            },
+            1 => Op::AddNftType {
+                signer,
+                nft_type_index,
+            },
+            2 => Op::SetRewardAmount {
+                signer,
+                nft_type_index,
+                reward_amount: rng.gen_range(0..1_000),
+            },
+            3 => Op::MintNft {
+                signer,
+                nft_type_index,
+                amount: rng.gen_range(0..6),
+            },
+            4 => Op::MintNftBatch {
+                signer,
+                entries: [(); 2].map(|_| rng.gen_range(0..TYPE_RANGE)),
+            },
+            5 => Op::RedeemBatch { signer },
+            _ => Op::Redeem {
+                signer,
+                // Usually the real authority, sometimes anyone else
+                authority: if rng.gen_bool(0.8) {
+                    0
+                } else {
+                    rng.gen_range(0..actors)
+                },
+                nft_type_index,
+                amount: rng.gen_range(0..6),
+            },
+        }
+    }
+
+    fn signer(&self) -> usize {
+        match *self {
+            Op::Initialize { signer, .. }
+            | Op::AddNftType { signer, .. }
+            | Op::SetRewardAmount { signer, .. }
+            | Op::MintNft { signer, .. }
+            | Op::MintNftBatch { signer, .. }
+            | Op::Redeem { signer, .. }
+            | Op::RedeemBatch { signer } => signer,
+        }
+    }
+
+    // Instructions that change the config's types and rewards
+    fn is_admin(&self) -> bool {
+        matches!(self, Op::AddNftType { .. } | Op::SetRewardAmount { .. })
+    }
+}
+
+impl fmt::Debug for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Op::Initialize {
+                signer,
+                config_id,
+                max_nft_types,
+            } => write!(f, "initialize(signer {signer}, config {config_id}, max {max_nft_types})"),
+            Op::AddNftType {
+                signer,
+                nft_type_index,
+            } => write!(f, "add_nft_type(signer {signer}, metadata {nft_type_index})"),
+            Op::SetRewardAmount {
+                signer,
+                nft_type_index,
+                reward_amount,
+            } => write!(
+                f,
+                "set_reward_amount(signer {signer}, type {nft_type_index}, {reward_amount})"
+            ),
+            Op::MintNft {
+                signer,
+                nft_type_index,
+                amount,
+            } => write!(f, "mint_nft(signer {signer}, type {nft_type_index}, {amount})"),
+            Op::MintNftBatch { signer, entries } => {
+                write!(f, "mint_nft_batch(signer {signer}, {entries:?})")
+            }
+            Op::Redeem {
+                signer,
+                authority,
+                nft_type_index,
+                amount,
+            } => write!(
+                f,
+                "redeem_and_burn_nft(signer {signer}, authority {authority}, type {nft_type_index}, {amount})"
+            ),
+            Op::RedeemBatch { signer } => write!(f, "redeem_and_burn_batch(signer {signer})"),
+        }
+    }
+}
+
+// Actor 0 is the config authority
+struct Fuzzer {
+    env: TestEnv,
+    actors: Vec<Keypair>,
+    history: Vec<Op>,
+    seed: u64,
+    expected_paid: u64,
+}
+
+impl Fuzzer {
+    async fn new(seed: u64) -> Self {
+        let mut env = TestEnv::new().await;
+        let mut actors = vec![env.authority.insecure_clone()];
+        for _ in 0..2 {
+            actors.push(env.new_user().await);
+        }
+
+        Self {
+            env,
+            actors,
+            history: vec![],
+            seed,
+            expected_paid: 0,
+        }
+    }
+
+    async fn step(&mut self, op: Op) {
+        self.history.push(op);
+        let config_before = self.config_snapshot().await;
+        let expected_reward = self.expected_reward(op).await;
+
+        let succeeded = self.execute(op).await;
+
+        // Only the authority can change the config's types and rewards
+        if op.signer() != 0 {
+            self.check(
+                !(succeeded && op.is_admin()),
+                "non-authority changed the config",
+            );
+            let config_after = self.config_snapshot().await;
+            self.check(
+                config_before == config_after,
+                "config changed by a non-authority transaction",
+            );
+        }
+
+        let authority = match op {
+            Op::Redeem { authority, .. } => Some(authority),
+            Op::RedeemBatch { .. } => Some(0),
+            _ => None,
+        };
+        if let (Some(authority), true) = (authority, succeeded) {
+            let Some(reward) = expected_reward else {
+                return self.check(false, "redeemed a type without a reward amount");
+            };
+
+            // The reward moves from the co-signing authority's account to the user's, which
+            // only changes what users hold when exactly one side is the vault
+            match (op.signer(), authority) {
+                (0, 0) => {}
+                (_, 0) => self.expected_paid += reward,
+                (0, _) => self.expected_paid -= reward,
+                _ => {}
+            }
+        }
+
+        self.check_vault().await;
+    }
+
+    async fn execute(&mut self, op: Op) -> bool {
+        let nft_config = self.env.nft_config;
+
+        let (ix, signers) = match op {
+            Op::Initialize {
+                signer,
+                config_id,
+                max_nft_types,
+            } => (
+                instructions::initialize(&self.key(signer), config_id, max_nft_types),
+                vec![signer],
+            ),
+            Op::AddNftType {
+                signer,
+                nft_type_index,
+            } => {
+                let (name, symbol, uri) = type_metadata(nft_type_index);
+                (
+                    instructions::add_nft_type(&self.key(signer), &nft_config, name, symbol, uri),
+                    vec![signer],
+                )
+            }
+            Op::SetRewardAmount {
+                signer,
+                nft_type_index,
+                reward_amount,
+            } => (
+                instructions::set_reward_amount(
+                    &self.key(signer),
+                    &nft_config,
+                    nft_type_index,
+                    reward_amount,
+                ),
+                vec![signer],
+            ),
+            Op::MintNft {
+                signer,
+                nft_type_index,
+                amount,
+            } => (
+                instructions::mint_nft(&self.key(signer), &nft_config, nft_type_index, amount),
+                vec![signer],
+            ),
+            Op::MintNftBatch { signer, entries } => {
+                let entries = entries
+                    .iter()
+                    .map(|&nft_type_index| MintBatchEntry { nft_type_index })
+                    .collect();
+                (
+                    instructions::mint_nft_batch(&self.key(signer), &nft_config, entries),
+                    vec![signer],
+                )
+            }
+            Op::Redeem {
+                signer,
+                authority,
+                nft_type_index,
+                amount,
+            } => (
+                instructions::redeem_and_burn_nft(
+                    &self.key(signer),
+                    &self.key(authority),
+                    &nft_config,
+                    &pda::nft_mint(&nft_config, nft_type_index).0,
+                    amount,
+                    0,
+                    None,
+                    &RedeemExtras::default(),
+                ),
+                vec![signer, authority],
+            ),
+            Op::RedeemBatch { signer } => {
+                let nft_mints: Vec<Pubkey> = self
+                    .held_mints(signer)
+                    .await
+                    .into_iter()
+                    .map(|(nft_type_index, _)| pda::nft_mint(&nft_config, nft_type_index).0)
+                    .collect();
+                (
+                    instructions::redeem_and_burn_batch(
+                        &self.key(signer),
+                        &self.key(0),
+                        &nft_config,
+                        &nft_mints,
+                        0,
+                        &RedeemBatchExtras::default(),
+                    ),
+                    vec![signer, 0],
+                )
+            }
+        };
+
+        let signers: Vec<Keypair> = signers
+            .into_iter()
+            .map(|index| self.actors[index].insecure_clone())
+            .collect();
+        let signers: Vec<&Keypair> = signers.iter().collect();
+        self.env.send(&[ix], &signers).await.is_ok()
+    }
+
+    // Reward the program should pay for a redemption, if every redeemed type has a reward
+    async fn expected_reward(&mut self, op: Op) -> Option<u64> {
+        match op {
+            Op::Redeem {
+                nft_type_index,
+                amount,
+                ..
+            } => self.type_reward(nft_type_index, amount).await,
+            Op::RedeemBatch { signer } => {
+                let mut total: u64 = 0;
+                for (nft_type_index, amount) in self.held_mints(signer).await {
+                    total = total.checked_add(self.type_reward(nft_type_index, amount).await?)?;
+                }
+                Some(total)
+            }
+            _ => None,
+        }
+    }
+
+    // The type indices and balances of the type mints `actor` holds
+    async fn held_mints(&mut self, actor: usize) -> Vec<(u8, u64)> {
+        let mut held = vec![];
+        for nft_type_index in 0..TYPE_RANGE {
+            let nft_mint = pda::nft_mint(&self.env.nft_config, nft_type_index).0;
+            let account = get_associated_token_address(&self.key(actor), &nft_mint);
+            if self.env.raw_account(&account).await.is_some() {
+                let balance = self.env.token_balance(&account).await;
+                if balance > 0 {
+                    held.push((nft_type_index, balance));
+                }
+            }
+        }
+        held
+    }
+
+    async fn type_reward(&mut self, nft_type_index: u8, amount: u64) -> Option<u64> {
+        // Types are matched by the name and uri mint_nft copied into the metadata
+        let nft_mint = pda::nft_mint(&self.env.nft_config, nft_type_index).0;
+        let account = self.env.raw_account(&pda::metadata(&nft_mint).0).await?;
+        let metadata = Metadata::safe_deserialize(&account.data).ok()?;
+        let name = metadata.name.trim_end_matches('\0');
+        let uri = metadata.uri.trim_end_matches('\0');
+
+        self.env
+            .config()
+            .await
+            .nft_types()
+            .iter()
+            .find(|t| t.name() == name && t.uri() == uri)
+            .and_then(|t| t.reward_amount())
+            .and_then(|reward| reward.checked_mul(amount))
+    }
+
+    // Rewards paid never exceed what was funded, and match the configured reward amounts
+    async fn check_vault(&mut self) {
+        let redeemable_mint = self.env.redeemable_mint;
+        let vault = get_associated_token_address(&self.key(0), &redeemable_mint);
+        let vault_balance = self.env.token_balance(&vault).await;
+
+        let mut paid = 0;
+        for index in 1..self.actors.len() {
+            let account = get_associated_token_address(&self.key(index), &redeemable_mint);
+            if self.env.raw_account(&account).await.is_some() {
+                paid += self.env.token_balance(&account).await;
+            }
+        }
+
+        self.check(
+            vault_balance + paid == REDEEMABLE_SUPPLY,
+            "redeemable tokens created or lost",
+        );
+        self.check(
+            paid <= REDEEMABLE_SUPPLY,
+            "paid more than the vault was funded with",
+        );
+        self.check(
+            paid == self.expected_paid,
+            "paid a different reward than configured",
+        );
+    }
+
+    async fn config_snapshot(&mut self) -> Vec<u8> {
+        let config = self.env.config().await;
+        let mut snapshot = config.authority.to_bytes().to_vec();
+        snapshot.push(config.max_nft_types);
+        snapshot.extend_from_slice(bytemuck::cast_slice(config.nft_types()));
+        snapshot
+    }
+
+    fn key(&self, index: usize) -> Pubkey {
+        self.actors[index].pubkey()
+    }
+
+    #[track_caller]
+    fn check(&self, invariant: bool, message: &str) {
+        assert!(
+            invariant,
+            "{message}\nseed: {}\nsequence: {:#?}",
+            self.seed, self.history
+        );
+    }
+}
+
+fn env_var<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok().and_then(|value| value.parse().ok())
+}
+
+#[tokio::test]
+async fn random_instruction_sequences_keep_invariants() {
+    let steps = env_var("FUZZ_STEPS").unwrap_or(DEFAULT_STEPS);
+    let seeds: Vec<u64> = match env_var("FUZZ_SEED") {
+        Some(seed) => vec![seed],
+        None => {
+            let runs = env_var("FUZZ_RUNS").unwrap_or(DEFAULT_RUNS);
+            let first = rand::random::<u64>();
+            (0..runs).map(|run| first.wrapping_add(run)).collect()
+        }
+    };
+
+    for seed in seeds {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut fuzzer = Fuzzer::new(seed).await;
+        for _ in 0..steps {
+            let op = Op::random(&mut rng, fuzzer.actors.len());
+            fuzzer.step(op).await;
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::signature::Signer;
+use trash::{Achievement, AchievementKind, ErrorCode, MAX_NAME_LENGTH};
+use trash4coin_client::instructions::{self, RedeemExtras};
+use trash4coin_client::pda;
+
+use crate::harness::{assert_error, TestEnv};
+
+const ACHIEVEMENT_ID: u64 = 1;
+
+async fn create_achievement(env: &mut TestEnv, threshold: u64) {
+    let authority = env.authority.pubkey();
+    env.send_as_authority(&[instructions::create_achievement(
+        &authority,
+        &env.nft_config,
+        ACHIEVEMENT_ID,
+        AchievementKind::LifetimeUnits { threshold },
+        "Recycler".to_string(),
+        "RCY".to_string(),
+        "https://example.com/badges/recycler.json".to_string(),
+    )])
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn claim_achievement_mints_a_badge() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    create_achievement(&mut env, 2).await;
+
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+    env.redeem(&user, &nft_mint, 2, &RedeemExtras::default())
+        .await
+        .unwrap();
+
+    let ix = instructions::claim_achievement(&user.pubkey(), &env.nft_config, ACHIEVEMENT_ID);
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    let achievement_key = pda::achievement(&env.nft_config, ACHIEVEMENT_ID).0;
+    let badge_mint = pda::badge_mint(&achievement_key, &user.pubkey()).0;
+    let badge = get_associated_token_address(&user.pubkey(), &badge_mint);
+    assert_eq!(env.token_balance(&badge).await, 1);
+    assert!(env
+        .raw_account(&pda::master_edition(&badge_mint).0)
+        .await
+        .is_some());
+
+    let achievement: Achievement = env.account(&achievement_key).await;
+    assert_eq!(achievement.claims, 1);
+}
+
+#[tokio::test]
+async fn create_achievement_rejects_long_metadata() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+
+    let ix = instructions::create_achievement(
+        &authority,
+        &env.nft_config,
+        ACHIEVEMENT_ID,
+        AchievementKind::DistinctTypes { threshold: 1 },
+        "x".repeat(MAX_NAME_LENGTH + 1),
+        "RCY".to_string(),
+        "https://example.com/badges/recycler.json".to_string(),
+    );
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::MetadataTooLong,
+    );
+}
+
+#[tokio::test]
+async fn claim_achievement_requires_meeting_the_threshold() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    create_achievement(&mut env, 2).await;
+
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+
+    let ix = instructions::claim_achievement(&user.pubkey(), &env.nft_config, ACHIEVEMENT_ID);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::AchievementNotEarned,
+    );
+}
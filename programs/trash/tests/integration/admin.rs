@@ -0,0 +1,374 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::signature::Signer;
+use trash::{CurveMode, ErrorCode, RewardCurve, TierConfig, VestingSchedule};
+use trash4coin_client::instructions;
+
+use crate::harness::{assert_error, TestEnv, CONFIG_ID, REDEEMABLE_SUPPLY};
+
+#[tokio::test]
+async fn initialize_and_create_redeemable_token() {
+    let mut env = TestEnv::new().await;
+
+    let config = env.config().await;
+    assert_eq!(config.authority, env.authority.pubkey());
+    assert_eq!(config.config_id, CONFIG_ID);
+    assert_eq!(config.max_nft_types, 10);
+    assert!(config.nft_types().is_empty());
+
+    let vault = get_associated_token_address(&env.authority.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&vault).await, REDEEMABLE_SUPPLY);
+}
+
+#[tokio::test]
+async fn initialize_rejects_out_of_range_max_nft_types() {
+    let mut env = TestEnv::start().await;
+    let authority = env.authority.pubkey();
+
+    for max_nft_types in [0, 11] {
+        let result = env
+            .send_as_authority(&[instructions::initialize(
+                &authority,
+                CONFIG_ID,
+                max_nft_types,
+            )])
+            .await;
+        assert_error(result, ErrorCode::InvalidMaxNFTTypes);
+    }
+}
+
+#[tokio::test]
+async fn add_nft_type_and_set_rewards() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+
+    env.send_as_authority(&[instructions::edit_reward_amount(
+        &authority,
+        &env.nft_config,
+        index,
+        250,
+    )])
+    .await
+    .unwrap();
+
+    let config = env.config().await;
+    assert_eq!(config.nft_types().len(), 1);
+    assert_eq!(config.nft_types()[0].name(), "Trash type 0");
+    assert_eq!(config.nft_types()[0].reward_amount(), Some(250));
+}
+
+#[tokio::test]
+async fn add_nft_type_rejects_empty_fields() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+
+    let cases = [
+        ("", "T", "https://example.com", ErrorCode::EmptyName),
+        ("Name", "", "https://example.com", ErrorCode::EmptySymbol),
+        ("Name", "T", "", ErrorCode::EmptyURI),
+    ];
+    for (name, symbol, uri, error) in cases {
+        let ix = instructions::add_nft_type(
+            &authority,
+            &env.nft_config,
+            name.to_string(),
+            symbol.to_string(),
+            uri.to_string(),
+        );
+        assert_error(env.send_as_authority(&[ix]).await, error);
+    }
+}
+
+#[tokio::test]
+async fn add_nft_type_stops_at_max_nft_types() {
+    let mut env = TestEnv::start().await;
+    let authority = env.authority.pubkey();
+    env.send_as_authority(&[instructions::initialize(&authority, CONFIG_ID, 1)])
+        .await
+        .unwrap();
+    env.add_type(None).await;
+
+    let ix = instructions::add_nft_type(
+        &authority,
+        &env.nft_config,
+        "Second".to_string(),
+        "S".to_string(),
+        "https://example.com/second".to_string(),
+    );
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::MaxNFTTypesReached,
+    );
+}
+
+#[tokio::test]
+async fn add_nft_type_rejects_metadata_longer_than_its_fields() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+
+    let ix = instructions::add_nft_type(
+        &authority,
+        &env.nft_config,
+        "N".repeat(33),
+        "S".to_string(),
+        "https://example.com/long".to_string(),
+    );
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::MetadataTooLong,
+    );
+}
+
+#[tokio::test]
+async fn set_reward_amount_validates_type_and_amount() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    env.add_type(None).await;
+
+    let ix = instructions::set_reward_amount(&authority, &env.nft_config, 1, 100);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidNFTType,
+    );
+
+    let ix = instructions::set_reward_amount(&authority, &env.nft_config, 0, 0);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidRewardAmount,
+    );
+}
+
+#[tokio::test]
+async fn edit_reward_amount_requires_the_config_authority() {
+    let mut env = TestEnv::new().await;
+    env.add_type(Some(100)).await;
+    let intruder = env.new_user().await;
+
+    let ix = instructions::edit_reward_amount(&intruder.pubkey(), &env.nft_config, 0, 1);
+    assert_error(
+        env.send(&[ix], &[&intruder]).await,
+        ErrorCode::UnauthorizedAccess,
+    );
+}
+
+#[tokio::test]
+async fn increase_max_nft_types_must_grow() {
+    let mut env = TestEnv::start().await;
+    let authority = env.authority.pubkey();
+    env.send_as_authority(&[instructions::initialize(&authority, CONFIG_ID, 5)])
+        .await
+        .unwrap();
+
+    env.send_as_authority(&[instructions::increase_max_nft_types(
+        &authority,
+        &env.nft_config,
+        8,
+    )])
+    .await
+    .unwrap();
+    assert_eq!(env.config().await.max_nft_types, 8);
+
+    let ix = instructions::increase_max_nft_types(&authority, &env.nft_config, 8);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidNewMaxNFTTypes,
+    );
+
+    // The config has a fixed number of type slots
+    let ix = instructions::increase_max_nft_types(&authority, &env.nft_config, 11);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidNewMaxNFTTypes,
+    );
+}
+
+#[tokio::test]
+async fn increase_token_supply_mints_to_the_vault() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let vault = get_associated_token_address(&authority, &env.redeemable_mint);
+
+    env.send_as_authority(&[instructions::increase_token_supply(
+        &authority,
+        &env.nft_config,
+        500,
+    )])
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&vault).await, REDEEMABLE_SUPPLY + 500);
+
+    let ix = instructions::increase_token_supply(&authority, &env.nft_config, 0);
+    assert_error(env.send_as_authority(&[ix]).await, ErrorCode::InvalidAmount);
+}
+
+#[tokio::test]
+async fn create_redeemable_token_rejects_zero_supply() {
+    let mut env = TestEnv::start().await;
+    let authority = env.authority.pubkey();
+    env.send_as_authority(&[instructions::initialize(&authority, CONFIG_ID, 10)])
+        .await
+        .unwrap();
+
+    let ix = instructions::create_redeemable_token(&authority, &env.nft_config, 0);
+    assert_error(env.send_as_authority(&[ix]).await, ErrorCode::InvalidAmount);
+}
+
+#[tokio::test]
+async fn type_settings_are_stored() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    let curve = RewardCurve {
+        mode: CurveMode::Linear,
+        reference_balance: 1_000,
+        min_multiplier_bps: 5_000,
+        exponent: 1,
+    };
+    let schedule = VestingSchedule {
+        min_reward: 1_000,
+        cliff_seconds: 10,
+        duration_seconds: 100,
+    };
+
+    env.send_as_authority(&[
+        instructions::set_reward_curve(&authority, &env.nft_config, index, Some(curve.clone())),
+        instructions::set_vesting_schedule(
+            &authority,
+            &env.nft_config,
+            index,
+            Some(schedule.clone()),
+        ),
+        instructions::set_staking_reward_rate(&authority, &env.nft_config, index, 3),
+    ])
+    .await
+    .unwrap();
+
+    let nft_type = env.config().await.nft_types[0];
+    assert!(nft_type.reward_curve() == Some(curve));
+    assert!(nft_type.vesting() == Some(schedule));
+    assert_eq!(nft_type.staking_reward_rate, 3);
+}
+
+#[tokio::test]
+async fn type_settings_are_validated() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+
+    let ix = instructions::set_reward_curve(
+        &authority,
+        &env.nft_config,
+        index,
+        Some(RewardCurve {
+            mode: CurveMode::Exponential,
+            reference_balance: 0,
+            min_multiplier_bps: 0,
+            exponent: 2,
+        }),
+    );
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidRewardCurve,
+    );
+
+    let ix = instructions::set_vesting_schedule(
+        &authority,
+        &env.nft_config,
+        index,
+        Some(VestingSchedule {
+            min_reward: 0,
+            cliff_seconds: 200,
+            duration_seconds: 100,
+        }),
+    );
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidVestingSchedule,
+    );
+
+    let ix = instructions::set_staking_reward_rate(&authority, &env.nft_config, index + 1, 3);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidNFTType,
+    );
+}
+
+#[tokio::test]
+async fn config_wide_settings_are_stored() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let tiers = [
+        TierConfig {
+            units_threshold: 1,
+            boost_bps: 100,
+        },
+        TierConfig {
+            units_threshold: 10,
+            boost_bps: 200,
+        },
+        TierConfig {
+            units_threshold: 100,
+            boost_bps: 300,
+        },
+    ];
+
+    env.send_as_authority(&[
+        instructions::set_tier_thresholds(&authority, &env.nft_config, tiers),
+        instructions::set_referral_config(&authority, &env.nft_config, 500, 1_000),
+        instructions::set_streak_bonus(&authority, &env.nft_config, 100, 1_000),
+        instructions::set_redemption_limits(&authority, &env.nft_config, 10, 100),
+    ])
+    .await
+    .unwrap();
+
+    let config = env.config().await;
+    assert_eq!(config.tiers[2].units_threshold, 100);
+    assert_eq!(config.referral_bps, 500);
+    assert_eq!(config.referral_cap, 1_000);
+    assert_eq!(config.streak_bonus_bps_per_day, 100);
+    assert_eq!(config.max_streak_bonus_bps, 1_000);
+    assert_eq!(config.max_reward_per_user_per_epoch, 10);
+    assert_eq!(config.max_reward_per_epoch, 100);
+}
+
+#[tokio::test]
+async fn config_wide_settings_are_validated() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+
+    let tiers = [
+        TierConfig {
+            units_threshold: 10,
+            boost_bps: 100,
+        },
+        TierConfig {
+            units_threshold: 5,
+            boost_bps: 200,
+        },
+        TierConfig::default(),
+    ];
+    let ix = instructions::set_tier_thresholds(&authority, &env.nft_config, tiers);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidTierThresholds,
+    );
+
+    let ix = instructions::set_referral_config(&authority, &env.nft_config, 10_001, 0);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidReferralConfig,
+    );
+}
+
+#[tokio::test]
+async fn admin_setters_require_the_config_authority() {
+    let mut env = TestEnv::new().await;
+    let intruder = env.new_user().await;
+    let intruder_key = intruder.pubkey();
+
+    let ix = instructions::set_redemption_limits(&intruder_key, &env.nft_config, 1, 1);
+    assert_error(
+        env.send(&[ix], &[&intruder]).await,
+        ErrorCode::UnauthorizedAccess,
+    );
+}
@@ -0,0 +1,262 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::signature::Signer;
+use trash::{Campaign, ErrorCode};
+use trash4coin_client::instructions::{self, RedeemBatchExtras, RedeemExtras};
+use trash4coin_client::pda;
+
+use crate::harness::{assert_error, replace_account, type_metadata, TestEnv, CONFIG_ID};
+
+const CAMPAIGN_ID: u64 = 7;
+
+#[tokio::test]
+async fn campaign_bonus_is_capped_by_the_budget() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    let now = env.clock().await.unix_timestamp;
+
+    // 50% bonus with room for one full bonus and part of a second
+    env.send_as_authority(&[instructions::create_campaign(
+        &authority,
+        &env.nft_config,
+        CAMPAIGN_ID,
+        now - 10,
+        now + 1_000,
+        vec![index],
+        15_000,
+        70,
+    )])
+    .await
+    .unwrap();
+
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+    let extras = RedeemExtras {
+        campaign_id: Some(CAMPAIGN_ID),
+        ..RedeemExtras::default()
+    };
+    env.redeem(&user, &nft_mint, 1, &extras).await.unwrap();
+    env.redeem(&user, &nft_mint, 1, &extras).await.unwrap();
+
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&user_redeemable).await, 150 + 120);
+    let campaign: Campaign = env
+        .account(&pda::campaign(&env.nft_config, CAMPAIGN_ID).0)
+        .await;
+    assert_eq!(campaign.remaining_budget, 0);
+}
+
+#[tokio::test]
+async fn forged_redemptions_do_not_spend_the_budget() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    let now = env.clock().await.unix_timestamp;
+
+    env.send_as_authority(&[instructions::create_campaign(
+        &authority,
+        &env.nft_config,
+        CAMPAIGN_ID,
+        now - 10,
+        now + 1_000,
+        vec![index],
+        15_000,
+        70,
+    )])
+    .await
+    .unwrap();
+
+    let attacker = env.new_user().await;
+    let extras = RedeemExtras {
+        campaign_id: Some(CAMPAIGN_ID),
+        ..RedeemExtras::default()
+    };
+    assert!(env
+        .forged_redeem(&attacker, index, 1, &extras)
+        .await
+        .is_err());
+
+    let campaign: Campaign = env
+        .account(&pda::campaign(&env.nft_config, CAMPAIGN_ID).0)
+        .await;
+    assert_eq!(campaign.remaining_budget, 70);
+}
+
+#[tokio::test]
+async fn batch_redeem_applies_the_campaign_to_each_covered_type() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let cans = env.add_type(Some(100)).await;
+    let bottles = env.add_type(Some(100)).await;
+    let jars = env.add_type(Some(100)).await;
+    let now = env.clock().await.unix_timestamp;
+
+    // 50% bonus on cans and bottles, with room for one full bonus and part of a second
+    env.send_as_authority(&[instructions::create_campaign(
+        &authority,
+        &env.nft_config,
+        CAMPAIGN_ID,
+        now - 10,
+        now + 1_000,
+        vec![cans, bottles],
+        15_000,
+        70,
+    )])
+    .await
+    .unwrap();
+
+    let user = env.new_user().await;
+    let nft_mints = [
+        env.seed_nft(&user.pubkey(), cans, 1).await,
+        env.seed_nft(&user.pubkey(), bottles, 1).await,
+        env.seed_nft(&user.pubkey(), jars, 1).await,
+    ];
+    let ix = instructions::redeem_and_burn_batch(
+        &user.pubkey(),
+        &authority,
+        &env.nft_config,
+        &nft_mints,
+        370,
+        &RedeemBatchExtras {
+            campaign_id: Some(CAMPAIGN_ID),
+            ..RedeemBatchExtras::default()
+        },
+    );
+    env.send_redeem(ix, &user).await.unwrap();
+
+    // Cans take a full bonus, bottles what is left of the budget and jars none
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&user_redeemable).await, 300 + 50 + 20);
+    let campaign: Campaign = env
+        .account(&pda::campaign(&env.nft_config, CAMPAIGN_ID).0)
+        .await;
+    assert_eq!(campaign.remaining_budget, 0);
+}
+
+#[tokio::test]
+async fn inactive_campaign_pays_no_bonus() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    let now = env.clock().await.unix_timestamp;
+
+    env.send_as_authority(&[instructions::create_campaign(
+        &authority,
+        &env.nft_config,
+        CAMPAIGN_ID,
+        now + 100,
+        now + 1_000,
+        vec![index],
+        20_000,
+        1_000,
+    )])
+    .await
+    .unwrap();
+
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    let extras = RedeemExtras {
+        campaign_id: Some(CAMPAIGN_ID),
+        ..RedeemExtras::default()
+    };
+    env.redeem(&user, &nft_mint, 1, &extras).await.unwrap();
+
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&user_redeemable).await, 100);
+}
+
+#[tokio::test]
+async fn create_campaign_is_validated() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+
+    let cases = [
+        (
+            100,
+            100,
+            vec![index],
+            15_000,
+            1,
+            ErrorCode::InvalidCampaignWindow,
+        ),
+        (
+            0,
+            100,
+            vec![index],
+            9_999,
+            1,
+            ErrorCode::InvalidCampaignMultiplier,
+        ),
+        (0, 100, vec![index], 15_000, 0, ErrorCode::InvalidAmount),
+        (0, 100, vec![], 15_000, 1, ErrorCode::InvalidCampaignTypes),
+        (
+            0,
+            100,
+            vec![index + 1],
+            15_000,
+            1,
+            ErrorCode::InvalidNFTType,
+        ),
+    ];
+    for (start_ts, end_ts, types, multiplier_bps, budget, error) in cases {
+        let ix = instructions::create_campaign(
+            &authority,
+            &env.nft_config,
+            CAMPAIGN_ID,
+            start_ts,
+            end_ts,
+            types,
+            multiplier_bps,
+            budget,
+        );
+        assert_error(env.send_as_authority(&[ix]).await, error);
+    }
+}
+
+#[tokio::test]
+async fn redeem_rejects_a_campaign_from_another_config() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+
+    let other_config = pda::nft_config(&authority, CONFIG_ID + 1).0;
+    let (name, symbol, uri) = type_metadata(0);
+    env.send_as_authority(&[
+        instructions::initialize(&authority, CONFIG_ID + 1, 10),
+        instructions::add_nft_type(&authority, &other_config, name, symbol, uri),
+        instructions::create_campaign(
+            &authority,
+            &other_config,
+            CAMPAIGN_ID,
+            0,
+            i64::MAX,
+            vec![0],
+            15_000,
+            1_000,
+        ),
+    ])
+    .await
+    .unwrap();
+
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    let mut ix = env.redeem_ix(
+        &user,
+        &nft_mint,
+        1,
+        &RedeemExtras {
+            campaign_id: Some(CAMPAIGN_ID),
+            ..RedeemExtras::default()
+        },
+    );
+    replace_account(
+        &mut ix,
+        &pda::campaign(&env.nft_config, CAMPAIGN_ID).0,
+        &pda::campaign(&other_config, CAMPAIGN_ID).0,
+    );
+    assert_error(
+        env.send_redeem(ix, &user).await,
+        ErrorCode::CampaignConfigMismatch,
+    );
+}
@@ -0,0 +1,45 @@
+use solana_sdk::signature::{keypair_from_seed, Signer};
+use trash::MAX_NFT_TYPES;
+use trash4coin_client::instructions::RedeemExtras;
+
+use crate::harness::{runs_as_sbf, TestEnv};
+
+// Compute units are only metered when the program runs as SBF, i.e. with SBF_OUT_DIR
+// pointing at a build of trash.so; the native build reports the cost of its CPIs alone. The
+// bench workflow runs this with --ignored --nocapture, which also prints the numbers.
+async fn redeem_units(catalog_size: u8) -> u64 {
+    // Fixed keys give both runs the same addresses, and so the same bump search costs
+    let mut env = TestEnv::new_with_authority(keypair_from_seed(&[1; 32]).unwrap()).await;
+    for _ in 0..catalog_size {
+        env.add_type(Some(100)).await;
+    }
+    let user = keypair_from_seed(&[2; 32]).unwrap();
+    env.fund(&user.pubkey()).await;
+    // The last type is the worst case for the metadata lookup
+    let nft_mint = env.seed_nft(&user.pubkey(), catalog_size - 1, 1).await;
+
+    let ix = env.redeem_ix(&user, &nft_mint, 1, &RedeemExtras::default());
+    let authority = env.authority.insecure_clone();
+    env.compute_units(&[ix], &[&user, &authority]).await
+}
+
+#[tokio::test]
+#[ignore = "compute units are only metered with SBF_OUT_DIR pointing at a build of trash.so"]
+async fn redeem_cost_barely_grows_with_the_catalog() {
+    assert!(
+        runs_as_sbf(),
+        "compute units are only meaningful on SBF; set SBF_OUT_DIR to a build of trash.so"
+    );
+
+    let mut units = vec![];
+    for catalog_size in [1, MAX_NFT_TYPES as u8 / 2, MAX_NFT_TYPES as u8] {
+        let used = redeem_units(catalog_size).await;
+        println!("redeem_and_burn_nft with {catalog_size} types: {used} CU");
+        units.push(used);
+    }
+    let (single, full) = (units[0], units[2]);
+
+    // The config is no longer deserialized per call, so only the lookup scales with the
+    // catalog
+    assert!(full <= single + 5_000);
+}
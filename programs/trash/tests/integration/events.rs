@@ -0,0 +1,81 @@
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::Event;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use trash::{EventHeader, SetRewardAmountEvent};
+use trash4coin_client::events::{decode_cpi_data, ProgramEvent};
+use trash4coin_client::{instructions, pda};
+
+use crate::harness::{type_metadata, TestEnv};
+
+#[tokio::test]
+async fn events_are_emitted_through_cpi_in_sequence() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    // initialize and create_redeemable_token emitted the first two
+    assert_eq!(env.config().await.event_seq, 2);
+
+    let (name, symbol, uri) = type_metadata(0);
+    let ixs = [
+        instructions::add_nft_type(&authority, &env.nft_config, name, symbol, uri),
+        instructions::set_reward_amount(&authority, &env.nft_config, 0, 100),
+    ];
+    let signer = env.authority.insecure_clone();
+    let logs = env.simulate_logs(&ixs, &[&signer]).await;
+
+    // Events go out as self-invocations, leaving nothing in the logs to truncate
+    assert!(logs.contains(&format!("Program {} invoke [2]", trash::ID)));
+    assert!(!logs.iter().any(|line| line.starts_with("Program data: ")));
+
+    env.send_as_authority(&ixs).await.unwrap();
+    assert_eq!(env.config().await.event_seq, 4);
+}
+
+#[test]
+fn event_cpi_data_decodes() {
+    let header = EventHeader {
+        nft_config: Pubkey::new_unique(),
+        actor: Pubkey::new_unique(),
+        seq: 3,
+        slot: 42,
+        unix_timestamp: 1_700_000_000,
+    };
+    let event = SetRewardAmountEvent {
+        header,
+        nft_type_index: 1,
+        old_reward_amount: Some(50),
+        new_reward_amount: 100,
+    };
+    let data = [EVENT_IX_TAG_LE.as_slice(), &event.data()].concat();
+
+    let decoded = decode_cpi_data(&data).expect("event CPI data did not decode");
+    assert_eq!(decoded.header().nft_config, header.nft_config);
+    assert_eq!(decoded.header().actor, header.actor);
+    assert_eq!(decoded.header().seq, 3);
+    assert_eq!(decoded.header().slot, 42);
+    assert_eq!(decoded.header().unix_timestamp, 1_700_000_000);
+    match decoded {
+        ProgramEvent::SetRewardAmountEvent(decoded) => {
+            assert_eq!(decoded.nft_type_index, 1);
+            assert_eq!(decoded.old_reward_amount, Some(50));
+            assert_eq!(decoded.new_reward_amount, 100);
+        }
+        _ => panic!("decoded the wrong event"),
+    }
+    // Instructions other than event CPIs are ignored
+    assert!(decode_cpi_data(&event.data()).is_none());
+}
+
+#[tokio::test]
+async fn event_cpi_cannot_be_invoked_directly() {
+    let mut env = TestEnv::new().await;
+
+    // Without the event authority's signature the program rejects the event instruction
+    let ix = Instruction {
+        program_id: trash::ID,
+        accounts: vec![AccountMeta::new_readonly(pda::event_authority().0, false)],
+        data: EVENT_IX_TAG_LE.to_vec(),
+    };
+    assert!(env.send(&[ix], &[]).await.is_err());
+}
@@ -0,0 +1,651 @@
+//! Test environment shared by every integration test.
+//!
+//! The program runs natively inside `solana-program-test`. The token metadata program is
+//! loaded from `tests/fixtures/mpl_token_metadata.so` (dump it with
+//! `solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s
+//! tests/fixtures/mpl_token_metadata.so`). Without it the tests fail, unless
+//! `METADATA_STUB=1` explicitly asks for [`metadata_stub`] in its place.
+
+use std::collections::HashSet;
+
+use anchor_lang::prelude::AccountInfo;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize};
+use anchor_spl::associated_token::{
+    get_associated_token_address,
+    spl_associated_token_account::instruction::create_associated_token_account,
+};
+use anchor_spl::metadata::mpl_token_metadata::{
+    self,
+    accounts::Metadata,
+    types::{Key, TokenStandard},
+};
+use anchor_spl::token::spl_token::{
+    self,
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use solana_program_test::{
+    find_file, processor, BanksClientError, ProgramTest, ProgramTestContext,
+};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::clock::Clock;
+use solana_sdk::entrypoint::ProgramResult;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::program_option::COption;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use trash::ErrorCode;
+use trash4coin_client::instructions::{self, RedeemExtras};
+use trash4coin_client::{accounts, pda};
+
+use crate::metadata_stub;
+
+pub const CONFIG_ID: u64 = 1;
+pub const REDEEMABLE_SUPPLY: u64 = 1_000_000_000;
+const USER_LAMPORTS: u64 = 10_000_000_000;
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Anchor's entrypoint needs the account slice to live as long as the accounts themselves
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    trash::entry(program_id, accounts, data)
+}
+
+fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new("trash", trash::ID, processor!(process_instruction));
+
+    if find_file("mpl_token_metadata.so").is_some() {
+        program_test.add_program("mpl_token_metadata", mpl_token_metadata::ID, None);
+    } else {
+        // The stub only mirrors the checks the program relies on, so it has to be asked for
+        assert!(
+            std::env::var("METADATA_STUB").is_ok_and(|value| value == "1"),
+            "tests/fixtures/mpl_token_metadata.so is missing; dump it from mainnet, or set \
+             METADATA_STUB=1 to run against the native stand-in"
+        );
+        program_test.add_program(
+            "mpl_token_metadata",
+            mpl_token_metadata::ID,
+            processor!(metadata_stub::process_instruction),
+        );
+    }
+
+    program_test
+}
+
+// solana-program-test loads trash.so in place of the native build when either variable is
+// set, and quietly keeps the native build when the file is missing, so that case panics
+pub fn runs_as_sbf() -> bool {
+    if std::env::var("SBF_OUT_DIR").is_err() && std::env::var("BPF_OUT_DIR").is_err() {
+        return false;
+    }
+    assert!(
+        find_file("trash.so").is_some(),
+        "SBF_OUT_DIR or BPF_OUT_DIR is set but has no trash.so; build it with cargo build-sbf"
+    );
+    true
+}
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub authority: Keypair,
+    pub nft_config: Pubkey,
+    pub redeemable_mint: Pubkey,
+    sent: HashSet<Signature>,
+}
+
+impl TestEnv {
+    /// A funded authority and nothing else.
+    pub async fn start() -> Self {
+        Self::start_with_authority(Keypair::new()).await
+    }
+
+    /// Like [`TestEnv::start`], with a chosen authority so config addresses are reproducible.
+    pub async fn start_with_authority(authority: Keypair) -> Self {
+        let context = program_test().start_with_context().await;
+        let nft_config = pda::nft_config(&authority.pubkey(), CONFIG_ID).0;
+
+        let mut env = Self {
+            context,
+            authority,
+            nft_config,
+            redeemable_mint: pda::redeemable_mint(&nft_config).0,
+            sent: HashSet::new(),
+        };
+        env.fund(&env.authority.pubkey()).await;
+
+        env
+    }
+
+    /// An initialized config with the redeemable token created.
+    pub async fn new() -> Self {
+        Self::new_with_authority(Keypair::new()).await
+    }
+
+    /// Like [`TestEnv::new`], with a chosen authority so config addresses are reproducible.
+    pub async fn new_with_authority(authority: Keypair) -> Self {
+        let mut env = Self::start_with_authority(authority).await;
+        let authority = env.authority.pubkey();
+
+        env.send_as_authority(&[
+            instructions::initialize(&authority, CONFIG_ID, 10),
+            instructions::create_redeemable_token(&authority, &env.nft_config, REDEEMABLE_SUPPLY),
+        ])
+        .await
+        .unwrap();
+
+        env
+    }
+
+    pub async fn fund(&mut self, address: &Pubkey) {
+        let ix = system_instruction::transfer(&self.context.payer.pubkey(), address, USER_LAMPORTS);
+        self.send(&[ix], &[]).await.unwrap();
+    }
+
+    pub async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.fund(&user.pubkey()).await;
+        user
+    }
+
+    /// Send a transaction paid for by the test payer and signed by `signers`.
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let tx = self.sign(instructions, signers).await?;
+        self.context.banks_client.process_transaction(tx).await
+    }
+
+    pub async fn send_as_authority(
+        &mut self,
+        instructions: &[Instruction],
+    ) -> Result<(), BanksClientError> {
+        let authority = self.authority.insecure_clone();
+        self.send(instructions, &[&authority]).await
+    }
+
+    /// Simulate a single instruction and return the data it set with `set_return_data`.
+    pub async fn return_data(&mut self, ix: Instruction, signers: &[&Keypair]) -> Vec<u8> {
+        let tx = self.sign(&[ix], signers).await.unwrap();
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(tx)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+
+        simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .map(|return_data| return_data.data)
+            .unwrap_or_default()
+    }
+
+    /// Simulate a transaction and return its log messages.
+    pub async fn simulate_logs(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Vec<String> {
+        let tx = self.sign(instructions, signers).await.unwrap();
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(tx)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+
+        simulation
+            .simulation_details
+            .map(|details| details.logs)
+            .unwrap_or_default()
+    }
+
+    /// Simulate a transaction and return the compute units it consumed.
+    pub async fn compute_units(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> u64 {
+        let tx = self.sign(instructions, signers).await.unwrap();
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(tx)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+
+        simulation
+            .simulation_details
+            .map(|details| details.units_consumed)
+            .unwrap_or_default()
+    }
+
+    // Identical transactions in the same slot are deduplicated, so re-sign those with a
+    // fresh blockhash
+    async fn sign(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Transaction, BanksClientError> {
+        let payer = self.context.payer.insecure_clone();
+        let mut all_signers = vec![&payer];
+        all_signers.extend_from_slice(signers);
+
+        let mut blockhash = self.context.last_blockhash;
+        loop {
+            let tx = Transaction::new_signed_with_payer(
+                instructions,
+                Some(&payer.pubkey()),
+                &all_signers,
+                blockhash,
+            );
+            if self.sent.insert(tx.signatures[0]) {
+                return Ok(tx);
+            }
+            blockhash = self.context.get_new_latest_blockhash().await?;
+        }
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self.raw_account(address).await.expect("account not found");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn raw_account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+    }
+
+    pub async fn config(&mut self) -> trash::NFTConfig {
+        let nft_config = self.nft_config;
+        let account = self
+            .raw_account(&nft_config)
+            .await
+            .expect("account not found");
+        accounts::decode_nft_config(&account.data).unwrap()
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.raw_account(address)
+            .await
+            .map(|account| account.lamports)
+            .unwrap_or(0)
+    }
+
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        let account = self
+            .raw_account(address)
+            .await
+            .expect("token account not found");
+        TokenAccount::unpack(&account.data).unwrap().amount
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+    }
+
+    pub async fn set_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn advance_time(&mut self, seconds: i64) {
+        let now = self.clock().await.unix_timestamp;
+        self.set_timestamp(now + seconds).await;
+    }
+
+    pub async fn set_epoch(&mut self, epoch: u64) {
+        let mut clock = self.clock().await;
+        clock.epoch = epoch;
+        self.context.set_sysvar(&clock);
+    }
+
+    /// Register a new NFT type and return its index.
+    pub async fn add_type(&mut self, reward_amount: Option<u64>) -> u8 {
+        let index = self.config().await.nft_type_count;
+        let authority = self.authority.pubkey();
+        let (name, symbol, uri) = type_metadata(index);
+
+        let mut ixs = vec![instructions::add_nft_type(
+            &authority,
+            &self.nft_config,
+            name,
+            symbol,
+            uri,
+        )];
+        if let Some(reward_amount) = reward_amount {
+            ixs.push(instructions::set_reward_amount(
+                &authority,
+                &self.nft_config,
+                index,
+                reward_amount,
+            ));
+        }
+        self.send_as_authority(&ixs).await.unwrap();
+
+        index
+    }
+
+    /// Give `owner` `amount` more units of the type's program mint, the one `mint_nft` creates.
+    pub async fn seed_nft(&mut self, owner: &Pubkey, nft_type_index: u8, amount: u64) -> Pubkey {
+        let mint = pda::nft_mint(&self.nft_config, nft_type_index).0;
+        let supply = match self.raw_account(&mint).await {
+            Some(account) => Mint::unpack(&account.data).unwrap().supply,
+            None => 0,
+        };
+        let balance = match self
+            .raw_account(&get_associated_token_address(owner, &mint))
+            .await
+        {
+            Some(account) => TokenAccount::unpack(&account.data).unwrap().amount,
+            None => 0,
+        };
+        let (name, symbol, uri) = type_metadata(nft_type_index);
+        self.seed_mint(
+            &mint,
+            owner,
+            name,
+            symbol,
+            uri,
+            supply + amount,
+            balance + amount,
+        );
+        mint
+    }
+
+    /// Give `owner` `amount` units of a fresh mint that copies the type's metadata, which is
+    /// all an attacker can do without the program's mint.
+    pub fn seed_forged_nft(&mut self, owner: &Pubkey, nft_type_index: u8, amount: u64) -> Pubkey {
+        let (name, symbol, uri) = type_metadata(nft_type_index);
+        self.seed_nft_with_metadata(owner, name, symbol, uri, amount)
+    }
+
+    pub fn seed_nft_with_metadata(
+        &mut self,
+        owner: &Pubkey,
+        name: String,
+        symbol: String,
+        uri: String,
+        amount: u64,
+    ) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        self.seed_mint(&mint, owner, name, symbol, uri, amount, amount);
+        mint
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn seed_mint(
+        &mut self,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        name: String,
+        symbol: String,
+        uri: String,
+        supply: u64,
+        amount: u64,
+    ) {
+        let mint = *mint;
+        self.set_mint(&mint, &self.authority.pubkey(), 0, supply);
+        self.set_token_account(
+            &get_associated_token_address(owner, &mint),
+            &mint,
+            owner,
+            amount,
+        );
+
+        let metadata = Metadata {
+            key: Key::MetadataV1,
+            update_authority: self.authority.pubkey(),
+            mint,
+            name: metadata_stub::puff(name, mpl_token_metadata::MAX_NAME_LENGTH),
+            symbol: metadata_stub::puff(symbol, mpl_token_metadata::MAX_SYMBOL_LENGTH),
+            uri: metadata_stub::puff(uri, mpl_token_metadata::MAX_URI_LENGTH),
+            seller_fee_basis_points: 0,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(TokenStandard::FungibleAsset),
+            collection: None,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        };
+        let mut data = metadata.try_to_vec().unwrap();
+        data.resize(metadata_stub::MAX_METADATA_LEN, 0);
+        self.set_account(&pda::metadata(&mint).0, data, mpl_token_metadata::ID);
+    }
+
+    /// Create an SPL mint with the authority keypair as its mint authority.
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        self.set_mint(&mint, &self.authority.pubkey(), decimals, 0);
+        mint
+    }
+
+    /// Create `owner`'s associated token account for `mint`.
+    pub async fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let ix = create_associated_token_account(
+            &self.context.payer.pubkey(),
+            owner,
+            mint,
+            &spl_token::ID,
+        );
+        self.send(&[ix], &[]).await.unwrap();
+        get_associated_token_address(owner, mint)
+    }
+
+    /// Mint tokens of a mint created by [`TestEnv::create_mint`].
+    pub async fn mint_to(&mut self, mint: &Pubkey, destination: &Pubkey, amount: u64) {
+        let authority = self.authority.pubkey();
+        let ix = spl_token::instruction::mint_to(
+            &spl_token::ID,
+            mint,
+            destination,
+            &authority,
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.send_as_authority(&[ix]).await.unwrap();
+    }
+
+    /// Redeem with no minimum, no expected rate and the authority co-signing.
+    pub async fn redeem(
+        &mut self,
+        user: &Keypair,
+        nft_mint: &Pubkey,
+        amount: u64,
+        extras: &RedeemExtras,
+    ) -> Result<(), BanksClientError> {
+        let ix = self.redeem_ix(user, nft_mint, amount, extras);
+        self.send_redeem(ix, user).await
+    }
+
+    pub fn redeem_ix(
+        &self,
+        user: &Keypair,
+        nft_mint: &Pubkey,
+        amount: u64,
+        extras: &RedeemExtras,
+    ) -> Instruction {
+        instructions::redeem_and_burn_nft(
+            &user.pubkey(),
+            &self.authority.pubkey(),
+            &self.nft_config,
+            nft_mint,
+            amount,
+            0,
+            None,
+            extras,
+        )
+    }
+
+    pub async fn send_redeem(
+        &mut self,
+        ix: Instruction,
+        user: &Keypair,
+    ) -> Result<(), BanksClientError> {
+        let authority = self.authority.insecure_clone();
+        self.send(&[ix], &[user, &authority]).await
+    }
+
+    /// Redeem a forged copy of a type's NFT, with a key of the attacker's standing in for the
+    /// config authority and paying out of a vault of the attacker's own mint.
+    pub async fn forged_redeem(
+        &mut self,
+        attacker: &Keypair,
+        nft_type_index: u8,
+        amount: u64,
+        extras: &RedeemExtras,
+    ) -> Result<(), BanksClientError> {
+        let nft_mint = self.seed_forged_nft(&attacker.pubkey(), nft_type_index, amount);
+        let fake_authority = Keypair::new();
+        let fake_mint = self.create_mint(0);
+        let fake_vault = self
+            .create_token_account(&fake_mint, &fake_authority.pubkey())
+            .await;
+        self.mint_to(&fake_mint, &fake_vault, REDEEMABLE_SUPPLY)
+            .await;
+
+        let mut ix = instructions::redeem_and_burn_nft(
+            &attacker.pubkey(),
+            &fake_authority.pubkey(),
+            &self.nft_config,
+            &nft_mint,
+            amount,
+            0,
+            None,
+            extras,
+        );
+        replace_account(&mut ix, &self.redeemable_mint, &fake_mint);
+        replace_account(
+            &mut ix,
+            &get_associated_token_address(&fake_authority.pubkey(), &self.redeemable_mint),
+            &fake_vault,
+        );
+        replace_account(
+            &mut ix,
+            &get_associated_token_address(&attacker.pubkey(), &self.redeemable_mint),
+            &get_associated_token_address(&attacker.pubkey(), &fake_mint),
+        );
+        self.send(&[ix], &[attacker, &fake_authority]).await
+    }
+
+    fn set_mint(&mut self, mint: &Pubkey, authority: &Pubkey, decimals: u8, supply: u64) {
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                mint_authority: COption::Some(*authority),
+                supply,
+                decimals,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.set_account(mint, data, spl_token::ID);
+    }
+
+    fn set_token_account(&mut self, address: &Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.set_account(address, data, spl_token::ID);
+    }
+
+    pub fn set_account(&mut self, address: &Pubkey, data: Vec<u8>, owner: Pubkey) {
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context
+            .set_account(address, &AccountSharedData::from(account));
+    }
+}
+
+pub fn type_metadata(nft_type_index: u8) -> (String, String, String) {
+    (
+        format!("Trash type {nft_type_index}"),
+        format!("TT{nft_type_index}"),
+        format!("https://example.com/types/{nft_type_index}.json"),
+    )
+}
+
+/// Swap one account of an instruction for another, keeping its signer and writable flags.
+pub fn replace_account(ix: &mut Instruction, from: &Pubkey, to: &Pubkey) {
+    let meta = ix
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == *from)
+        .expect("account not in instruction");
+    meta.pubkey = *to;
+}
+
+pub fn decode_return<T: AnchorDeserialize>(data: &[u8]) -> T {
+    T::deserialize(&mut &data[..]).unwrap()
+}
+
+#[track_caller]
+pub fn assert_error(result: Result<(), BanksClientError>, expected: ErrorCode) {
+    let expected_code = anchor_lang::error::ERROR_CODE_OFFSET + expected as u32;
+    match result.map_err(|err| err.unwrap()) {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => {
+            assert_eq!(
+                code, expected_code,
+                "expected {expected:?}, got error code {code}"
+            )
+        }
+        other => panic!("expected {expected:?}, got {other:?}"),
+    }
+}
+
+// Like assert_error, for the errors Anchor raises itself when a constraint fails
+pub fn assert_anchor_error(
+    result: Result<(), BanksClientError>,
+    expected: anchor_lang::error::ErrorCode,
+) {
+    let expected_code = u32::from(expected);
+    match result.map_err(|err| err.unwrap()) {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => {
+            assert_eq!(
+                code, expected_code,
+                "expected {expected:?}, got error code {code}"
+            )
+        }
+        other => panic!("expected {expected:?}, got {other:?}"),
+    }
+}
@@ -0,0 +1,17 @@
+//! Integration tests running every instruction against an in-process bank.
+
+mod achievements;
+mod admin;
+mod campaigns;
+mod compute;
+mod events;
+mod harness;
+mod metadata_stub;
+mod migration;
+mod minting;
+mod redemption;
+mod referrals;
+mod reward_legs;
+mod sponsors;
+mod staking;
+mod vesting;
@@ -0,0 +1,206 @@
+//! Stand-in for the token metadata program, used with `METADATA_STUB=1` when
+//! `tests/fixtures/mpl_token_metadata.so` is not available. It implements the two
+//! instructions the program calls with the same account layouts and the checks the program
+//! relies on.
+
+use anchor_lang::prelude::borsh::BorshDeserialize;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::AnchorSerialize;
+use anchor_spl::metadata::mpl_token_metadata::{
+    self,
+    accounts::{MasterEdition, Metadata},
+    errors::MplTokenMetadataError,
+    instructions::{CreateMasterEditionV3InstructionArgs, CreateMetadataAccountV3InstructionArgs},
+    types::{Key, TokenStandard},
+};
+use anchor_spl::token::spl_token::{self, instruction::AuthorityType, state::Mint};
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::entrypoint::ProgramResult;
+use solana_sdk::program::{invoke, invoke_signed};
+use solana_sdk::program_error::ProgramError;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::system_instruction;
+use solana_sdk::sysvar::Sysvar;
+
+const CREATE_MASTER_EDITION_V3: u8 = 17;
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+pub const MAX_METADATA_LEN: usize = 679;
+const MAX_MASTER_EDITION_LEN: usize = 282;
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    match data.split_first() {
+        Some((&CREATE_METADATA_ACCOUNT_V3, mut args)) => {
+            let args = CreateMetadataAccountV3InstructionArgs::deserialize(&mut args)?;
+            create_metadata_account_v3(program_id, accounts, args)
+        }
+        Some((&CREATE_MASTER_EDITION_V3, mut args)) => {
+            let args = CreateMasterEditionV3InstructionArgs::deserialize(&mut args)?;
+            create_master_edition_v3(program_id, accounts, args)
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn create_metadata_account_v3(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: CreateMetadataAccountV3InstructionArgs,
+) -> ProgramResult {
+    let [metadata, mint, mint_authority, payer, update_authority, system_program, ..] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let mint_state = Mint::unpack(&mint.data.borrow())?;
+    if mint_state.mint_authority != COption::Some(*mint_authority.key) || !mint_authority.is_signer
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (address, bump) = Metadata::find_pda(mint.key);
+    if address != *metadata.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        metadata,
+        system_program,
+        MAX_METADATA_LEN,
+        &[b"metadata", program_id.as_ref(), mint.key.as_ref(), &[bump]],
+    )?;
+
+    let token_standard = if mint_state.decimals == 0 {
+        TokenStandard::FungibleAsset
+    } else {
+        TokenStandard::Fungible
+    };
+    let state = Metadata {
+        key: Key::MetadataV1,
+        update_authority: *update_authority.key,
+        mint: *mint.key,
+        name: puff(args.data.name, mpl_token_metadata::MAX_NAME_LENGTH),
+        symbol: puff(args.data.symbol, mpl_token_metadata::MAX_SYMBOL_LENGTH),
+        uri: puff(args.data.uri, mpl_token_metadata::MAX_URI_LENGTH),
+        seller_fee_basis_points: args.data.seller_fee_basis_points,
+        creators: args.data.creators,
+        primary_sale_happened: false,
+        is_mutable: args.is_mutable,
+        edition_nonce: None,
+        token_standard: Some(token_standard),
+        collection: args.data.collection,
+        uses: args.data.uses,
+        collection_details: args.collection_details,
+        programmable_config: None,
+    };
+    write_state(metadata, &state)
+}
+
+fn create_master_edition_v3(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: CreateMasterEditionV3InstructionArgs,
+) -> ProgramResult {
+    let [edition, mint, _update_authority, mint_authority, payer, metadata, token_program, system_program, ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if metadata.owner != program_id {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mint_state = Mint::unpack(&mint.data.borrow())?;
+    if mint_state.supply != 1 {
+        return Err(ProgramError::Custom(
+            MplTokenMetadataError::EditionsMustHaveExactlyOneToken as u32,
+        ));
+    }
+
+    let (address, bump) = MasterEdition::find_pda(mint.key);
+    if address != *edition.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        edition,
+        system_program,
+        MAX_MASTER_EDITION_LEN,
+        &[
+            b"metadata",
+            program_id.as_ref(),
+            mint.key.as_ref(),
+            b"edition",
+            &[bump],
+        ],
+    )?;
+    write_state(
+        edition,
+        &MasterEdition {
+            key: Key::MasterEditionV2,
+            supply: 0,
+            max_supply: args.max_supply,
+        },
+    )?;
+
+    // The edition takes over the mint (and freeze) authority so no more tokens can be minted
+    let mut authority_types = vec![AuthorityType::MintTokens];
+    if mint_state.freeze_authority == COption::Some(*mint_authority.key) {
+        authority_types.push(AuthorityType::FreezeAccount);
+    }
+    for authority_type in authority_types {
+        invoke(
+            &spl_token::instruction::set_authority(
+                token_program.key,
+                mint.key,
+                Some(edition.key),
+                authority_type,
+                mint_authority.key,
+                &[],
+            )?,
+            &[mint.clone(), mint_authority.clone(), token_program.clone()],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            account.key,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            program_id,
+        ),
+        &[payer.clone(), account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+fn write_state(account: &AccountInfo, state: &impl AnchorSerialize) -> ProgramResult {
+    let bytes = state.try_to_vec()?;
+    account.data.borrow_mut()[..bytes.len()].copy_from_slice(&bytes);
+    Ok(())
+}
+
+// Pad a string with NUL bytes to its fixed on-chain length, as the real program does
+pub fn puff(value: String, length: usize) -> String {
+    let padding = length.saturating_sub(value.len());
+    value + &"\0".repeat(padding)
+}
@@ -0,0 +1,155 @@
+use anchor_lang::{AnchorDeserialize, AnchorSerialize, Discriminator};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+use trash::{ErrorCode, NFTConfig, NFTConfigV1, NFT_CONFIG_VERSION};
+use trash4coin_client::{accounts, instructions, pda};
+
+use crate::harness::{assert_error, replace_account, type_metadata, TestEnv, CONFIG_ID};
+
+// A config account written by the originally deployed program: initialize(10), three
+// add_nft_type calls with type_metadata(0..3), and set_reward_amount of 100 and 200 for
+// the first two types. The authority is baseline_authority().
+const BASELINE_CONFIG: &[u8] = include_bytes!("../fixtures/nft_config_baseline.bin");
+
+fn baseline_authority() -> Keypair {
+    keypair_from_seed(&[7; 32]).unwrap()
+}
+
+#[track_caller]
+fn assert_baseline_fields(config: &NFTConfig, authority: &Pubkey) {
+    assert_eq!(config.authority, *authority);
+    assert_eq!(config.config_id, 0);
+    assert_eq!(config.legacy_address, 1);
+    assert_eq!(config.max_nft_types, 10);
+    assert_eq!(config.nft_type_count, 3);
+    for (index, nft_type) in config.nft_types().iter().enumerate() {
+        let (name, symbol, uri) = type_metadata(index as u8);
+        assert_eq!(nft_type.name(), name);
+        assert_eq!(nft_type.symbol(), symbol);
+        assert_eq!(nft_type.uri(), uri);
+        assert!(nft_type.reward_legs().is_empty());
+        assert!(nft_type.vesting().is_none());
+        assert!(nft_type.reward_curve().is_none());
+        assert_eq!(nft_type.staking_reward_rate, 0);
+    }
+    assert_eq!(config.nft_types()[0].reward_amount(), Some(100));
+    assert_eq!(config.nft_types()[1].reward_amount(), Some(200));
+    assert_eq!(config.nft_types()[2].reward_amount(), None);
+
+    // Everything added since starts out unset
+    assert_eq!(config.max_reward_per_user_per_epoch, 0);
+    assert_eq!(config.max_reward_per_epoch, 0);
+    assert_eq!(config.current_epoch, 0);
+    assert_eq!(config.epoch_rewarded, 0);
+    assert!(config.tiers.iter().all(|tier| tier.units_threshold == 0));
+    assert_eq!(config.referral_bps, 0);
+    assert_eq!(config.referral_cap, 0);
+    assert_eq!(config.streak_bonus_bps_per_day, 0);
+    assert_eq!(config.max_streak_bonus_bps, 0);
+}
+
+#[test]
+fn baseline_account_matches_the_v1_layout() {
+    assert_eq!(BASELINE_CONFIG.len(), NFTConfigV1::space());
+    assert_eq!(BASELINE_CONFIG[..8], NFTConfig::DISCRIMINATOR);
+
+    let v1 = NFTConfigV1::deserialize(&mut &BASELINE_CONFIG[8..]).unwrap();
+    assert_eq!(v1.authority, baseline_authority().pubkey());
+    assert_eq!(v1.max_nft_types, 10);
+    assert_eq!(v1.nft_types.len(), 3);
+    assert_eq!(v1.nft_types[1].uri, "https://example.com/types/1.json");
+
+    // Serializing it again gives back the deployed bytes, zero tail included
+    let mut data = NFTConfig::DISCRIMINATOR.to_vec();
+    v1.serialize(&mut data).unwrap();
+    data.resize(NFTConfigV1::space(), 0);
+    assert_eq!(data, BASELINE_CONFIG);
+}
+
+#[test]
+fn baseline_accounts_decode_in_the_client() {
+    // The client upgrades accounts that have not been migrated yet in memory
+    let authority = baseline_authority().pubkey();
+    let config = accounts::decode_nft_config(BASELINE_CONFIG).unwrap();
+    assert_baseline_fields(&config, &authority);
+    assert_eq!(config.event_seq, 0);
+    assert_eq!(config.version, NFT_CONFIG_VERSION);
+}
+
+#[tokio::test]
+async fn migrate_config_upgrades_baseline_accounts() {
+    let mut env = TestEnv::start_with_authority(baseline_authority()).await;
+    let authority = env.authority.pubkey();
+    let (nft_config, _) = pda::legacy_nft_config(&authority);
+    env.nft_config = nft_config;
+    env.redeemable_mint = pda::redeemable_mint(&nft_config).0;
+    env.set_account(&nft_config, BASELINE_CONFIG.to_vec(), trash::ID);
+
+    env.send_as_authority(&[instructions::migrate_legacy_config(&authority)])
+        .await
+        .unwrap();
+
+    let account = env.raw_account(&nft_config).await.unwrap();
+    assert_eq!(account.data.len(), NFTConfig::space());
+    assert!(account.lamports >= Rent::default().minimum_balance(NFTConfig::space()));
+
+    let config = env.config().await;
+    assert_baseline_fields(&config, &authority);
+    assert_eq!(config.version, NFT_CONFIG_VERSION);
+    assert_eq!(config.reserved, [0; 64]);
+    // The migration itself is the first event
+    assert_eq!(config.event_seq, 1);
+
+    // The config keeps its address, so it still signs for the mints derived from it
+    env.send_as_authority(&[instructions::create_redeemable_token(
+        &authority,
+        &nft_config,
+        1_000,
+    )])
+    .await
+    .unwrap();
+    let index = env.add_type(Some(400)).await;
+    assert_eq!(index, 3);
+    assert_eq!(env.config().await.nft_types()[3].reward_amount(), Some(400));
+}
+
+#[tokio::test]
+async fn migrate_config_rejects_current_layout() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    assert_eq!(env.config().await.version, NFT_CONFIG_VERSION);
+
+    let result = env
+        .send_as_authority(&[instructions::migrate_config(&authority, CONFIG_ID)])
+        .await;
+    assert_error(result, ErrorCode::ConfigAlreadyMigrated);
+}
+
+#[tokio::test]
+async fn migrate_config_requires_the_config_authority() {
+    let mut env = TestEnv::start_with_authority(baseline_authority()).await;
+    let authority = env.authority.pubkey();
+    let (nft_config, _) = pda::legacy_nft_config(&authority);
+    env.set_account(&nft_config, BASELINE_CONFIG.to_vec(), trash::ID);
+    let user = env.new_user().await;
+
+    // The config's address is derived from its authority, so nobody else can migrate it
+    let mut ix = instructions::migrate_legacy_config(&authority);
+    replace_account(&mut ix, &authority, &user.pubkey());
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::InvalidConfigAccount,
+    );
+
+    // Nor can a baseline account be migrated as a namespaced config
+    let mut ix = instructions::migrate_config(&authority, CONFIG_ID);
+    replace_account(&mut ix, &env.nft_config, &nft_config);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidConfigAccount,
+    );
+
+    let account = env.raw_account(&nft_config).await.unwrap();
+    assert_eq!(account.data.len(), NFTConfigV1::space());
+}
@@ -0,0 +1,242 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token::{self, state::Mint};
+use solana_sdk::program_option::COption;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use trash::{ErrorCode, MintBatchEntry, UserRecord, SECONDS_PER_DAY};
+use trash4coin_client::instructions::{self, RedeemExtras};
+use trash4coin_client::pda;
+
+use crate::harness::{assert_error, replace_account, TestEnv};
+
+async fn mint(env: &mut TestEnv, minter: &Keypair, nft_type_index: u8, amount: u64) {
+    let ix = instructions::mint_nft(&minter.pubkey(), &env.nft_config, nft_type_index, amount);
+    env.send(&[ix], &[minter]).await.unwrap();
+}
+
+#[tokio::test]
+async fn minted_nfts_can_be_redeemed() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let minter = env.new_user().await;
+
+    mint(&mut env, &minter, index, 1).await;
+
+    let nft_mint = pda::nft_mint(&env.nft_config, index).0;
+    let minter_nfts = get_associated_token_address(&minter.pubkey(), &nft_mint);
+    assert_eq!(env.token_balance(&minter_nfts).await, 1);
+
+    env.redeem(&minter, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+    let minter_redeemable = get_associated_token_address(&minter.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&minter_nfts).await, 0);
+    assert_eq!(env.token_balance(&minter_redeemable).await, 100);
+}
+
+#[tokio::test]
+async fn minted_nfts_get_a_master_edition() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let minter = env.new_user().await;
+
+    mint(&mut env, &minter, index, 1).await;
+
+    // The edition holds the mint and freeze authority, so the supply stays at one
+    let nft_mint = pda::nft_mint(&env.nft_config, index).0;
+    let master_edition = pda::master_edition(&nft_mint).0;
+    assert!(env.raw_account(&master_edition).await.is_some());
+    let mint_account = env.raw_account(&nft_mint).await.unwrap();
+    let mint_state = Mint::unpack(&mint_account.data).unwrap();
+    assert_eq!(mint_state.supply, 1);
+    assert_eq!(mint_state.mint_authority, COption::Some(master_edition));
+    assert_eq!(mint_state.freeze_authority, COption::Some(master_edition));
+}
+
+#[tokio::test]
+async fn daily_minting_builds_a_streak_bonus() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    for _ in 0..3 {
+        env.add_type(Some(100)).await;
+    }
+    env.send_as_authority(&[instructions::set_streak_bonus(
+        &authority,
+        &env.nft_config,
+        1_000,
+        3_000,
+    )])
+    .await
+    .unwrap();
+    let minter = env.new_user().await;
+    let user_record = pda::user_record(&env.nft_config, &minter.pubkey()).0;
+
+    // A type can only be minted once, so each day has to mint a different one
+    mint(&mut env, &minter, 0, 1).await;
+    env.advance_time(SECONDS_PER_DAY).await;
+    mint(&mut env, &minter, 1, 1).await;
+
+    // A two day streak adds 20% on top of the base reward
+    let nft_mint = pda::nft_mint(&env.nft_config, 1).0;
+    env.redeem(&minter, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+    let minter_redeemable = get_associated_token_address(&minter.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&minter_redeemable).await, 120);
+
+    // Skipping days breaks the streak
+    env.advance_time(3 * SECONDS_PER_DAY).await;
+    mint(&mut env, &minter, 2, 1).await;
+    let record: UserRecord = env.account(&user_record).await;
+    assert_eq!(record.current_streak, 1);
+    assert_eq!(record.longest_streak, 2);
+}
+
+#[tokio::test]
+async fn streaks_end_once_every_type_is_minted() {
+    let mut env = TestEnv::new().await;
+    env.add_type(Some(100)).await;
+    let minter = env.new_user().await;
+    let user_record = pda::user_record(&env.nft_config, &minter.pubkey()).0;
+
+    mint(&mut env, &minter, 0, 1).await;
+    env.advance_time(SECONDS_PER_DAY).await;
+
+    // The type's mint already exists, for this minter and everyone else
+    let ix = instructions::mint_nft(&minter.pubkey(), &env.nft_config, 0, 1);
+    assert!(env.send(&[ix], &[&minter]).await.is_err());
+    let other = env.new_user().await;
+    let ix = instructions::mint_nft(&other.pubkey(), &env.nft_config, 0, 1);
+    assert!(env.send(&[ix], &[&other]).await.is_err());
+
+    let record: UserRecord = env.account(&user_record).await;
+    assert_eq!(record.current_streak, 1);
+}
+
+#[tokio::test]
+async fn mint_nft_is_validated() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let minter = env.new_user().await;
+
+    let ix = instructions::mint_nft(&minter.pubkey(), &env.nft_config, index + 1, 1);
+    assert_error(env.send(&[ix], &[&minter]).await, ErrorCode::InvalidNFTType);
+
+    for amount in [0, 2] {
+        let ix = instructions::mint_nft(&minter.pubkey(), &env.nft_config, index, amount);
+        assert_error(env.send(&[ix], &[&minter]).await, ErrorCode::InvalidAmount);
+    }
+}
+
+fn batch(nft_type_indices: &[u8]) -> Vec<MintBatchEntry> {
+    nft_type_indices
+        .iter()
+        .map(|&nft_type_index| MintBatchEntry { nft_type_index })
+        .collect()
+}
+
+#[tokio::test]
+async fn batch_mint_mints_every_type_at_once() {
+    let mut env = TestEnv::new().await;
+    for _ in 0..3 {
+        env.add_type(Some(100)).await;
+    }
+    let minter = env.new_user().await;
+    let seq = env.config().await.event_seq;
+
+    let ix = instructions::mint_nft_batch(&minter.pubkey(), &env.nft_config, batch(&[2, 0, 1]));
+    env.send(&[ix], &[&minter]).await.unwrap();
+
+    for index in 0..3 {
+        let nft_mint = pda::nft_mint(&env.nft_config, index).0;
+        let minter_nfts = get_associated_token_address(&minter.pubkey(), &nft_mint);
+        assert_eq!(env.token_balance(&minter_nfts).await, 1);
+        // Each type's mint is capped like one from mint_nft
+        let master_edition = pda::master_edition(&nft_mint).0;
+        let mint_account = env.raw_account(&nft_mint).await.unwrap();
+        let mint_state = Mint::unpack(&mint_account.data).unwrap();
+        assert_eq!(mint_state.mint_authority, COption::Some(master_edition));
+    }
+    // One event per type, the streak starting and the batch summary
+    assert_eq!(env.config().await.event_seq, seq + 5);
+    let user_record = pda::user_record(&env.nft_config, &minter.pubkey()).0;
+    let record: UserRecord = env.account(&user_record).await;
+    assert_eq!(record.current_streak, 1);
+
+    // The metadata matches the type, so batch-minted NFTs redeem like any other
+    let nft_mint = pda::nft_mint(&env.nft_config, 1).0;
+    env.redeem(&minter, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+    let minter_redeemable = get_associated_token_address(&minter.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&minter_redeemable).await, 100);
+}
+
+#[tokio::test]
+async fn batch_mint_creates_mints_at_funded_addresses() {
+    let mut env = TestEnv::new().await;
+    env.add_type(Some(100)).await;
+    let minter = env.new_user().await;
+
+    // Anyone can send lamports to a type's mint address before the mint exists
+    let nft_mint = pda::nft_mint(&env.nft_config, 0).0;
+    let payer = env.context.payer.pubkey();
+    let ix = system_instruction::transfer(&payer, &nft_mint, Rent::default().minimum_balance(0));
+    env.send(&[ix], &[]).await.unwrap();
+
+    let ix = instructions::mint_nft_batch(&minter.pubkey(), &env.nft_config, batch(&[0]));
+    env.send(&[ix], &[&minter]).await.unwrap();
+
+    let minter_nfts = get_associated_token_address(&minter.pubkey(), &nft_mint);
+    assert_eq!(env.token_balance(&minter_nfts).await, 1);
+    let mint_account = env.raw_account(&nft_mint).await.unwrap();
+    assert_eq!(mint_account.owner, spl_token::ID);
+    assert!(mint_account.lamports >= Rent::default().minimum_balance(Mint::LEN));
+}
+
+#[tokio::test]
+async fn mint_nft_batch_is_validated() {
+    let mut env = TestEnv::new().await;
+    for _ in 0..2 {
+        env.add_type(Some(100)).await;
+    }
+    let minter = env.new_user().await;
+    let nft_config = env.nft_config;
+
+    let cases = [
+        (batch(&[]), ErrorCode::EmptyBatch),
+        (batch(&[0, 0]), ErrorCode::DuplicateBatchEntry),
+        (batch(&[0, 2]), ErrorCode::InvalidNFTType),
+    ];
+    for (entries, expected) in cases {
+        let ix = instructions::mint_nft_batch(&minter.pubkey(), &nft_config, entries);
+        assert_error(env.send(&[ix], &[&minter]).await, expected);
+    }
+
+    // Accounts that don't line up with the entries
+    let mut ix = instructions::mint_nft_batch(&minter.pubkey(), &nft_config, batch(&[0, 1]));
+    ix.accounts.pop();
+    assert_error(
+        env.send(&[ix], &[&minter]).await,
+        ErrorCode::InvalidBatchAccounts,
+    );
+
+    let mut ix = instructions::mint_nft_batch(&minter.pubkey(), &nft_config, batch(&[0]));
+    let mint = pda::nft_mint(&nft_config, 0).0;
+    replace_account(&mut ix, &mint, &pda::nft_mint(&nft_config, 1).0);
+    assert_error(
+        env.send(&[ix], &[&minter]).await,
+        ErrorCode::InvalidBatchAccounts,
+    );
+
+    let mut ix = instructions::mint_nft_batch(&minter.pubkey(), &nft_config, batch(&[0]));
+    let master_edition = pda::master_edition(&mint).0;
+    replace_account(&mut ix, &master_edition, &Pubkey::new_unique());
+    assert_error(
+        env.send(&[ix], &[&minter]).await,
+        ErrorCode::InvalidBatchAccounts,
+    );
+}
@@ -0,0 +1,536 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use trash::{CurveMode, ErrorCode, RewardCurve, TierConfig, UserInfo, UserRecord, VestingSchedule};
+use trash4coin_client::instructions::{self, RedeemBatchExtras, RedeemExtras};
+use trash4coin_client::pda;
+
+use crate::harness::{
+    assert_anchor_error, assert_error, decode_return, replace_account, TestEnv, REDEEMABLE_SUPPLY,
+};
+
+#[tokio::test]
+async fn redeem_pays_the_reward_and_burns() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 3).await;
+
+    env.redeem(&user, &nft_mint, 2, &RedeemExtras::default())
+        .await
+        .unwrap();
+
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    let user_nfts = get_associated_token_address(&user.pubkey(), &nft_mint);
+    assert_eq!(env.token_balance(&user_redeemable).await, 200);
+    assert_eq!(env.token_balance(&user_nfts).await, 1);
+
+    let user_record: UserRecord = env
+        .account(&pda::user_record(&env.nft_config, &user.pubkey()).0)
+        .await;
+    assert_eq!(user_record.lifetime_units_redeemed, 2);
+    assert_eq!(user_record.lifetime_rewarded, 200);
+    assert_eq!(user_record.redeemed_types_mask, 1);
+}
+
+#[tokio::test]
+async fn quote_redemption_follows_the_reward_curve() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+
+    // The vault holds half the reference balance, so the linear curve pays 75%
+    env.send_as_authority(&[instructions::set_reward_curve(
+        &authority,
+        &env.nft_config,
+        index,
+        Some(RewardCurve {
+            mode: CurveMode::Linear,
+            reference_balance: 2 * REDEEMABLE_SUPPLY,
+            min_multiplier_bps: 5_000,
+            exponent: 1,
+        }),
+    )])
+    .await
+    .unwrap();
+
+    let ix = instructions::quote_redemption(&authority, &env.nft_config, index);
+    let quote: u64 = decode_return(&env.return_data(ix, &[]).await);
+    assert_eq!(quote, 75);
+}
+
+#[tokio::test]
+async fn quote_redemption_prices_against_the_config_vault() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+
+    // Another mint held by the authority can't stand in for the redeemable mint
+    let other_mint = env.create_mint(0);
+    let other_vault = env.create_token_account(&other_mint, &authority).await;
+    env.mint_to(&other_mint, &other_vault, 1).await;
+    let mut ix = instructions::quote_redemption(&authority, &env.nft_config, index);
+    replace_account(&mut ix, &env.redeemable_mint, &other_mint);
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&authority, &env.redeemable_mint),
+        &other_vault,
+    );
+    assert_anchor_error(
+        env.send(&[ix], &[]).await,
+        anchor_lang::error::ErrorCode::ConstraintSeeds,
+    );
+
+    // Nor can a vault owned by anyone but the config authority
+    let stranger = env.new_user().await;
+    let redeemable_mint = env.redeemable_mint;
+    let stranger_vault = env
+        .create_token_account(&redeemable_mint, &stranger.pubkey())
+        .await;
+    let mut ix = instructions::quote_redemption(&authority, &env.nft_config, index);
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&authority, &redeemable_mint),
+        &stranger_vault,
+    );
+    assert_anchor_error(
+        env.send(&[ix], &[]).await,
+        anchor_lang::error::ErrorCode::ConstraintTokenOwner,
+    );
+}
+
+#[tokio::test]
+async fn get_user_info_returns_balances() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 4).await;
+    let redeemable_mint = env.redeemable_mint;
+    env.create_token_account(&redeemable_mint, &user.pubkey())
+        .await;
+
+    let ix = instructions::get_user_info(&user.pubkey(), &env.nft_config, &nft_mint);
+    let user_info: UserInfo = decode_return(&env.return_data(ix, &[&user]).await);
+    assert_eq!(user_info.nft_mint, nft_mint);
+    assert_eq!(user_info.nft_balance, 4);
+    assert_eq!(user_info.redeemable_balance, 0);
+}
+
+#[tokio::test]
+async fn tier_boost_applies_after_crossing_a_threshold() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    let tiers = [
+        TierConfig {
+            units_threshold: 1,
+            boost_bps: 1_000,
+        },
+        TierConfig::default(),
+        TierConfig::default(),
+    ];
+    env.send_as_authority(&[instructions::set_tier_thresholds(
+        &authority,
+        &env.nft_config,
+        tiers,
+    )])
+    .await
+    .unwrap();
+
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+    env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+    env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+
+    // The first redemption is unboosted and moves the user to Bronze for the second
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&user_redeemable).await, 100 + 110);
+}
+
+#[tokio::test]
+async fn redemption_limits_reset_each_epoch() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    env.send_as_authority(&[instructions::set_redemption_limits(
+        &authority,
+        &env.nft_config,
+        150,
+        0,
+    )])
+    .await
+    .unwrap();
+
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+    env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+    assert_error(
+        env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+            .await,
+        ErrorCode::RedemptionLimitExceeded,
+    );
+
+    let epoch = env.clock().await.epoch;
+    env.set_epoch(epoch + 1).await;
+    env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn forged_redemptions_do_not_use_up_the_epoch_limit() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    env.send_as_authority(&[instructions::set_redemption_limits(
+        &authority,
+        &env.nft_config,
+        0,
+        100,
+    )])
+    .await
+    .unwrap();
+
+    let attacker = env.new_user().await;
+    assert!(env
+        .forged_redeem(&attacker, index, 1, &RedeemExtras::default())
+        .await
+        .is_err());
+    assert_eq!(env.config().await.epoch_rewarded, 0);
+
+    // The whole global allowance is still there for real NFTs
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+        .await
+        .unwrap();
+    assert_eq!(env.config().await.epoch_rewarded, 100);
+}
+
+#[tokio::test]
+async fn redeem_requires_a_reward_and_a_positive_amount() {
+    let mut env = TestEnv::new().await;
+    let unpriced = env.add_type(None).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), unpriced, 1).await;
+
+    assert_error(
+        env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+            .await,
+        ErrorCode::RewardAmountNotSet,
+    );
+    assert_error(
+        env.redeem(&user, &nft_mint, 0, &RedeemExtras::default())
+            .await,
+        ErrorCode::InvalidAmount,
+    );
+}
+
+#[tokio::test]
+async fn redeem_rejects_metadata_that_does_not_match() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    let other_mint = env.seed_forged_nft(&user.pubkey(), index, 1);
+
+    // Metadata of a different mint
+    let mut ix = env.redeem_ix(&user, &nft_mint, 1, &RedeemExtras::default());
+    replace_account(
+        &mut ix,
+        &pda::metadata(&nft_mint).0,
+        &pda::metadata(&other_mint).0,
+    );
+    assert_error(env.send_redeem(ix, &user).await, ErrorCode::InvalidMetadata);
+
+    // Metadata that names no configured type
+    let unknown_mint = env.seed_nft_with_metadata(
+        &user.pubkey(),
+        "Unknown".to_string(),
+        "UNK".to_string(),
+        "https://example.com/unknown.json".to_string(),
+        1,
+    );
+    assert_error(
+        env.redeem(&user, &unknown_mint, 1, &RedeemExtras::default())
+            .await,
+        ErrorCode::InvalidNFTType,
+    );
+}
+
+#[tokio::test]
+async fn redeem_rejects_mints_that_are_not_the_type_mint() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+
+    // A copy of the type's metadata on a mint the program never created
+    let forged_mint = env.seed_forged_nft(&user.pubkey(), index, 1);
+    assert_error(
+        env.redeem(&user, &forged_mint, 1, &RedeemExtras::default())
+            .await,
+        ErrorCode::InvalidNFTMint,
+    );
+
+    // Paying out of someone else's vault
+    let forger = env.new_user().await;
+    assert!(env
+        .forged_redeem(&forger, index, 1, &RedeemExtras::default())
+        .await
+        .is_err());
+    let user_record = pda::user_record(&env.nft_config, &forger.pubkey()).0;
+    assert!(env.raw_account(&user_record).await.is_none());
+}
+
+#[tokio::test]
+async fn redeem_requires_the_config_authority() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+
+    // The impostor co-signs as the owner of a vault of their own
+    let impostor = env.new_user().await;
+    let redeemable_mint = env.redeemable_mint;
+    let impostor_vault = env
+        .create_token_account(&redeemable_mint, &impostor.pubkey())
+        .await;
+    let mut ix = env.redeem_ix(&user, &nft_mint, 1, &RedeemExtras::default());
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&env.authority.pubkey(), &redeemable_mint),
+        &impostor_vault,
+    );
+    replace_account(&mut ix, &env.authority.pubkey(), &impostor.pubkey());
+    assert_error(
+        env.send(&[ix], &[&user, &impostor]).await,
+        ErrorCode::UnauthorizedAccess,
+    );
+}
+
+#[tokio::test]
+async fn redeem_enforces_slippage_guards() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    let authority = env.authority.pubkey();
+
+    let ix = instructions::redeem_and_burn_nft(
+        &user.pubkey(),
+        &authority,
+        &env.nft_config,
+        &nft_mint,
+        1,
+        101,
+        None,
+        &RedeemExtras::default(),
+    );
+    assert_error(
+        env.send_redeem(ix, &user).await,
+        ErrorCode::RewardBelowMinimum,
+    );
+
+    let ix = instructions::redeem_and_burn_nft(
+        &user.pubkey(),
+        &authority,
+        &env.nft_config,
+        &nft_mint,
+        1,
+        0,
+        Some(99),
+        &RedeemExtras::default(),
+    );
+    assert_error(
+        env.send_redeem(ix, &user).await,
+        ErrorCode::RewardRateMismatch,
+    );
+}
+
+#[tokio::test]
+async fn redeem_rejects_overflowing_rewards() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(u64::MAX)).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+
+    assert_error(
+        env.redeem(&user, &nft_mint, 2, &RedeemExtras::default())
+            .await,
+        ErrorCode::ArithmeticOverflow,
+    );
+}
+
+fn batch_ix(
+    env: &TestEnv,
+    user: &Keypair,
+    nft_mints: &[Pubkey],
+    min_reward_out: u64,
+) -> Instruction {
+    instructions::redeem_and_burn_batch(
+        &user.pubkey(),
+        &env.authority.pubkey(),
+        &env.nft_config,
+        nft_mints,
+        min_reward_out,
+        &RedeemBatchExtras::default(),
+    )
+}
+
+#[tokio::test]
+async fn batch_redeem_pays_every_type_in_one_transfer() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let cans = env.add_type(Some(100)).await;
+    let bottles = env.add_type(Some(250)).await;
+    env.send_as_authority(&[instructions::set_referral_config(
+        &authority,
+        &env.nft_config,
+        1_000,
+        1_000,
+    )])
+    .await
+    .unwrap();
+
+    let referrer = env.new_user().await;
+    let redeemable_mint = env.redeemable_mint;
+    let referrer_redeemable = env
+        .create_token_account(&redeemable_mint, &referrer.pubkey())
+        .await;
+    let user = env.new_user().await;
+    let ix = instructions::set_referrer(&user.pubkey(), &env.nft_config, &referrer.pubkey());
+    env.send(&[ix], &[&user]).await.unwrap();
+    let can_mint = env.seed_nft(&user.pubkey(), cans, 3).await;
+    let bottle_mint = env.seed_nft(&user.pubkey(), bottles, 2).await;
+
+    let ix = instructions::redeem_and_burn_batch(
+        &user.pubkey(),
+        &authority,
+        &env.nft_config,
+        &[can_mint, bottle_mint],
+        800,
+        &RedeemBatchExtras {
+            referrer: Some(referrer.pubkey()),
+            ..RedeemBatchExtras::default()
+        },
+    );
+    env.send_redeem(ix, &user).await.unwrap();
+
+    // Whole balances are burned and paid for together, with the referrer's 10% on top
+    for nft_mint in [can_mint, bottle_mint] {
+        let user_nfts = get_associated_token_address(&user.pubkey(), &nft_mint);
+        assert_eq!(env.token_balance(&user_nfts).await, 0);
+    }
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &redeemable_mint);
+    assert_eq!(env.token_balance(&user_redeemable).await, 800);
+    assert_eq!(env.token_balance(&referrer_redeemable).await, 80);
+    let vault = get_associated_token_address(&authority, &redeemable_mint);
+    assert_eq!(env.token_balance(&vault).await, REDEEMABLE_SUPPLY - 880);
+
+    let user_record: UserRecord = env
+        .account(&pda::user_record(&env.nft_config, &user.pubkey()).0)
+        .await;
+    assert_eq!(user_record.lifetime_units_redeemed, 5);
+    assert_eq!(user_record.lifetime_rewarded, 800);
+    assert_eq!(user_record.redeemed_types_mask, 0b11);
+}
+
+#[tokio::test]
+async fn batch_redeem_is_validated() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    let vested = env.add_type(Some(100)).await;
+    env.send_as_authority(&[instructions::set_vesting_schedule(
+        &authority,
+        &env.nft_config,
+        vested,
+        Some(VestingSchedule {
+            min_reward: 1_000,
+            cliff_seconds: 0,
+            duration_seconds: 100,
+        }),
+    )])
+    .await
+    .unwrap();
+    let other = env.add_type(Some(100)).await;
+    let empty = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    let other_mint = env.seed_nft(&user.pubkey(), other, 1).await;
+    let empty_mint = env.seed_nft(&user.pubkey(), empty, 0).await;
+    let vested_mint = env.seed_nft(&user.pubkey(), vested, 1).await;
+    let forged_mint = env.seed_forged_nft(&user.pubkey(), index, 1);
+
+    let cases = [
+        (vec![], ErrorCode::EmptyBatch),
+        (vec![other_mint, forged_mint], ErrorCode::InvalidNFTMint),
+        (vec![nft_mint, nft_mint], ErrorCode::DuplicateBatchEntry),
+        (vec![nft_mint, empty_mint], ErrorCode::InvalidAmount),
+        (vec![nft_mint, vested_mint], ErrorCode::VestingTypeInBatch),
+    ];
+    for (nft_mints, expected) in cases {
+        let ix = batch_ix(&env, &user, &nft_mints, 0);
+        assert_error(env.send_redeem(ix, &user).await, expected);
+    }
+
+    let ix = batch_ix(&env, &user, &[nft_mint, other_mint], 201);
+    assert_error(
+        env.send_redeem(ix, &user).await,
+        ErrorCode::RewardBelowMinimum,
+    );
+
+    // The user's token account for a different mint
+    let mut ix = batch_ix(&env, &user, &[nft_mint], 0);
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&user.pubkey(), &nft_mint),
+        &get_associated_token_address(&user.pubkey(), &other_mint),
+    );
+    assert_error(
+        env.send_redeem(ix, &user).await,
+        ErrorCode::InvalidBatchAccounts,
+    );
+
+    // Only the config authority can pay out, even from a vault the impostor owns
+    let impostor = env.new_user().await;
+    let redeemable_mint = env.redeemable_mint;
+    let impostor_vault = env
+        .create_token_account(&redeemable_mint, &impostor.pubkey())
+        .await;
+    let mut ix = batch_ix(&env, &user, &[nft_mint], 0);
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&authority, &redeemable_mint),
+        &impostor_vault,
+    );
+    replace_account(&mut ix, &authority, &impostor.pubkey());
+    assert_error(
+        env.send(&[ix], &[&user, &impostor]).await,
+        ErrorCode::UnauthorizedAccess,
+    );
+}
+
+#[tokio::test]
+async fn batch_redeem_rejects_an_overflowing_total() {
+    let mut env = TestEnv::new().await;
+    let first = env.add_type(Some(u64::MAX / 2 + 1)).await;
+    let second = env.add_type(Some(u64::MAX / 2 + 1)).await;
+    let user = env.new_user().await;
+    let first_mint = env.seed_nft(&user.pubkey(), first, 1).await;
+    let second_mint = env.seed_nft(&user.pubkey(), second, 1).await;
+
+    // Each type's reward fits in a u64, their sum does not
+    let ix = batch_ix(&env, &user, &[first_mint, second_mint], 0);
+    assert_error(
+        env.send_redeem(ix, &user).await,
+        ErrorCode::ArithmeticOverflow,
+    );
+}
@@ -0,0 +1,142 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::signature::Signer;
+use trash::{ErrorCode, UserRecord};
+use trash4coin_client::instructions::{self, RedeemExtras};
+use trash4coin_client::pda;
+
+use crate::harness::{assert_error, replace_account, TestEnv};
+
+#[tokio::test]
+async fn referrer_share_is_capped() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    env.send_as_authority(&[instructions::set_referral_config(
+        &authority,
+        &env.nft_config,
+        1_000,
+        15,
+    )])
+    .await
+    .unwrap();
+
+    let referrer = env.new_user().await;
+    let redeemable_mint = env.redeemable_mint;
+    let referrer_redeemable = env
+        .create_token_account(&redeemable_mint, &referrer.pubkey())
+        .await;
+    let user = env.new_user().await;
+    let ix = instructions::set_referrer(&user.pubkey(), &env.nft_config, &referrer.pubkey());
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+    let extras = RedeemExtras {
+        referrer: Some(referrer.pubkey()),
+        ..RedeemExtras::default()
+    };
+    env.redeem(&user, &nft_mint, 1, &extras).await.unwrap();
+    env.redeem(&user, &nft_mint, 1, &extras).await.unwrap();
+
+    // 10% of each 100 reward, stopping at the cap of 15
+    assert_eq!(env.token_balance(&referrer_redeemable).await, 15);
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &redeemable_mint);
+    assert_eq!(env.token_balance(&user_redeemable).await, 200);
+
+    let referrer_record: UserRecord = env
+        .account(&pda::user_record(&env.nft_config, &referrer.pubkey()).0)
+        .await;
+    assert_eq!(referrer_record.referral_earned, 15);
+}
+
+#[tokio::test]
+async fn set_referrer_can_only_be_set_once() {
+    let mut env = TestEnv::new().await;
+    let user = env.new_user().await;
+    let referrer = env.new_user().await;
+
+    // The user's own record doubles as the referrer record, so self-referral never loads
+    let ix = instructions::set_referrer(&user.pubkey(), &env.nft_config, &user.pubkey());
+    assert!(env.send(&[ix], &[&user]).await.is_err());
+
+    let ix = instructions::set_referrer(&user.pubkey(), &env.nft_config, &referrer.pubkey());
+    env.send(std::slice::from_ref(&ix), &[&user]).await.unwrap();
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::ReferrerAlreadySet,
+    );
+}
+
+#[tokio::test]
+async fn redeem_checks_the_referrer_accounts() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+    let referrer = env.new_user().await;
+    let ix = instructions::set_referrer(&user.pubkey(), &env.nft_config, &referrer.pubkey());
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    assert_error(
+        env.redeem(&user, &nft_mint, 1, &RedeemExtras::default())
+            .await,
+        ErrorCode::ReferrerAccountsRequired,
+    );
+
+    // The user's own record in place of the referrer's
+    let redeemable_mint = env.redeemable_mint;
+    env.create_token_account(&redeemable_mint, &referrer.pubkey())
+        .await;
+    let mut ix = env.redeem_ix(
+        &user,
+        &nft_mint,
+        1,
+        &RedeemExtras {
+            referrer: Some(referrer.pubkey()),
+            ..RedeemExtras::default()
+        },
+    );
+    replace_account(
+        &mut ix,
+        &pda::user_record(&env.nft_config, &referrer.pubkey()).0,
+        &pda::user_record(&env.nft_config, &user.pubkey()).0,
+    );
+    assert_error(env.send_redeem(ix, &user).await, ErrorCode::InvalidReferrer);
+}
+
+#[tokio::test]
+async fn referral_payouts_count_toward_epoch_limits() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    env.send_as_authority(&[
+        instructions::set_referral_config(&authority, &env.nft_config, 1_000, 1_000),
+        instructions::set_redemption_limits(&authority, &env.nft_config, 0, 105),
+    ])
+    .await
+    .unwrap();
+
+    let referrer = env.new_user().await;
+    let redeemable_mint = env.redeemable_mint;
+    let referrer_redeemable = env
+        .create_token_account(&redeemable_mint, &referrer.pubkey())
+        .await;
+    let user = env.new_user().await;
+    let ix = instructions::set_referrer(&user.pubkey(), &env.nft_config, &referrer.pubkey());
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+    let extras = RedeemExtras {
+        referrer: Some(referrer.pubkey()),
+        ..RedeemExtras::default()
+    };
+    env.redeem(&user, &nft_mint, 1, &extras).await.unwrap();
+
+    // The 10 referral share is cut to the 5 left under the global limit of 105
+    assert_eq!(env.token_balance(&referrer_redeemable).await, 5);
+    assert_eq!(env.config().await.epoch_rewarded, 105);
+    let referrer_record: UserRecord = env
+        .account(&pda::user_record(&env.nft_config, &referrer.pubkey()).0)
+        .await;
+    assert_eq!(referrer_record.epoch_rewarded, 5);
+    assert_eq!(referrer_record.referral_earned, 5);
+}
@@ -0,0 +1,323 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use trash::{ErrorCode, RecyclingTier, RewardLeg, TierConfig, UserRecord, MAX_REWARD_LEGS};
+use trash4coin_client::{instructions, pda};
+
+use crate::harness::{assert_error, replace_account, TestEnv};
+
+const TOKEN_LEG: u64 = 10;
+const LAMPORT_LEG: u64 = 5_000;
+
+// A type paying one token leg from a funded reward vault and one lamport leg
+async fn setup() -> (TestEnv, Keypair, Pubkey, Pubkey) {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(None).await;
+
+    let reward_mint = env.create_mint(6);
+    let reward_vault = pda::reward_vault(&env.nft_config, &reward_mint).0;
+    env.send_as_authority(&[
+        instructions::create_reward_vault(&authority, &env.nft_config, &reward_mint),
+        instructions::set_reward_legs(
+            &authority,
+            &env.nft_config,
+            index,
+            vec![
+                RewardLeg::Token {
+                    mint: reward_mint,
+                    amount: TOKEN_LEG,
+                },
+                RewardLeg::Lamports {
+                    amount: LAMPORT_LEG,
+                },
+            ],
+        ),
+    ])
+    .await
+    .unwrap();
+    env.mint_to(&reward_mint, &reward_vault, 1_000).await;
+    env.fund(&pda::lamport_vault(&env.nft_config).0).await;
+
+    let user = env.new_user().await;
+    env.create_token_account(&reward_mint, &user.pubkey()).await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+
+    (env, user, nft_mint, reward_mint)
+}
+
+#[tokio::test]
+async fn redeem_multi_pays_every_leg() {
+    let (mut env, user, nft_mint, reward_mint) = setup().await;
+    let lamports_before = env.lamports(&user.pubkey()).await;
+
+    let ix = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &nft_mint,
+        2,
+        vec![2 * TOKEN_LEG, 2 * LAMPORT_LEG],
+        &[reward_mint],
+    );
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    let user_rewards = get_associated_token_address(&user.pubkey(), &reward_mint);
+    assert_eq!(env.token_balance(&user_rewards).await, 2 * TOKEN_LEG);
+    // The user also pays the rent of their new user record
+    let user_record = pda::user_record(&env.nft_config, &user.pubkey()).0;
+    let record_rent = env.lamports(&user_record).await;
+    assert_eq!(
+        env.lamports(&user.pubkey()).await,
+        lamports_before + 2 * LAMPORT_LEG - record_rent
+    );
+    let user_nfts = get_associated_token_address(&user.pubkey(), &nft_mint);
+    assert_eq!(env.token_balance(&user_nfts).await, 0);
+}
+
+#[tokio::test]
+async fn redeem_multi_counts_toward_lifetime_totals_and_tiers() {
+    let (mut env, user, nft_mint, reward_mint) = setup().await;
+    let authority = env.authority.pubkey();
+    let tiers = [
+        TierConfig {
+            units_threshold: 2,
+            boost_bps: 1_000,
+        },
+        TierConfig::default(),
+        TierConfig::default(),
+    ];
+    env.send_as_authority(&[instructions::set_tier_thresholds(
+        &authority,
+        &env.nft_config,
+        tiers,
+    )])
+    .await
+    .unwrap();
+
+    let ix = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &nft_mint,
+        2,
+        vec![2 * TOKEN_LEG, 2 * LAMPORT_LEG],
+        &[reward_mint],
+    );
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    // No leg pays redeemable tokens, so only the units count
+    let user_record: UserRecord = env
+        .account(&pda::user_record(&env.nft_config, &user.pubkey()).0)
+        .await;
+    assert_eq!(user_record.lifetime_units_redeemed, 2);
+    assert_eq!(user_record.lifetime_rewarded, 0);
+    assert_eq!(user_record.redeemed_types_mask, 0b1);
+    assert!(user_record.tier == RecyclingTier::Bronze);
+}
+
+#[tokio::test]
+async fn set_reward_legs_is_validated() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(None).await;
+
+    let legs = vec![RewardLeg::Lamports { amount: 1 }; MAX_REWARD_LEGS + 1];
+    let ix = instructions::set_reward_legs(&authority, &env.nft_config, index, legs);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::TooManyRewardLegs,
+    );
+
+    let legs = vec![RewardLeg::Lamports { amount: 0 }];
+    let ix = instructions::set_reward_legs(&authority, &env.nft_config, index, legs);
+    assert_error(
+        env.send_as_authority(&[ix]).await,
+        ErrorCode::InvalidRewardAmount,
+    );
+}
+
+#[tokio::test]
+async fn redeem_multi_requires_reward_legs() {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 1).await;
+
+    let ix = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &nft_mint,
+        1,
+        vec![],
+        &[],
+    );
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::RewardAmountNotSet,
+    );
+}
+
+#[tokio::test]
+async fn redeem_multi_checks_leg_accounts() {
+    let (mut env, user, nft_mint, reward_mint) = setup().await;
+    let user_rewards = get_associated_token_address(&user.pubkey(), &reward_mint);
+    let reward_vault = pda::reward_vault(&env.nft_config, &reward_mint).0;
+
+    let ix = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &nft_mint,
+        1,
+        vec![0, 0],
+        &[],
+    );
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::MissingRewardLegAccounts,
+    );
+
+    // A token account of the right mint that the config does not own
+    let mut ix = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &nft_mint,
+        1,
+        vec![0, 0],
+        &[reward_mint],
+    );
+    replace_account(&mut ix, &reward_vault, &user_rewards);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::InvalidRewardVault,
+    );
+
+    // Someone else's token account as the destination
+    let other = env.new_user().await;
+    let other_rewards = env
+        .create_token_account(&reward_mint, &other.pubkey())
+        .await;
+    let mut ix = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &nft_mint,
+        1,
+        vec![0, 0],
+        &[reward_mint],
+    );
+    replace_account(&mut ix, &user_rewards, &other_rewards);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::InvalidRewardTokenAccount,
+    );
+}
+
+#[tokio::test]
+async fn redeem_multi_rejects_mints_from_outside_the_program() {
+    let (mut env, user, _, reward_mint) = setup().await;
+
+    // Same name and uri as the configured type, but not the program's mint for it
+    let forged_mint = env.seed_forged_nft(&user.pubkey(), 0, 1);
+    let ix = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &forged_mint,
+        1,
+        vec![0, 0],
+        &[reward_mint],
+    );
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::InvalidNFTMint);
+}
+
+#[tokio::test]
+async fn redeem_multi_checks_min_reward_out() {
+    let (mut env, user, nft_mint, reward_mint) = setup().await;
+
+    let ix = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &nft_mint,
+        1,
+        vec![TOKEN_LEG, LAMPORT_LEG + 1],
+        &[reward_mint],
+    );
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::RewardBelowMinimum,
+    );
+
+    // Quotes for a different set of legs
+    for min_reward_out in [vec![TOKEN_LEG], vec![TOKEN_LEG, LAMPORT_LEG, 0]] {
+        let ix = instructions::redeem_and_burn_nft_multi(
+            &user.pubkey(),
+            &env.nft_config,
+            &nft_mint,
+            1,
+            min_reward_out,
+            &[reward_mint],
+        );
+        assert_error(
+            env.send(&[ix], &[&user]).await,
+            ErrorCode::RewardLegCountMismatch,
+        );
+    }
+}
+
+#[tokio::test]
+async fn redeem_multi_charges_redeemable_legs_to_epoch_limits() {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(None).await;
+
+    let redeemable_mint = pda::redeemable_mint(&env.nft_config).0;
+    let reward_vault = pda::reward_vault(&env.nft_config, &redeemable_mint).0;
+    env.send_as_authority(&[
+        instructions::create_reward_vault(&authority, &env.nft_config, &redeemable_mint),
+        instructions::set_reward_legs(
+            &authority,
+            &env.nft_config,
+            index,
+            vec![RewardLeg::Token {
+                mint: redeemable_mint,
+                amount: TOKEN_LEG,
+            }],
+        ),
+        instructions::set_redemption_limits(&authority, &env.nft_config, 15, 0),
+    ])
+    .await
+    .unwrap();
+    let fund_vault = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &get_associated_token_address(&authority, &redeemable_mint),
+        &reward_vault,
+        &authority,
+        &[],
+        1_000,
+    )
+    .unwrap();
+    env.send_as_authority(&[fund_vault]).await.unwrap();
+
+    let user = env.new_user().await;
+    env.create_token_account(&redeemable_mint, &user.pubkey())
+        .await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+    let redeem_one = instructions::redeem_and_burn_nft_multi(
+        &user.pubkey(),
+        &env.nft_config,
+        &nft_mint,
+        1,
+        vec![TOKEN_LEG],
+        &[redeemable_mint],
+    );
+
+    env.send(std::slice::from_ref(&redeem_one), &[&user])
+        .await
+        .unwrap();
+    assert_error(
+        env.send(&[redeem_one], &[&user]).await,
+        ErrorCode::RedemptionLimitExceeded,
+    );
+    let user_record: UserRecord = env
+        .account(&pda::user_record(&env.nft_config, &user.pubkey()).0)
+        .await;
+    assert_eq!(user_record.epoch_rewarded, TOKEN_LEG);
+}
@@ -0,0 +1,241 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use trash::{ErrorCode, SponsorPool};
+use trash4coin_client::instructions::{self, RedeemBatchExtras, RedeemExtras};
+use trash4coin_client::pda;
+
+use crate::harness::{assert_error, replace_account, TestEnv, CONFIG_ID};
+
+const PAYOUT_RATE: u64 = 5;
+const DEPOSIT: u64 = 500;
+
+struct Sponsored {
+    env: TestEnv,
+    sponsor: Keypair,
+    sponsor_mint: Pubkey,
+    user: Keypair,
+    nft_mint: Pubkey,
+}
+
+impl Sponsored {
+    fn extras(&self) -> RedeemExtras {
+        RedeemExtras {
+            sponsor: Some((self.sponsor.pubkey(), self.sponsor_mint)),
+            ..RedeemExtras::default()
+        }
+    }
+
+    fn sponsor_vault(&self) -> Pubkey {
+        let sponsor_pool = pda::sponsor_pool(
+            &self.env.nft_config,
+            &self.sponsor.pubkey(),
+            &self.sponsor_mint,
+        )
+        .0;
+        pda::sponsor_vault(&sponsor_pool).0
+    }
+}
+
+// A funded sponsor pool paying PAYOUT_RATE per unit of type 0, and a user holding two units
+async fn setup() -> Sponsored {
+    let mut env = TestEnv::new().await;
+    let index = env.add_type(Some(100)).await;
+
+    let sponsor = env.new_user().await;
+    let sponsor_key = sponsor.pubkey();
+    let sponsor_mint = env.create_mint(0);
+    let sponsor_tokens = env.create_token_account(&sponsor_mint, &sponsor_key).await;
+    env.mint_to(&sponsor_mint, &sponsor_tokens, 1_000).await;
+
+    env.send(
+        &[
+            instructions::create_sponsor_pool(&sponsor_key, &env.nft_config, &sponsor_mint),
+            instructions::set_sponsor_payout_rate(
+                &sponsor_key,
+                &env.nft_config,
+                &sponsor_mint,
+                index,
+                PAYOUT_RATE,
+            ),
+            instructions::deposit_sponsor_tokens(
+                &sponsor_key,
+                &env.nft_config,
+                &sponsor_mint,
+                DEPOSIT,
+            ),
+        ],
+        &[&sponsor],
+    )
+    .await
+    .unwrap();
+
+    let user = env.new_user().await;
+    env.create_token_account(&sponsor_mint, &user.pubkey())
+        .await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+
+    Sponsored {
+        env,
+        sponsor,
+        sponsor_mint,
+        user,
+        nft_mint,
+    }
+}
+
+#[tokio::test]
+async fn sponsor_pool_pays_out_on_redeem_and_can_be_withdrawn() {
+    let mut s = setup().await;
+    let sponsor_vault = s.sponsor_vault();
+    assert_eq!(s.env.token_balance(&sponsor_vault).await, DEPOSIT);
+
+    let extras = s.extras();
+    s.env
+        .redeem(&s.user, &s.nft_mint, 2, &extras)
+        .await
+        .unwrap();
+
+    let user_sponsor_tokens = get_associated_token_address(&s.user.pubkey(), &s.sponsor_mint);
+    assert_eq!(
+        s.env.token_balance(&user_sponsor_tokens).await,
+        2 * PAYOUT_RATE
+    );
+    assert_eq!(
+        s.env.token_balance(&sponsor_vault).await,
+        DEPOSIT - 2 * PAYOUT_RATE
+    );
+
+    let sponsor_key = s.sponsor.pubkey();
+    let ix = instructions::withdraw_sponsor_tokens(
+        &sponsor_key,
+        &s.env.nft_config,
+        &s.sponsor_mint,
+        100,
+    );
+    s.env.send(&[ix], &[&s.sponsor]).await.unwrap();
+
+    let sponsor_tokens = get_associated_token_address(&sponsor_key, &s.sponsor_mint);
+    assert_eq!(
+        s.env.token_balance(&sponsor_tokens).await,
+        1_000 - DEPOSIT + 100
+    );
+    let sponsor_pool: SponsorPool = s
+        .env
+        .account(&pda::sponsor_pool(&s.env.nft_config, &sponsor_key, &s.sponsor_mint).0)
+        .await;
+    assert_eq!(sponsor_pool.payout_rates[0], PAYOUT_RATE);
+}
+
+#[tokio::test]
+async fn sponsor_pool_pays_out_for_every_type_in_a_batch() {
+    let mut s = setup().await;
+    let sponsor_vault = s.sponsor_vault();
+    let sponsor_key = s.sponsor.pubkey();
+    let other = s.env.add_type(Some(100)).await;
+    let ix = instructions::set_sponsor_payout_rate(
+        &sponsor_key,
+        &s.env.nft_config,
+        &s.sponsor_mint,
+        other,
+        3,
+    );
+    s.env.send(&[ix], &[&s.sponsor]).await.unwrap();
+    let user_key = s.user.pubkey();
+    let other_mint = s.env.seed_nft(&user_key, other, 4).await;
+
+    let ix = instructions::redeem_and_burn_batch(
+        &user_key,
+        &s.env.authority.pubkey(),
+        &s.env.nft_config,
+        &[s.nft_mint, other_mint],
+        0,
+        &RedeemBatchExtras {
+            sponsor: Some((sponsor_key, s.sponsor_mint)),
+            ..RedeemBatchExtras::default()
+        },
+    );
+    s.env.send_redeem(ix, &s.user).await.unwrap();
+
+    let user_sponsor_tokens = get_associated_token_address(&user_key, &s.sponsor_mint);
+    let payout = 2 * PAYOUT_RATE + 4 * 3;
+    assert_eq!(s.env.token_balance(&user_sponsor_tokens).await, payout);
+    assert_eq!(s.env.token_balance(&sponsor_vault).await, DEPOSIT - payout);
+}
+
+#[tokio::test]
+async fn sponsor_pool_must_belong_to_the_config() {
+    let mut s = setup().await;
+    let authority = s.env.authority.pubkey();
+    let other_config = pda::nft_config(&authority, CONFIG_ID + 1).0;
+    s.env
+        .send_as_authority(&[instructions::initialize(&authority, CONFIG_ID + 1, 10)])
+        .await
+        .unwrap();
+
+    let mut ix = instructions::set_sponsor_payout_rate(
+        &s.sponsor.pubkey(),
+        &s.env.nft_config,
+        &s.sponsor_mint,
+        0,
+        1,
+    );
+    replace_account(&mut ix, &s.env.nft_config, &other_config);
+    assert_error(
+        s.env.send(&[ix], &[&s.sponsor]).await,
+        ErrorCode::SponsorPoolConfigMismatch,
+    );
+}
+
+#[tokio::test]
+async fn redeem_checks_sponsor_accounts() {
+    let mut s = setup().await;
+    let extras = s.extras();
+    let sponsor_vault = s.sponsor_vault();
+    let user_sponsor_tokens = get_associated_token_address(&s.user.pubkey(), &s.sponsor_mint);
+    let sponsor_tokens = get_associated_token_address(&s.sponsor.pubkey(), &s.sponsor_mint);
+
+    let mut ix = s.env.redeem_ix(&s.user, &s.nft_mint, 1, &extras);
+    replace_account(&mut ix, &sponsor_vault, &sponsor_tokens);
+    assert_error(
+        s.env.send_redeem(ix, &s.user).await,
+        ErrorCode::InvalidSponsorVault,
+    );
+
+    let mut ix = s.env.redeem_ix(&s.user, &s.nft_mint, 1, &extras);
+    replace_account(&mut ix, &user_sponsor_tokens, &sponsor_tokens);
+    assert_error(
+        s.env.send_redeem(ix, &s.user).await,
+        ErrorCode::InvalidSponsorTokenAccount,
+    );
+
+    // Passing the program id leaves an optional account out
+    let mut ix = s.env.redeem_ix(&s.user, &s.nft_mint, 1, &extras);
+    replace_account(&mut ix, &user_sponsor_tokens, &trash::ID);
+    assert_error(
+        s.env.send_redeem(ix, &s.user).await,
+        ErrorCode::IncompleteSponsorAccounts,
+    );
+}
+
+#[tokio::test]
+async fn forged_redemptions_cannot_drain_the_pool() {
+    let mut s = setup().await;
+    let sponsor_vault = s.sponsor_vault();
+    let attacker = s.env.new_user().await;
+    let sponsor_mint = s.sponsor_mint;
+    s.env
+        .create_token_account(&sponsor_mint, &attacker.pubkey())
+        .await;
+
+    let extras = s.extras();
+    // A copy of the type's metadata on another mint, paid for out of the attacker's own vault
+    assert!(s
+        .env
+        .forged_redeem(&attacker, 0, 100, &extras)
+        .await
+        .is_err());
+    assert_eq!(s.env.token_balance(&sponsor_vault).await, DEPOSIT);
+    let attacker_sponsor_tokens = get_associated_token_address(&attacker.pubkey(), &sponsor_mint);
+    assert_eq!(s.env.token_balance(&attacker_sponsor_tokens).await, 0);
+}
@@ -0,0 +1,212 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use trash::{ErrorCode, StakePosition};
+use trash4coin_client::instructions::{self, RedeemExtras};
+use trash4coin_client::pda;
+
+use crate::harness::{assert_error, replace_account, TestEnv};
+
+const STAKING_RATE: u64 = 2;
+
+// Type 0 earns STAKING_RATE per unit per second; the user has staked 3 of 5 units
+async fn setup() -> (TestEnv, Keypair, Pubkey) {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    let redeemable_mint = env.redeemable_mint;
+    let reward_vault = pda::reward_vault(&env.nft_config, &redeemable_mint).0;
+    env.send_as_authority(&[
+        instructions::set_staking_reward_rate(&authority, &env.nft_config, index, STAKING_RATE),
+        instructions::create_reward_vault(&authority, &env.nft_config, &redeemable_mint),
+    ])
+    .await
+    .unwrap();
+    env.mint_to(&redeemable_mint, &reward_vault, 10_000).await;
+
+    let user = env.new_user().await;
+    env.create_token_account(&redeemable_mint, &user.pubkey())
+        .await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 5).await;
+    let ix = instructions::stake_nft(&user.pubkey(), &env.nft_config, &nft_mint, 3);
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    (env, user, nft_mint)
+}
+
+fn stake_accounts(env: &TestEnv, user: &Keypair, nft_mint: &Pubkey) -> (Pubkey, Pubkey) {
+    let stake_position = pda::stake_position(&env.nft_config, &user.pubkey(), nft_mint).0;
+    (stake_position, pda::stake_escrow(&stake_position).0)
+}
+
+#[tokio::test]
+async fn stake_claim_and_unstake() {
+    let (mut env, user, nft_mint) = setup().await;
+    let (stake_position, stake_escrow) = stake_accounts(&env, &user, &nft_mint);
+    let user_nfts = get_associated_token_address(&user.pubkey(), &nft_mint);
+    assert_eq!(env.token_balance(&stake_escrow).await, 3);
+    assert_eq!(env.token_balance(&user_nfts).await, 2);
+
+    env.advance_time(10).await;
+    let ix = instructions::claim_staking_rewards(&user.pubkey(), &env.nft_config, &nft_mint);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    assert_eq!(
+        env.token_balance(&user_redeemable).await,
+        3 * STAKING_RATE * 10
+    );
+
+    let ix = instructions::unstake_nft(&user.pubkey(), &env.nft_config, &nft_mint, 1);
+    env.send(&[ix], &[&user]).await.unwrap();
+    assert_eq!(env.token_balance(&user_nfts).await, 3);
+
+    let position: StakePosition = env.account(&stake_position).await;
+    assert_eq!(position.amount, 2);
+    assert_eq!(position.accrued_rewards, 0);
+}
+
+#[tokio::test]
+async fn rate_changes_only_apply_from_when_they_are_made() {
+    let (mut env, user, nft_mint) = setup().await;
+    let authority = env.authority.pubkey();
+
+    env.advance_time(10).await;
+    env.send_as_authority(&[instructions::set_staking_reward_rate(
+        &authority,
+        &env.nft_config,
+        0,
+        5,
+    )])
+    .await
+    .unwrap();
+    env.advance_time(10).await;
+
+    // Ten seconds at the old rate, then ten at the new one
+    let ix = instructions::claim_staking_rewards(&user.pubkey(), &env.nft_config, &nft_mint);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    assert_eq!(
+        env.token_balance(&user_redeemable).await,
+        3 * STAKING_RATE * 10 + 3 * 5 * 10
+    );
+}
+
+#[tokio::test]
+async fn redeem_burns_from_the_stake_escrow() {
+    let (mut env, user, nft_mint) = setup().await;
+    let (_, stake_escrow) = stake_accounts(&env, &user, &nft_mint);
+    let extras = RedeemExtras {
+        from_stake: true,
+        ..RedeemExtras::default()
+    };
+
+    env.redeem(&user, &nft_mint, 2, &extras).await.unwrap();
+
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&stake_escrow).await, 1);
+    assert_eq!(env.token_balance(&user_redeemable).await, 200);
+
+    assert_error(
+        env.redeem(&user, &nft_mint, 2, &extras).await,
+        ErrorCode::InsufficientStakedAmount,
+    );
+}
+
+#[tokio::test]
+async fn unstake_checks_the_position() {
+    let (mut env, user, nft_mint) = setup().await;
+    let (_, stake_escrow) = stake_accounts(&env, &user, &nft_mint);
+
+    let ix = instructions::unstake_nft(&user.pubkey(), &env.nft_config, &nft_mint, 4);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::InsufficientStakedAmount,
+    );
+
+    let user_nfts = get_associated_token_address(&user.pubkey(), &nft_mint);
+    let mut ix = instructions::unstake_nft(&user.pubkey(), &env.nft_config, &nft_mint, 1);
+    replace_account(&mut ix, &stake_escrow, &user_nfts);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::InvalidStakeEscrow,
+    );
+}
+
+#[tokio::test]
+async fn redeem_from_stake_checks_the_stake_accounts() {
+    let (mut env, user, nft_mint) = setup().await;
+    let (stake_position, stake_escrow) = stake_accounts(&env, &user, &nft_mint);
+    let extras = RedeemExtras {
+        from_stake: true,
+        ..RedeemExtras::default()
+    };
+
+    // A position staking another type's mint
+    let other_index = env.add_type(Some(100)).await;
+    let other_mint = env.seed_nft(&user.pubkey(), other_index, 1).await;
+    let ix = instructions::stake_nft(&user.pubkey(), &env.nft_config, &other_mint, 1);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let (other_position, _) = stake_accounts(&env, &user, &other_mint);
+
+    let mut ix = env.redeem_ix(&user, &nft_mint, 1, &extras);
+    replace_account(&mut ix, &stake_position, &other_position);
+    assert_error(
+        env.send_redeem(ix, &user).await,
+        ErrorCode::InvalidStakePosition,
+    );
+
+    let mut ix = env.redeem_ix(&user, &nft_mint, 1, &extras);
+    replace_account(&mut ix, &stake_escrow, &trash::ID);
+    assert_error(
+        env.send_redeem(ix, &user).await,
+        ErrorCode::IncompleteStakeAccounts,
+    );
+}
+
+#[tokio::test]
+async fn claim_staking_rewards_needs_accrued_rewards() {
+    let (mut env, user, nft_mint) = setup().await;
+
+    let ix = instructions::claim_staking_rewards(&user.pubkey(), &env.nft_config, &nft_mint);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::NothingToClaim);
+}
+
+#[tokio::test]
+async fn stake_rejects_mints_from_outside_the_program() {
+    let (mut env, user, _) = setup().await;
+
+    // Same name and uri as type 0, but not the program's mint for it
+    let forged_mint = env.seed_forged_nft(&user.pubkey(), 0, 1);
+    let ix = instructions::stake_nft(&user.pubkey(), &env.nft_config, &forged_mint, 1);
+    assert_error(env.send(&[ix], &[&user]).await, ErrorCode::InvalidNFTMint);
+}
+
+#[tokio::test]
+async fn huge_accruals_stay_claimable_and_unstakeable() {
+    let (mut env, user, nft_mint) = setup().await;
+    let authority = env.authority.pubkey();
+    env.send_as_authority(&[instructions::set_staking_reward_rate(
+        &authority,
+        &env.nft_config,
+        0,
+        u64::MAX,
+    )])
+    .await
+    .unwrap();
+
+    // 3 units at u64::MAX per second overflow u64 within the first second
+    env.advance_time(1_000_000).await;
+    let ix = instructions::claim_staking_rewards(&user.pubkey(), &env.nft_config, &nft_mint);
+    env.send(&[ix], &[&user]).await.unwrap();
+
+    // The claim pays out the whole vault and keeps the rest accrued
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    assert_eq!(env.token_balance(&user_redeemable).await, 10_000);
+
+    let ix = instructions::unstake_nft(&user.pubkey(), &env.nft_config, &nft_mint, 3);
+    env.send(&[ix], &[&user]).await.unwrap();
+    let (stake_position, _) = stake_accounts(&env, &user, &nft_mint);
+    let position: StakePosition = env.account(&stake_position).await;
+    assert_eq!(position.amount, 0);
+    assert_eq!(position.accrued_rewards, u64::MAX - 10_000);
+}
@@ -0,0 +1,114 @@
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use trash::{ErrorCode, VestingPosition, VestingSchedule};
+use trash4coin_client::instructions::{self, RedeemExtras};
+use trash4coin_client::pda;
+
+use crate::harness::{assert_error, replace_account, TestEnv};
+
+const CLIFF: i64 = 100;
+const DURATION: i64 = 1_000;
+
+// Type 0 pays 100 per unit and vests redemptions of at least `min_reward`
+async fn setup(min_reward: u64) -> (TestEnv, Keypair, Pubkey) {
+    let mut env = TestEnv::new().await;
+    let authority = env.authority.pubkey();
+    let index = env.add_type(Some(100)).await;
+    env.send_as_authority(&[instructions::set_vesting_schedule(
+        &authority,
+        &env.nft_config,
+        index,
+        Some(VestingSchedule {
+            min_reward,
+            cliff_seconds: CLIFF,
+            duration_seconds: DURATION,
+        }),
+    )])
+    .await
+    .unwrap();
+
+    let user = env.new_user().await;
+    let nft_mint = env.seed_nft(&user.pubkey(), index, 2).await;
+
+    (env, user, nft_mint)
+}
+
+fn vesting_extras() -> RedeemExtras {
+    RedeemExtras {
+        vesting_position_id: Some(0),
+        ..RedeemExtras::default()
+    }
+}
+
+#[tokio::test]
+async fn vested_rewards_unlock_after_the_cliff() {
+    let (mut env, user, nft_mint) = setup(100).await;
+    env.redeem(&user, &nft_mint, 2, &vesting_extras())
+        .await
+        .unwrap();
+
+    let user_redeemable = get_associated_token_address(&user.pubkey(), &env.redeemable_mint);
+    let vesting_vault = pda::vesting_vault(&env.nft_config, &user.pubkey(), 0).0;
+    assert_eq!(env.token_balance(&user_redeemable).await, 0);
+    assert_eq!(env.token_balance(&vesting_vault).await, 200);
+
+    let claim = instructions::claim_vested(&user.pubkey(), &env.nft_config, 0);
+    assert_error(
+        env.send(std::slice::from_ref(&claim), &[&user]).await,
+        ErrorCode::NothingToClaim,
+    );
+
+    env.advance_time(DURATION / 2).await;
+    env.send(std::slice::from_ref(&claim), &[&user])
+        .await
+        .unwrap();
+    let claimed = env.token_balance(&user_redeemable).await;
+    assert!(claimed > 0 && claimed < 200);
+
+    env.advance_time(DURATION).await;
+    env.send(&[claim], &[&user]).await.unwrap();
+    assert_eq!(env.token_balance(&user_redeemable).await, 200);
+
+    let position: VestingPosition = env
+        .account(&pda::vesting_position(&env.nft_config, &user.pubkey(), 0).0)
+        .await;
+    assert_eq!(position.claimed_amount, position.total_amount);
+}
+
+#[tokio::test]
+async fn vesting_accounts_must_match_the_schedule() {
+    let (mut env, user, nft_mint) = setup(150).await;
+
+    // 200 reaches the threshold, so the reward has to vest
+    assert_error(
+        env.redeem(&user, &nft_mint, 2, &RedeemExtras::default())
+            .await,
+        ErrorCode::VestingAccountsRequired,
+    );
+
+    // 100 does not, so vesting accounts are refused
+    assert_error(
+        env.redeem(&user, &nft_mint, 1, &vesting_extras()).await,
+        ErrorCode::VestingNotApplicable,
+    );
+}
+
+#[tokio::test]
+async fn claim_vested_checks_the_vault() {
+    let (mut env, user, nft_mint) = setup(100).await;
+    env.redeem(&user, &nft_mint, 1, &vesting_extras())
+        .await
+        .unwrap();
+    env.advance_time(DURATION).await;
+
+    let vesting_vault = pda::vesting_vault(&env.nft_config, &user.pubkey(), 0).0;
+    let authority_vault =
+        get_associated_token_address(&env.authority.pubkey(), &env.redeemable_mint);
+    let mut ix = instructions::claim_vested(&user.pubkey(), &env.nft_config, 0);
+    replace_account(&mut ix, &vesting_vault, &authority_vault);
+    assert_error(
+        env.send(&[ix], &[&user]).await,
+        ErrorCode::InvalidVestingVault,
+    );
+}
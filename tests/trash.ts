@@ -1,12 +1,11 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { Trash4coin } from "../target/types/trash4coin";
+import { Trash } from "../target/types/trash";
 import {
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   createMint,
-  createAssociatedTokenAccount,
-  mintTo,
+  getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import {
   PublicKey,
@@ -16,13 +15,21 @@ import {
 } from "@solana/web3.js";
 import { expect } from "chai";
 
+// NFTConfig is zero-copy, so names, symbols and uris are zero-padded byte arrays
+const text = (bytes: number[]) =>
+  Buffer.from(bytes).toString("utf8").replace(/\0+$/, "");
+
 describe("trash4coin", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
 
-  const program = anchor.workspace.Trashy4coin as Program<Trash4coin>;
+  const program = anchor.workspace.Trash as Program<Trash>;
   const authority = Keypair.generate();
   const user = Keypair.generate();
+  const configId = new anchor.BN(1);
+  const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
+    "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+  );
   let nftConfigPda: PublicKey;
   let nftMint: PublicKey;
   let redeemableMint: PublicKey;
@@ -44,13 +51,17 @@ describe("trash4coin", () => {
 
   it("Initializes the program", async () => {
     const [configPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("nft_config")],
+      [
+        Buffer.from("nft-config"),
+        authority.publicKey.toBuffer(),
+        configId.toArrayLike(Buffer, "le", 8),
+      ],
       program.programId
     );
     nftConfigPda = configPda;
 
     await program.methods
-      .initialize(5)
+      .initialize(configId, 5)
       .accounts({
         authority: authority.publicKey,
         nftConfig: nftConfigPda,
@@ -64,8 +75,13 @@ describe("trash4coin", () => {
     expect(nftConfig.authority.toString()).to.equal(
       authority.publicKey.toString()
     );
+    expect(nftConfig.configId.toString()).to.equal("1");
     expect(nftConfig.maxNftTypes).to.equal(5);
-    expect(nftConfig.nftTypes).to.be.empty;
+    expect(nftConfig.version).to.equal(2);
+    expect(nftConfig.legacyAddress).to.equal(0);
+    // Every type slot is allocated up front; only the first nftTypeCount are in use
+    expect(nftConfig.nftTypes).to.have.lengthOf(10);
+    expect(nftConfig.nftTypeCount).to.equal(0);
   });
 
   it("Adds an NFT type", async () => {
@@ -79,10 +95,10 @@ describe("trash4coin", () => {
       .rpc();
 
     const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
-    expect(nftConfig.nftTypes).to.have.lengthOf(1);
-    expect(nftConfig.nftTypes[0].name).to.equal("Test NFT");
-    expect(nftConfig.nftTypes[0].symbol).to.equal("TNFT");
-    expect(nftConfig.nftTypes[0].uri).to.equal("https://example.com/nft");
+    expect(nftConfig.nftTypeCount).to.equal(1);
+    expect(text(nftConfig.nftTypes[0].name)).to.equal("Test NFT");
+    expect(text(nftConfig.nftTypes[0].symbol)).to.equal("TNFT");
+    expect(text(nftConfig.nftTypes[0].uri)).to.equal("https://example.com/nft");
   });
 
   it("Sets reward amount for NFT type", async () => {
@@ -99,18 +115,187 @@ describe("trash4coin", () => {
     expect(nftConfig.nftTypes[0].rewardAmount.toString()).to.equal("100");
   });
 
-  it("Creates redeemable token", async () => {
-    redeemableMint = await createMint(
-      provider.connection,
-      authority,
-      authority.publicKey,
-      null,
-      9
+  it("Sets reward legs for NFT type", async () => {
+    await program.methods
+      .setRewardLegs(0, [{ lamports: { amount: new anchor.BN(1000) } }])
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
+    const nftType = nftConfig.nftTypes[0];
+    expect(nftType.rewardLegCount).to.equal(1);
+    // Kind 1 is a lamport leg
+    expect(nftType.rewardLegs[0].kind).to.equal(1);
+    expect(nftType.rewardLegs[0].amount.toString()).to.equal("1000");
+  });
+
+  it("Sets redemption limits", async () => {
+    await program.methods
+      .setRedemptionLimits(new anchor.BN(500), new anchor.BN(10000))
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
+    expect(nftConfig.maxRewardPerUserPerEpoch.toString()).to.equal("500");
+    expect(nftConfig.maxRewardPerEpoch.toString()).to.equal("10000");
+  });
+
+  it("Sets a vesting schedule for NFT type", async () => {
+    await program.methods
+      .setVestingSchedule(0, {
+        minReward: new anchor.BN(1_000_000),
+        cliffSeconds: new anchor.BN(7 * 24 * 60 * 60),
+        durationSeconds: new anchor.BN(90 * 24 * 60 * 60),
+      })
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
+    const vesting = nftConfig.nftTypes[0].vesting;
+    expect(vesting.minReward.toString()).to.equal("1000000");
+    expect(vesting.durationSeconds.toString()).to.equal(
+      (90 * 24 * 60 * 60).toString()
     );
+  });
 
-    authorityRedeemableTokenAccount = await createAssociatedTokenAccount(
-      provider.connection,
-      authority,
+  it("Sets staking reward rate for NFT type", async () => {
+    await program.methods
+      .setStakingRewardRate(0, new anchor.BN(2))
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
+    expect(nftConfig.nftTypes[0].stakingRewardRate.toString()).to.equal("2");
+  });
+
+  it("Sets a reward curve for NFT type", async () => {
+    await program.methods
+      .setRewardCurve(0, {
+        mode: { linear: {} },
+        referenceBalance: new anchor.BN(1000000000),
+        minMultiplierBps: 5000,
+        exponent: 1,
+      })
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
+    const rewardCurve = nftConfig.nftTypes[0].rewardCurve;
+    expect(rewardCurve.referenceBalance.toString()).to.equal("1000000000");
+    expect(rewardCurve.minMultiplierBps).to.equal(5000);
+    // CurveMode is stored as its variant index
+    expect(rewardCurve.mode).to.equal(0);
+  });
+
+  it("Sets tier thresholds", async () => {
+    await program.methods
+      .setTierThresholds([
+        { unitsThreshold: new anchor.BN(10), boostBps: 500 },
+        { unitsThreshold: new anchor.BN(100), boostBps: 1000 },
+        { unitsThreshold: new anchor.BN(1000), boostBps: 2000 },
+      ])
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
+    expect(nftConfig.tiers[2].boostBps).to.equal(2000);
+  });
+
+  it("Creates an achievement", async () => {
+    const achievementId = new anchor.BN(1);
+    const [achievementPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("achievement"),
+        nftConfigPda.toBuffer(),
+        achievementId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .createAchievement(
+        achievementId,
+        { lifetimeUnits: { threshold: new anchor.BN(100) } },
+        "First 100 kg",
+        "T4C100",
+        "https://example.com/badges/100kg"
+      )
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+        achievement: achievementPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const achievement = await program.account.achievement.fetch(
+      achievementPda
+    );
+    expect(achievement.name).to.equal("First 100 kg");
+    expect(achievement.claims.toString()).to.equal("0");
+  });
+
+  it("Sets referral config", async () => {
+    await program.methods
+      .setReferralConfig(500, new anchor.BN(1_000_000))
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
+    expect(nftConfig.referralBps).to.equal(500);
+    expect(nftConfig.referralCap.toString()).to.equal("1000000");
+  });
+
+  it("Sets streak bonus", async () => {
+    await program.methods
+      .setStreakBonus(100, 1000)
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const nftConfig = await program.account.nftConfig.fetch(nftConfigPda);
+    expect(nftConfig.streakBonusBpsPerDay).to.equal(100);
+    expect(nftConfig.maxStreakBonusBps).to.equal(1000);
+  });
+
+  it("Creates redeemable token", async () => {
+    [redeemableMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("redeemable-mint"), nftConfigPda.toBuffer()],
+      program.programId
+    );
+    authorityRedeemableTokenAccount = getAssociatedTokenAddressSync(
       redeemableMint,
       authority.publicKey
     );
@@ -137,28 +322,28 @@ describe("trash4coin", () => {
   });
 
   it("Mints an NFT", async () => {
-    nftMint = Keypair.generate().publicKey;
+    [nftMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("nft-mint"), nftConfigPda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
     const metadataAddress = PublicKey.findProgramAddressSync(
       [
         Buffer.from("metadata"),
-        TOKEN_PROGRAM_ID.toBuffer(),
+        TOKEN_METADATA_PROGRAM_ID.toBuffer(),
         nftMint.toBuffer(),
       ],
-      program.programId
+      TOKEN_METADATA_PROGRAM_ID
     )[0];
     const masterEditionAddress = PublicKey.findProgramAddressSync(
       [
         Buffer.from("metadata"),
-        TOKEN_PROGRAM_ID.toBuffer(),
+        TOKEN_METADATA_PROGRAM_ID.toBuffer(),
         nftMint.toBuffer(),
         Buffer.from("edition"),
       ],
-      program.programId
+      TOKEN_METADATA_PROGRAM_ID
     )[0];
-
-    userNftTokenAccount = await createAssociatedTokenAccount(
-      provider.connection,
-      user,
+    userNftTokenAccount = getAssociatedTokenAddressSync(
       nftMint,
       user.publicKey
     );
@@ -176,7 +361,15 @@ describe("trash4coin", () => {
         systemProgram: SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-        tokenMetadataProgram: TOKEN_PROGRAM_ID, // This should be the actual Metaplex Token Metadata Program ID
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        userRecord: PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("user-record"),
+            nftConfigPda.toBuffer(),
+            user.publicKey.toBuffer(),
+          ],
+          program.programId
+        )[0],
       })
       .signers([user])
       .rpc();
@@ -187,10 +380,95 @@ describe("trash4coin", () => {
     expect(tokenAccount.value.uiAmount).to.equal(1);
   });
 
-  it("Redeems and burns NFT", async () => {
-    userRedeemableTokenAccount = await createAssociatedTokenAccount(
+  it("Creates a campaign", async () => {
+    const campaignId = new anchor.BN(1);
+    const [campaignPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("campaign"),
+        nftConfigPda.toBuffer(),
+        campaignId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const now = Math.floor(Date.now() / 1000);
+
+    await program.methods
+      .createCampaign(
+        campaignId,
+        new anchor.BN(now),
+        new anchor.BN(now + 30 * 24 * 60 * 60),
+        Buffer.from([0]),
+        15000,
+        new anchor.BN(1000)
+      )
+      .accounts({
+        authority: authority.publicKey,
+        nftConfig: nftConfigPda,
+        campaign: campaignPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const campaign = await program.account.campaign.fetch(campaignPda);
+    expect(campaign.multiplierBps).to.equal(15000);
+    expect(campaign.remainingBudget.toString()).to.equal("1000");
+  });
+
+  it("Creates a sponsor pool and sets a payout rate", async () => {
+    const sponsorMint = await createMint(
       provider.connection,
-      user,
+      authority,
+      authority.publicKey,
+      null,
+      6
+    );
+    const [sponsorPoolPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("sponsor-pool"),
+        nftConfigPda.toBuffer(),
+        authority.publicKey.toBuffer(),
+        sponsorMint.toBuffer(),
+      ],
+      program.programId
+    );
+    const [sponsorVaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("sponsor-vault"), sponsorPoolPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createSponsorPool()
+      .accounts({
+        sponsor: authority.publicKey,
+        nftConfig: nftConfigPda,
+        sponsorMint,
+        sponsorPool: sponsorPoolPda,
+        sponsorVault: sponsorVaultPda,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .setSponsorPayoutRate(0, new anchor.BN(50))
+      .accounts({
+        sponsor: authority.publicKey,
+        nftConfig: nftConfigPda,
+        sponsorPool: sponsorPoolPda,
+      })
+      .signers([authority])
+      .rpc();
+
+    const sponsorPool = await program.account.sponsorPool.fetch(sponsorPoolPda);
+    expect(sponsorPool.vault.toString()).to.equal(sponsorVaultPda.toString());
+    expect(sponsorPool.payoutRates[0].toString()).to.equal("50");
+  });
+
+  it("Redeems and burns NFT", async () => {
+    userRedeemableTokenAccount = getAssociatedTokenAddressSync(
       redeemableMint,
       user.publicKey
     );
@@ -198,14 +476,18 @@ describe("trash4coin", () => {
     const metadataAddress = PublicKey.findProgramAddressSync(
       [
         Buffer.from("metadata"),
-        TOKEN_PROGRAM_ID.toBuffer(),
+        TOKEN_METADATA_PROGRAM_ID.toBuffer(),
         nftMint.toBuffer(),
       ],
-      program.programId
+      TOKEN_METADATA_PROGRAM_ID
     )[0];
 
     await program.methods
-      .redeemAndBurnNft(new anchor.BN(1))
+      .redeemAndBurnNft(
+        new anchor.BN(1),
+        new anchor.BN(100),
+        new anchor.BN(100)
+      )
       .accounts({
         user: user.publicKey,
         nftMint: nftMint,
@@ -216,12 +498,31 @@ describe("trash4coin", () => {
         authority: authority.publicKey,
         metadata: metadataAddress,
         nftConfig: nftConfigPda,
+        userRecord: PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("user-record"),
+            nftConfigPda.toBuffer(),
+            user.publicKey.toBuffer(),
+          ],
+          program.programId
+        )[0],
+        vestingPosition: null,
+        vestingVault: null,
+        stakePosition: null,
+        stakeEscrow: null,
+        campaign: null,
+        sponsorPool: null,
+        sponsorVault: null,
+        userSponsorTokenAccount: null,
+        referrerRecord: null,
+        referrerTokenAccount: null,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: SYSVAR_RENT_PUBKEY,
       })
-      .signers([user])
+      // The reward comes out of the authority's token account, so it co-signs
+      .signers([user, authority])
       .rpc();
 
     const nftTokenAccount = await provider.connection.getTokenAccountBalance(
@@ -233,6 +534,7 @@ describe("trash4coin", () => {
       await provider.connection.getTokenAccountBalance(
         userRedeemableTokenAccount
       );
-    expect(userRedeemableBalance.value.uiAmount).to.equal(100 / 1e9); // 100 / 1e9 because the reward amount is 100 and decimals is 9
+    // 100 base reward plus a 1% one-day streak bonus, with 9 decimals
+    expect(userRedeemableBalance.value.uiAmount).to.equal(101 / 1e9);
   });
 });